@@ -1,3 +1,5 @@
+import 'dart:typed_data';
+
 import 'package:dust/dust.dart';
 import 'package:flutter/foundation.dart';
 import 'package:flutter_test/flutter_test.dart';
@@ -8,6 +10,25 @@ part 'integration_test.dust.dart';
 
 const int kIntMin = -9223372036854775808;
 
+class PlainEventSerializer extends CompressedSerializer<String> {
+  const PlainEventSerializer();
+
+  @override
+  Serializer<String> get inner => const StringSerializer();
+}
+
+class EventSerializer extends PlainEventSerializer {
+  const EventSerializer();
+
+  @override
+  List<int> get dictionary => kEventDictionary;
+}
+
+final kEventDictionary = CompressedSerializer.trainDictionary([
+  for (var i = 0; i < 8; i++)
+    '{"kind":"click","target":"button-$i","timestamp":17000000$i}'.codeUnits,
+]);
+
 @Model()
 abstract class Trivial with _$Trivial {
   Trivial._();
@@ -15,13 +36,15 @@ abstract class Trivial with _$Trivial {
   factory Trivial() = _Trivial;
 }
 
+typedef Tag = String;
+
 @Model()
 abstract class Something with _$Something {
   Something._();
 
   factory Something({
     required String atomOne,
-    String? atomTwo,
+    Tag? atomTwo,
     @Ln() required Trivial linkOne,
     @Ln() Trivial? linkTwo,
     @Ln() List<Something> linkThree,
@@ -31,6 +54,58 @@ abstract class Something with _$Something {
   int someNonPersistentField = 0;
 }
 
+@Model(timestamps: true)
+abstract class Stamped with _$Stamped {
+  Stamped._();
+
+  factory Stamped({@Display() required String name}) = _Stamped;
+}
+
+@Model()
+abstract class Flagged with _$Flagged {
+  Flagged._();
+
+  factory Flagged({@Dft(false) bool done}) = _Flagged;
+}
+
+@Model(encapsulate: true)
+abstract class Counter with _$Counter {
+  Counter._();
+
+  factory Counter({@Dft(0) int count}) = _Counter;
+
+  void increment() => _count$.set(_count$.peek() + 1);
+}
+
+@Model()
+abstract class Board with _$Board {
+  Board._();
+
+  factory Board({
+    @Ln() @Ordered() List<Trivial> columns,
+    @Ln() @Cascade() List<Board> children,
+  }) = _Board;
+}
+
+@Model(idFrom: ['email'])
+abstract class Account with _$Account {
+  Account._();
+
+  factory Account({required String email, String? nickname}) = _Account;
+
+  late final displayName$ = Reactive((o) => nickname$.get(o) ?? email$.get(o));
+}
+
+@Model()
+abstract class Migrated with _$Migrated {
+  Migrated._();
+
+  factory Migrated({
+    @Bindings(readonly: true) required String name,
+    @Bindings(hidden: true) @AddedIn(2, 'none') required String note,
+  }) = _Migrated;
+}
+
 /// These tests must be run with native binaries bundled alongside.
 /// This can be done with `flutter test integration_test`.
 void main() {
@@ -42,6 +117,12 @@ void main() {
       Dust.open('${dir.path}/data.sqlite3', [
         const $TrivialRepository(),
         const $SomethingRepository(),
+        const $StampedRepository(),
+        const $FlaggedRepository(),
+        const $CounterRepository(),
+        const $BoardRepository(),
+        const $AccountRepository(),
+        const $MigratedRepository(),
       ]);
     });
 
@@ -128,6 +209,779 @@ void main() {
       assert(const $SomethingRepository().get(somethingElse.id).peek() == null);
     });
 
+    test('object_store_initial_multilinks', () {
+      final trivial = Trivial();
+      final targets = [
+        for (var i = 0; i < 3; i++) Something(atomOne: '$i', linkOne: trivial),
+      ];
+      final something =
+          Something(atomOne: 'test', linkOne: trivial, linkThree: targets);
+      final linked = something.linkThree$.peek();
+      assert(linked.length == 3);
+      assert(linked.toSet().containsAll(targets));
+      for (final target in targets) {
+        assert(target.backlink$.peek().single == something);
+      }
+    });
+
+    test('object_store_timestamps', () {
+      var now = 1000;
+      final clock = Dust.clock;
+      Dust.clock = () => now;
+      final stamped = Stamped(name: 'a');
+      assert(stamped.createdAt$.peek() == 1000);
+      assert(stamped.updatedAt$.peek() == 1000);
+      now = 2000;
+      stamped.name$.set('b');
+      assert(stamped.createdAt$.peek() == 1000);
+      assert(stamped.updatedAt$.peek() == 2000);
+      now = 3000;
+      stamped.name$.set('c');
+      assert(stamped.createdAt$.peek() == 1000);
+      assert(stamped.updatedAt$.peek() == 3000);
+      Dust.clock = clock;
+    });
+
+    test('object_store_display', () {
+      final stamped = Stamped(name: 'a');
+      assert('$stamped' == 'a');
+      stamped.name$.set('b');
+      assert(stamped.toString() == 'b');
+    });
+
+    test('object_store_subscribe', () {
+      final stamped = Stamped(name: 'a');
+      final names = <String>[];
+      final subscription = stamped.subscribe((s) => names.add(s.name$.peek()));
+      stamped.name$.set('b');
+      assert(names.length == 1 && names.last == 'b');
+      subscription.cancel();
+      stamped.name$.set('c');
+      assert(names.length == 1);
+    });
+
+    test('object_store_type_alias', () {
+      final something = Something(atomOne: 'a', linkOne: Trivial());
+      assert(something.atomTwo$.get(null) == null);
+      const Tag tag = 'b';
+      something.atomTwo$.set(tag);
+      assert(something.atomTwo$.get(null) == 'b');
+    });
+
+    test('object_store_multilinks_extend', () {
+      final something = Something(atomOne: 'a', linkOne: Trivial());
+      final others = [
+        for (var i = 0; i < 100; i++)
+          Something(atomOne: '$i', linkOne: Trivial())
+      ];
+      var notified = 0;
+      final subscription = Subscription(
+          (o) => something.linkThree$.connect(o), () => notified++);
+      something.linkThree$.extend(others);
+      assert(notified == 1);
+      assert(something.linkThree$.get(null).length == 100);
+      assert(others.every((e) => e.backlink$.get(null).contains(something)));
+      subscription.cancel();
+    });
+
+    test('object_store_canonical_map', () {
+      const serializer = MapSerializer(StringSerializer(), Int64Serializer());
+      Uint8List serialize(Map<String, int> map) {
+        final builder = BytesBuilder();
+        serializer.serialize(map, builder);
+        return builder.takeBytes();
+      }
+
+      final bytes = serialize({'a': 1, 'b': 2, 'c': 3});
+      assert(listEquals(bytes, serialize({'c': 3, 'a': 1, 'b': 2})));
+      final map = serializer.deserialize(BytesReader(bytes.buffer.asByteData()));
+      assert(mapEquals(map, {'a': 1, 'b': 2, 'c': 3}));
+    });
+
+    test('object_store_compressed', () {
+      Uint8List serialize(Serializer<String> serializer, String value) {
+        final builder = BytesBuilder();
+        serializer.serialize(value, builder);
+        return builder.takeBytes();
+      }
+
+      const value =
+          '{"kind":"click","target":"button-42","timestamp":170000042}';
+      final bytes = serialize(const EventSerializer(), value);
+      final plain = serialize(const PlainEventSerializer(), value);
+      assert(bytes.length < plain.length);
+      final reader = BytesReader(bytes.buffer.asByteData());
+      assert(const EventSerializer().deserialize(reader) == value);
+    });
+
+    test('object_store_varint', () {
+      Uint8List serialize(Serializer<int> serializer, int value) {
+        final builder = BytesBuilder();
+        serializer.serialize(value, builder);
+        return builder.takeBytes();
+      }
+
+      for (final value in [0, 1, -1, 63, -64, 300, kIntMin, -kIntMin - 1]) {
+        final bytes = serialize(const VarIntSerializer(), value);
+        final reader = BytesReader(bytes.buffer.asByteData());
+        assert(const VarIntSerializer().deserialize(reader) == value);
+      }
+      assert(listEquals(serialize(const VarIntSerializer(), -1), [1]));
+      assert(listEquals(serialize(const VarIntSerializer(), 300), [216, 4]));
+      assert(serialize(const IntSerializer(), 300).length == 8);
+      assert(serialize(const VarIntSerializer(), kIntMin).length == 10);
+    });
+
+    test('object_store_backlinks_any', () {
+      const otherLabel = 42;
+      final target = Something(atomOne: 'target', linkOne: Trivial());
+      final source = Something(atomOne: 'source', linkOne: Trivial());
+      final backlinks = Backlinks<Something>.any(
+          target.id,
+          [$SomethingRepository.linkThreeLabel, otherLabel],
+          const $SomethingRepository());
+      source.linkThree$.insert(target);
+      Dust.instance
+          .setEdge(Dust.instance.randomId(), (source.id, otherLabel, target.id));
+      Dust.instance.barrier();
+      assert(backlinks.get(null).length == 1);
+      assert(backlinks.get(null).single == source);
+    });
+
+    test('object_store_missing_fields', () {
+      const repository = $SomethingRepository();
+      final id = Dust.instance.randomId();
+      Dust.instance.setNode(id, $SomethingRepository.Label);
+      Dust.instance.setAtom(id ^ $SomethingRepository.atomOneLabel, (
+        id,
+        $SomethingRepository.atomOneLabel,
+        'partial',
+        $SomethingRepository.atomOneSerializer,
+      ));
+      Dust.instance.barrier();
+      assert(listEquals(
+          repository.missingFields(id), [$SomethingField.linkOne]));
+      final complete = Something(atomOne: 'complete', linkOne: Trivial());
+      assert(repository.missingFields(complete.id).isEmpty);
+    });
+
+    test('object_store_cluster_edge_ids', () {
+      Dust.instance.clusterEdgeIds = true;
+      final something = Something(atomOne: 'a', linkOne: Trivial());
+      something.linkThree$.extend([
+        for (var i = 0; i < 100; i++)
+          Something(atomOne: '$i', linkOne: Trivial())
+      ]);
+      Dust.instance.clusterEdgeIds = false;
+      final ids = <Id>{};
+      Dust.instance.getEdgeDstBySrcLabel(something.id,
+          $SomethingRepository.linkThreeLabel, (id, _) => ids.add(id));
+      assert(ids.length == 100);
+      assert(ids.every((id) => id.high == something.id.high));
+      assert(something.linkThree$.get(null).length == 100);
+    });
+
+    test('object_store_encapsulate', () {
+      final counter = Counter();
+      assert(counter.count$.peek() == 0);
+      counter.increment();
+      counter.increment();
+      assert(counter.count$.peek() == 2);
+    });
+
+    test('object_store_clock', () {
+      final something = Something(atomOne: 'a', linkOne: Trivial());
+      assert(something.linkTwo$.clock() == null);
+      final atom = something.atomOne$.clock()!;
+      final link = something.linkOne$.clock()!;
+      something.atomOne$.set('b');
+      something.linkOne$.set(Trivial());
+      assert(something.atomOne$.clock()! > atom);
+      assert(something.linkOne$.clock()! > link);
+    });
+
+    test('object_store_node_label_cache', () {
+      Dust.instance.enableNodeLabelCache();
+      int count() {
+        var res = 0;
+        Dust.instance.getNodeByLabel($TrivialRepository.Label, (_) => res++);
+        return res;
+      }
+
+      Dust.instance.barrier();
+      final before = count();
+      assert(count() == before);
+      final trivial = Trivial();
+      assert(count() == before + 1);
+      Dust.instance.barrier();
+      assert(count() == before + 1);
+      trivial.delete();
+      Dust.instance.barrier();
+      assert(count() == before);
+    });
+
+    test('object_store_id_collision', () {
+      final trivial = Trivial();
+      final auto = const $SomethingRepository()
+          .auto(trivial.id, atomOne: 'a', linkOne: Trivial());
+      var rejected = false;
+      try {
+        auto.get(null);
+      } on IdCollisionException catch (e) {
+        rejected = e.id == trivial.id;
+      }
+      assert(rejected);
+      assert(const $TrivialRepository().get(trivial.id).get(null) != null);
+      assert(const $SomethingRepository().get(trivial.id).get(null) == null);
+    });
+
+    test('object_store_ordered_multilinks', () {
+      final a = Trivial(), b = Trivial(), c = Trivial(), d = Trivial();
+      final board = Board(columns: [a, b]);
+      board.columns$.extend([c]);
+      board.columns$.insert(d);
+      assert(listEquals(board.columns$.get(null), [a, b, c, d]));
+      board.columns$.moveBefore(d, a);
+      assert(listEquals(board.columns$.get(null), [d, a, b, c]));
+      board.columns$.moveAfter(a, c);
+      assert(listEquals(board.columns$.get(null), [d, b, c, a]));
+      board.columns$.moveBefore(c, b);
+      assert(listEquals(board.columns$.get(null), [d, c, b, a]));
+      board.columns$.remove(b);
+      assert(listEquals(board.columns$.get(null), [d, c, a]));
+      for (final (value, anchor) in [(b, a), (a, b), (a, a)]) {
+        var rejected = false;
+        try {
+          board.columns$.moveBefore(value, anchor);
+        } on ArgumentError {
+          rejected = true;
+        }
+        assert(rejected);
+      }
+      assert(listEquals(board.columns$.get(null), [d, c, a]));
+    });
+
+    test('object_store_id_from', () {
+      int count() {
+        var res = 0;
+        Dust.instance.getNodeByLabel($AccountRepository.Label, (_) => res++);
+        return res;
+      }
+
+      final before = count();
+      final first = Account(email: 'someone@example.com');
+      final second = Account(email: 'someone@example.com', nickname: 'me');
+      assert(first.id == second.id);
+      assert(first.nickname$.get(null) == 'me');
+      assert(count() == before + 1);
+      assert(Account(email: 'other@example.com').id != first.id);
+    });
+
+    test('object_store_link_type_mismatch', () {
+      final something = Something(atomOne: 'a', linkOne: Trivial());
+      final other = Something(atomOne: 'b', linkOne: Trivial());
+      for (final label in [
+        $SomethingRepository.linkOneLabel,
+        $SomethingRepository.linkTwoLabel,
+      ]) {
+        Dust.instance
+            .setEdge(something.id ^ label, (something.id, label, other.id));
+      }
+      Dust.instance.barrier();
+      for (final get in [
+        () => something.linkOne$.get(null),
+        () => something.linkTwo$.get(null),
+      ]) {
+        var mismatch = false;
+        try {
+          get();
+        } on LinkTypeMismatchException catch (e) {
+          mismatch = e.dst == other.id &&
+              e.expected == $TrivialRepository.Label &&
+              e.actual == $SomethingRepository.Label;
+        }
+        assert(mismatch);
+      }
+    });
+
+    test('object_store_create_batch', () {
+      final trivial = Trivial();
+      final observed = Something(atomOne: 'observed', linkOne: trivial);
+      var notified = 0;
+      final subscription = observed.subscribe((_) => notified++);
+      final ids = Dust.instance.batch(() {
+        final ids = const $SomethingRepository().createBatch([
+          for (var i = 0; i < 3; i++)
+            (
+              atomOne: 'item $i',
+              atomTwo: null,
+              linkOne: trivial,
+              linkTwo: null,
+              linkThree: [observed],
+            ),
+        ]);
+        observed.atomOne$.set('changed');
+        assert(notified == 0);
+        return ids;
+      });
+      assert(notified > 0);
+      subscription.cancel();
+      assert(ids.length == 3 && ids.toSet().length == 3);
+      for (var i = 0; i < 3; i++) {
+        final item = const $SomethingRepository().get(ids[i]).peek()!;
+        assert(item.atomOne$.peek() == 'item $i');
+        assert(item.linkOne$.peek().id == trivial.id);
+        assert(item.linkThree$.peek().single.id == observed.id);
+      }
+      assert(observed.backlink$.peek().length == 3);
+    });
+
+    test('object_store_cached_read', () {
+      final something = Something(atomOne: 'cached', linkOne: Trivial());
+      final first = something.atomOne$.get(null);
+      assert(identical(first, something.atomOne$.get(null)));
+      assert(identical(first, something.atomOne$.peek()));
+      something.atomOne$.set('changed');
+      assert(!identical(first, something.atomOne$.get(null)));
+    });
+
+    test('object_store_debounced', () async {
+      final something = Something(atomOne: 'a', linkOne: Trivial());
+      var writes = 0;
+      final subscription = something.subscribe((_) => writes++);
+      final debounced =
+          something.atomOne$.debounced(const Duration(milliseconds: 50));
+      for (var i = 0; i < 100; i++) {
+        debounced.set('value $i');
+      }
+      assert(debounced.peek() == 'value 99');
+      assert(something.atomOne$.peek() == 'a');
+      assert(writes == 0);
+      debounced.flush();
+      assert(!debounced.isPending && writes == 1);
+      assert(something.atomOne$.peek() == 'value 99');
+
+      for (var i = 0; i < 100; i++) {
+        debounced.set('again $i');
+      }
+      await Future.delayed(const Duration(milliseconds: 100));
+      assert(writes == 2);
+      assert(something.atomOne$.peek() == 'again 99');
+      subscription.cancel();
+    });
+
+    test('object_store_added_in', () {
+      // Simulates an object written before `note` was added to the schema.
+      final id = Dust.instance.randomId();
+      Dust.instance.setNode(id, $MigratedRepository.Label);
+      Dust.instance.setAtom(id ^ $MigratedRepository.nameLabel, (
+        id,
+        $MigratedRepository.nameLabel,
+        'old',
+        $MigratedRepository.nameSerializer,
+      ));
+      Dust.instance.barrier();
+      final old = const $MigratedRepository().get(id).peek()!;
+      assert(old.name$.peek() == 'old');
+      assert(old.note$.peek() == 'none');
+      assert(const $MigratedRepository().missingFields(id).isEmpty);
+      old.note$.set('set later');
+      assert(old.note$.peek() == 'set later');
+      assert(Migrated(name: 'new', note: 'given').note$.peek() == 'given');
+    });
+
+    test('object_store_referrers', () {
+      final trivial = Trivial();
+      final referrers = Referrers(trivial.id);
+      assert(referrers.peek().isEmpty);
+      final first = Something(atomOne: 'a', linkOne: trivial);
+      final second =
+          Something(atomOne: 'b', linkOne: trivial, linkTwo: trivial);
+      final board = Board(columns: [trivial]);
+      final grouped = referrers.peek();
+      assert(grouped.length == 2);
+      assert(grouped[$SomethingRepository.Label]!.length == 2);
+      assert(grouped[$SomethingRepository.Label]!.contains(first.id));
+      assert(grouped[$SomethingRepository.Label]!.contains(second.id));
+      assert(grouped[$BoardRepository.Label]!.single == board.id);
+      board.delete();
+      assert(!referrers.peek().containsKey($BoardRepository.Label));
+    });
+
+    test('object_store_create_lazy', () {
+      final created = const $FlaggedRepository().createLazy(done: true);
+      assert(!created.isResolved);
+      assert(const $FlaggedRepository().missingFields(created.id).isEmpty);
+      assert(created.model.done$.peek());
+      assert(created.isResolved);
+      assert(identical(created.model, created.model));
+      assert(created.model.id == created.id);
+    });
+
+    test('object_store_computed', () {
+      final account = Account(email: 'computed@example.com');
+      assert(account.displayName$.peek() == 'computed@example.com');
+      account.nickname$.set('computed');
+      assert(account.displayName$.peek() == 'computed');
+      account.nickname$.set(null);
+      assert(account.displayName$.peek() == 'computed@example.com');
+      assert($AccountField.values.length == 2);
+    });
+
+    test('object_store_sensitive', () {
+      final trivial = Trivial();
+      final token = AtomOption<String>(trivial.id ^ 2333, trivial.id,
+          2333, const StringSerializer(),
+          sensitive: true);
+      token.set('secret');
+      assert(token.peek() == 'secret');
+      Dust.instance.getAtomById(token.id, (slv) {
+        assert(const StringSerializer().deserialize(BytesReader(slv!.$3)) ==
+            'secret');
+      });
+      token.set(null);
+      assert(token.peek() == null);
+    });
+
+    test('object_store_lazy_atom', () {
+      final trivial = Trivial();
+      const label = 2334;
+      Dust.instance.setAtom<String>(trivial.id ^ label,
+          (trivial.id, label, 'body', const StringSerializer()));
+      Dust.instance.barrier();
+      final reads = Dust.instance.atomReads;
+      final body = Atom<String>(
+          trivial.id ^ label, trivial.id, label, const StringSerializer(),
+          lazy: true);
+      assert(Dust.instance.atomReads == reads);
+      assert(body.peek() == 'body');
+      assert(Dust.instance.atomReads == reads + 1);
+      assert(body.peek() == 'body');
+      assert(Dust.instance.atomReads == reads + 1);
+    });
+
+    test('object_store_get_or_create', () {
+      int count() {
+        var res = 0;
+        Dust.instance.getNodeByLabel($StampedRepository.Label, (_) => res++);
+        return res;
+      }
+
+      final before = count();
+      final id = Dust.instance.randomId();
+      final first = const $StampedRepository().getOrCreate(id, name: 'first');
+      final second = const $StampedRepository().getOrCreate(id, name: 'second');
+      assert(identical(first, second));
+      assert(second.name$.peek() == 'first');
+      assert(count() == before + 1);
+    });
+
+    test('object_store_change_kind', () {
+      final nodes = <(Id, ChangeKind)>[];
+      final atoms = <(Id, ChangeKind)>[];
+      final owner = Object();
+      Dust.instance.subscribeChangeByLabel($FlaggedRepository.Label,
+          (id, kind) => nodes.add((id, kind)), owner);
+      Dust.instance.subscribeChangeByLabel($FlaggedRepository.doneLabel,
+          (id, kind) => atoms.add((id, kind)), owner);
+      final flagged = Flagged();
+      assert(nodes.single == (flagged.id, ChangeKind.created));
+      assert(atoms.single == (flagged.done$.id, ChangeKind.created));
+      flagged.done$.set(true);
+      assert(atoms.last == (flagged.done$.id, ChangeKind.updated));
+      flagged.delete();
+      assert(nodes.last == (flagged.id, ChangeKind.deleted));
+      assert(atoms.last == (flagged.done$.id, ChangeKind.deleted));
+      assert(nodes.length == 2 && atoms.length == 3);
+    });
+
+    test('object_store_content_equals', () {
+      const repository = $SomethingRepository();
+      final trivial = Trivial();
+      final target = Something(atomOne: 'target', linkOne: trivial);
+      final first = Something(
+          atomOne: 'a', linkOne: trivial, linkThree: [target, target]);
+      final second = Something(atomOne: 'a', linkOne: trivial);
+      assert(!repository.contentEquals(first, second));
+      second.linkThree$.insert(target);
+      assert(first.id != second.id);
+      assert(repository.contentEquals(first, second));
+      second.atomTwo$.set('b');
+      assert(!repository.contentEquals(first, second));
+    });
+
+    test('object_store_watch', () async {
+      final flagged = Flagged();
+      final values = <bool>[];
+      final subscription =
+          flagged.watch().listen((e) => values.add(e.done$.peek()));
+      await Future<void>.delayed(Duration.zero);
+      flagged.done$.set(true);
+      await Future<void>.delayed(Duration.zero);
+      subscription.pause();
+      flagged.done$.set(false);
+      flagged.done$.set(true);
+      subscription.resume();
+      await Future<void>.delayed(Duration.zero);
+      assert(listEquals(values, [false, true, true]));
+      await subscription.cancel();
+    });
+
+    test('object_store_resolve', () {
+      final trivial = Trivial();
+      final flagged = Flagged(done: true);
+      final resolved = [
+        for (final id in [trivial.id, flagged.id, Dust.instance.randomId()])
+          Dust.instance.resolve(id)
+      ];
+      assert(identical(resolved[0], trivial));
+      assert(resolved[1] is Flagged && (resolved[1] as Flagged).done$.peek());
+      assert(resolved[2] == null);
+    });
+
+    test('object_store_clear_remove', () {
+      final trivial = Trivial();
+      final other = Something(atomOne: 'other', linkOne: trivial);
+      final some = Something(
+          atomOne: 'test',
+          linkOne: trivial,
+          linkTwo: trivial,
+          linkThree: [other]);
+      some.linkTwo$.clear();
+      assert(some.linkTwo$.state(null) is LinkCleared);
+      assert(Dust.instance.getEdgeWrittenById(some.linkTwo$.id));
+      some.linkThree$.remove(other);
+      assert(some.linkThree$.get(null).isEmpty);
+      var edges = 0;
+      Dust.instance.getEdgeSrcByDstLabel(
+          other.id, $SomethingRepository.linkThreeLabel, (_, __) => edges++);
+      assert(edges == 0);
+    });
+
+    test('object_store_patch', () {
+      final trivial = Trivial();
+      final other = Trivial();
+      final some = Something(atomOne: 'one', atomTwo: 'two', linkOne: trivial);
+      final clocks = (some.atomTwo$.clock(), some.linkTwo$.clock());
+      const $SomethingRepository().patch(some, atomOne: 'new', linkOne: other);
+      assert(some.atomOne$.peek() == 'new' && some.linkOne$.peek() == other);
+      assert(some.atomTwo$.peek() == 'two' && some.linkTwo$.peek() == null);
+      assert((some.atomTwo$.clock(), some.linkTwo$.clock()) == clocks);
+    });
+
+    test('object_store_manifest', () {
+      final names = Dust.instance.manifest.map((e) => e.name).toSet();
+      assert(names.containsAll(['Trivial', 'Something', 'Stamped', 'Board']));
+      const info = $SomethingRepository.manifest;
+      assert(info.label == $SomethingRepository.Label);
+      final fields = {for (final field in info.fields) field.name: field};
+      assert(fields['atomTwo']!.kind == FieldKind.atomOption);
+      assert(fields['atomTwo']!.label == $SomethingRepository.atomTwoLabel);
+      assert(fields['linkThree']!.type == 'Something');
+      assert(fields['backlink']!.kind == FieldKind.backlinks);
+      assert(fields['backlink']!.label == null);
+      assert($TrivialRepository.manifest.fields.isEmpty);
+    });
+
+    test('object_store_manifest_bindings', () {
+      final fields = {
+        for (final field in $MigratedRepository.manifest.fields)
+          field.name: field,
+      };
+      assert(fields['name']!.readonly && !fields['name']!.hidden);
+      assert(fields['note']!.hidden && !fields['note']!.readonly);
+      final nickname = $AccountRepository.manifest.fields
+          .firstWhere((e) => e.name == 'nickname');
+      assert(!nickname.readonly && !nickname.hidden);
+    });
+
+    test('object_store_default', () {
+      final flagged = Flagged();
+      assert(flagged.done$.peek() == false);
+      var stored = false;
+      Dust.instance.getAtomById(flagged.done$.id, (slv) {
+        stored = slv != null &&
+            const BoolSerializer().deserialize(BytesReader(slv.$3)) == false;
+      });
+      assert(stored);
+      flagged.done$.set(true);
+      assert(flagged.done$.peek() == true);
+      assert(Flagged(done: true).done$.peek() == true);
+    });
+
+    test('object_store_get_fields', () {
+      final trivial = Trivial();
+      final something = Something(atomOne: 'test', linkOne: trivial);
+      something.linkThree$.insert(something);
+      final fields = const $SomethingRepository().getFields(something.id, [
+        $SomethingField.atomOne,
+        $SomethingField.linkTwo,
+        $SomethingField.linkThree,
+      ]);
+      assert(fields.length == 3);
+      assert(fields[$SomethingField.atomOne] == 'test');
+      assert(fields.containsKey($SomethingField.linkTwo));
+      assert(fields[$SomethingField.linkTwo] == null);
+      assert((fields[$SomethingField.linkThree]! as List<Id>).single ==
+          something.id);
+      assert(!fields.containsKey($SomethingField.atomTwo));
+      assert(!fields.containsKey($SomethingField.linkOne));
+      assert(!fields.containsKey($SomethingField.backlink));
+      final none = const $SomethingRepository().getFields(something.id, []);
+      assert(none.isEmpty);
+    });
+
+    test('object_store_duplicate', () {
+      final trivial = Trivial();
+      final target = Something(atomOne: 'target', linkOne: trivial);
+      final original = Something(
+          atomOne: 'test',
+          atomTwo: '2333',
+          linkOne: trivial,
+          linkTwo: trivial,
+          linkThree: [target]);
+      final copy = const $SomethingRepository().duplicate(original);
+      assert(copy.id != original.id);
+      assert(copy.atomOne$.peek() == 'test');
+      assert(copy.atomTwo$.peek() == '2333');
+      assert(copy.linkOne$.peek() == trivial);
+      assert(copy.linkTwo$.peek() == trivial);
+      assert(copy.linkThree$.peek().single == target);
+      assert(target.backlink$.peek().toSet().containsAll([original, copy]));
+      assert(copy.backlink$.peek().isEmpty);
+
+      final other = Trivial();
+      final changed = const $SomethingRepository()
+          .duplicate(original, atomOne: 'changed', linkTwo: other);
+      assert(changed.atomOne$.peek() == 'changed');
+      assert(changed.atomTwo$.peek() == '2333');
+      assert(changed.linkTwo$.peek() == other);
+      assert(original.atomOne$.peek() == 'test');
+    });
+
+    test('object_store_link_state', () {
+      final trivial = Trivial();
+      final unset = Something(atomOne: 'test', linkOne: trivial);
+      assert(unset.linkTwo$.state(null) is LinkUnset);
+      final some =
+          Something(atomOne: 'test', linkOne: trivial, linkTwo: trivial);
+      final state = some.linkTwo$.state(null);
+      assert(state is LinkSome && state.target == trivial);
+      some.linkTwo$.set(null);
+      assert(some.linkTwo$.state(null) is LinkCleared);
+      some.linkTwo$.set(trivial);
+      assert(some.linkTwo$.state(null) is LinkSome);
+      unset.linkTwo$.set(null);
+      assert(unset.linkTwo$.state(null) is LinkCleared);
+    });
+
+    test('object_store_link_dangling', () {
+      final target = Trivial();
+      final some = Something(
+          atomOne: 'test', linkOne: Trivial(), linkTwo: target);
+      target.delete();
+      final state = some.linkTwo$.state(null);
+      assert(state is LinkDangling && state.target == target.id);
+      assert(some.linkTwo$.get(null) == null);
+      some.linkTwo$.set(null);
+      assert(some.linkTwo$.state(null) is LinkCleared);
+    });
+
+    test('object_store_link_set_id', () {
+      final first = Trivial();
+      final second = Trivial();
+      final some = Something(atomOne: 'test', linkOne: first);
+      some.linkOne$.setId(second.id, check: true);
+      some.linkTwo$.setId(first.id);
+      assert(some.linkOne$.get(null) == second);
+      assert(some.linkTwo$.get(null) == first);
+      var thrown = false;
+      try {
+        some.linkOne$.setId(Flagged().id, check: true);
+      } on LinkTypeMismatchException {
+        thrown = true;
+      }
+      assert(thrown && some.linkOne$.get(null) == second);
+    });
+
+    test('object_store_multilinks_iterable', () {
+      final trivial = Trivial();
+      final some = Something(atomOne: 'some', linkOne: trivial);
+      final targets = [
+        for (var i = 0; i < 3; i++)
+          Something(atomOne: '$i', linkOne: trivial),
+      ];
+      some.linkThree$.extend(targets);
+      Dust.instance.barrier();
+      final visited = <Something>[];
+      for (final target in some.linkThree$) {
+        visited.add(target);
+      }
+      assert(visited.length == 3 && visited.toSet().containsAll(targets));
+      final sources = [for (final source in targets[0].backlink$) source];
+      assert(sources.length == 1 && sources[0] == some);
+      assert(some.linkThree$.contains(targets[1]));
+    });
+
+    test('object_store_cascade_delete', () {
+      final column = Trivial();
+      final first = Board(columns: [column]);
+      final second = Board();
+      final parent = Board(columns: [column], children: [first, second]);
+      // A cycle of cascading links, which must not recurse forever.
+      first.children$.insert(parent);
+      final target = Trivial();
+      final some = Something(atomOne: 'some', linkOne: target);
+      parent.delete();
+      for (final board in [parent, first, second]) {
+        assert(const $BoardRepository().get(board.id).peek() == null);
+      }
+      assert(const $TrivialRepository().get(column.id).peek() != null);
+      some.delete();
+      assert(const $TrivialRepository().get(target.id).peek() != null);
+    });
+
+    test('object_store_multilinks_dedup', () {
+      final trivial = Trivial();
+      final other = Something(atomOne: 'other', linkOne: trivial);
+      final some = Something(atomOne: 'test', linkOne: trivial);
+      final distinct = Multilinks<Something>(some.id,
+          $SomethingRepository.linkThreeLabel, const $SomethingRepository(),
+          distinct: true);
+      // As if inserted concurrently by two devices.
+      Multilinks.write(
+          some.id, $SomethingRepository.linkThreeLabel, [other.id, other.id]);
+      Dust.instance.barrier();
+      assert(listEquals(some.linkThree$.get(null), [other, other]));
+      assert(listEquals(distinct.get(null), [other]));
+      distinct.insert(other);
+      assert(distinct.get(null).length == 1);
+      assert(some.linkThree$.get(null).length == 2);
+      distinct.remove(other);
+      assert(distinct.get(null).isEmpty);
+      assert(some.linkThree$.get(null).isEmpty);
+    });
+
+    test('object_store_all_ids', () {
+      final created = [Flagged(), Flagged(done: true)];
+      final ids = const $FlaggedRepository().allIds();
+      assert(ids.toSet().containsAll(created.map((e) => e.id)));
+      final all = const $FlaggedRepository().all().get(null);
+      assert(ids.length == all.length);
+    });
+
+    test('object_store_hash', () {
+      final trivial = Trivial();
+      final trivialCopy = _Trivial._(trivial.id);
+      assert(!identical(trivial, trivialCopy));
+      final set = {trivial, trivialCopy, Trivial()};
+      assert(set.length == 2);
+      assert(set.contains(trivial));
+      final map = {trivial: 0};
+      map[trivialCopy] = 1;
+      assert(map.length == 1 && map[trivial] == 1);
+    });
+
     test('object_store_perf', () {
       final something = Something(atomOne: '', linkOne: Trivial());
       final stopwatch = Stopwatch()..start();
@@ -13,6 +13,15 @@ mixin _$Trivial {
   Id get id;
 
   void delete();
+
+  /// Calls [f] whenever any field of this object changes, until the returned
+  /// subscription is cancelled (or garbage collected). Changes to backlinks
+  /// are not included.
+  Subscription subscribe(void Function(Trivial) f);
+
+  /// Returns a stream which emits this object on listen and whenever
+  /// [subscribe] would call back. Changes while paused are coalesced.
+  Stream<Trivial> watch();
 }
 
 final class _Trivial extends Trivial {
@@ -27,6 +36,19 @@ final class _Trivial extends Trivial {
 
   @override
   void delete() => const $TrivialRepository().delete(this);
+
+  @override
+  Subscription subscribe(void Function(Trivial) f) =>
+      Subscription((o) {}, () => f(this));
+
+  @override
+  Stream<Trivial> watch() => watchStream(this, subscribe);
+
+  @override
+  bool operator ==(Object other) => other is Trivial && other.id == id;
+
+  @override
+  int get hashCode => id.hashCode;
 }
 
 class $TrivialRepository implements Repository<Trivial> {
@@ -34,6 +56,13 @@ class $TrivialRepository implements Repository<Trivial> {
 
   static const int Label = 4898135217045869580;
 
+  /// The shape of [Trivial], see [Dust.manifest].
+  static const manifest = ModelInfo(
+    name: 'Trivial',
+    label: $TrivialRepository.Label,
+    fields: [],
+  );
+
   static final Map<Id, WeakReference<NodeOption<Trivial>>> $entries = {};
 
   static bool $init = false;
@@ -42,6 +71,8 @@ class $TrivialRepository implements Repository<Trivial> {
   Schema init() {
     $init = true;
     return const Schema(
+      label: $TrivialRepository.Label,
+      model: manifest,
       stickyNodes: [$TrivialRepository.Label],
       stickyAtoms: [],
       stickyEdges: [],
@@ -68,11 +99,24 @@ class $TrivialRepository implements Repository<Trivial> {
     $write(
       $id,
     );
-    final $res = $node.get(null)!;
+    return $node.get(null)!;
+  }
 
-    return $res;
+  /// Same as [create], but returns only the ID of the new object, skipping
+  /// the construction of the object itself.
+  Id createId() {
+    final $id = Dust.instance.randomId();
+    $write(
+      $id,
+    );
+    return $id;
   }
 
+  /// Same as [createId], but returns a handle which reads the new object
+  /// back only on first access.
+  Created<Trivial> createLazy() =>
+      Created(createId(), const $TrivialRepository());
+
   NodeAuto<Trivial> auto(
     Id $id,
   ) {
@@ -82,10 +126,30 @@ class $TrivialRepository implements Repository<Trivial> {
       () => $write(
         $id,
       ),
-      ($res) {},
     );
   }
 
+  /// Returns the object with given ID, first creating it from the given
+  /// fields if there is none. Same as calling `get` on [auto].
+  Trivial getOrCreate(
+    Id $id,
+  ) =>
+      auto(
+        $id,
+      ).get(null);
+
+  /// Creates a new [Trivial] with the same field values as [$model],
+  /// except for the given ones. Given `null`s are ignored, so nullable fields
+  /// can only be cleared after the copy has been created.
+  Trivial duplicate(Trivial $model) {
+    return create();
+  }
+
+  /// Returns if [$a] and [$b] have the same field values, regardless of
+  /// their IDs (e.g. to detect duplicates on import). Links are compared by
+  /// the IDs of their targets, multilinks and backlinks as sets of these.
+  bool contentEquals(Trivial $a, Trivial $b) => true;
+
   @override
   NodeOption<Trivial> get(Id $id) {
     final $existing = $entries[$id]?.target;
@@ -101,15 +165,30 @@ class $TrivialRepository implements Repository<Trivial> {
   @override
   void delete(Trivial $model) {
     assert($init, 'Repository should be registered in `Dust.open`.');
-    final $id = $model.id;
+    $delete($model.id, {});
+    Dust.instance.barrier();
+  }
+
+  /// Deletes the object with given ID without a barrier, together with the
+  /// objects it owns through `@Cascade()` fields, skipping the ones in
+  /// [$visited] (so that cycles end).
+  void $delete(Id $id, Set<Id> $visited) {
+    if (!$visited.add($id)) return;
     final $store = Dust.instance;
     $entries.remove($id);
     $store.setNode($id, null);
-    $store.barrier();
   }
 
   NodesByLabel<Trivial> all() =>
       NodesByLabel($TrivialRepository.Label, const $TrivialRepository());
+
+  /// Returns the IDs of all objects, without constructing or subscribing to
+  /// any of them (unlike [all]).
+  List<Id> allIds() {
+    final $res = <Id>[];
+    Dust.instance.getNodeByLabel($TrivialRepository.Label, $res.add);
+    return $res;
+  }
 }
 
 // ignore_for_file: duplicate_ignore, unused_local_variable, non_constant_identifier_names, constant_identifier_names, invalid_use_of_protected_member, unnecessary_cast, prefer_const_constructors, lines_longer_than_80_chars, require_trailing_commas, inference_failure_on_function_invocation, unnecessary_parenthesis, unnecessary_raw_strings, unnecessary_null_checks, join_return_with_assignment, prefer_final_locals, avoid_js_rounded_ints, avoid_positional_boolean_parameters, always_specify_types
@@ -118,13 +197,22 @@ class $TrivialRepository implements Repository<Trivial> {
 mixin _$Something {
   Id get id;
   Atom<String> get atomOne$;
-  AtomOption<String?> get atomTwo$;
+  AtomOption<Tag?> get atomTwo$;
   Link<Trivial> get linkOne$;
   LinkOption<Trivial?> get linkTwo$;
   Multilinks<Something> get linkThree$;
   Backlinks<Something> get backlink$;
 
   void delete();
+
+  /// Calls [f] whenever any field of this object changes, until the returned
+  /// subscription is cancelled (or garbage collected). Changes to backlinks
+  /// are not included.
+  Subscription subscribe(void Function(Something) f);
+
+  /// Returns a stream which emits this object on listen and whenever
+  /// [subscribe] would call back. Changes while paused are coalesced.
+  Stream<Something> watch();
 }
 
 final class _Something extends Something {
@@ -142,7 +230,7 @@ final class _Something extends Something {
 
   factory _Something({
     required String atomOne,
-    String? atomTwo,
+    Tag? atomTwo,
     required Trivial linkOne,
     Trivial? linkTwo,
     Iterable<Something> linkThree = const Iterable.empty(),
@@ -167,7 +255,7 @@ final class _Something extends Something {
   final Atom<String> atomOne$;
 
   @override
-  final AtomOption<String?> atomTwo$;
+  final AtomOption<Tag?> atomTwo$;
 
   @override
   final Link<Trivial> linkOne$;
@@ -183,6 +271,25 @@ final class _Something extends Something {
 
   @override
   void delete() => const $SomethingRepository().delete(this);
+
+  @override
+  Subscription subscribe(void Function(Something) f) =>
+      Subscription((o) {
+        atomOne$.connect(o);
+        atomTwo$.connect(o);
+        linkOne$.connect(o);
+        linkTwo$.connect(o);
+        linkThree$.connect(o);
+      }, () => f(this));
+
+  @override
+  Stream<Something> watch() => watchStream(this, subscribe);
+
+  @override
+  bool operator ==(Object other) => other is Something && other.id == id;
+
+  @override
+  int get hashCode => id.hashCode;
 }
 
 class $SomethingRepository implements Repository<Something> {
@@ -198,6 +305,50 @@ class $SomethingRepository implements Repository<Something> {
   static const atomOneSerializer = StringSerializer();
   static const atomTwoSerializer = OptionSerializer(StringSerializer());
 
+  /// The shape of [Something], see [Dust.manifest].
+  static const manifest = ModelInfo(
+    name: 'Something',
+    label: $SomethingRepository.Label,
+    fields: [
+      FieldInfo(
+        name: 'atomOne',
+        kind: FieldKind.atom,
+        type: 'String',
+        label: $SomethingRepository.atomOneLabel,
+      ),
+      FieldInfo(
+        name: 'atomTwo',
+        kind: FieldKind.atomOption,
+        type: 'Tag?',
+        label: $SomethingRepository.atomTwoLabel,
+      ),
+      FieldInfo(
+        name: 'linkOne',
+        kind: FieldKind.link,
+        type: 'Trivial',
+        label: $SomethingRepository.linkOneLabel,
+      ),
+      FieldInfo(
+        name: 'linkTwo',
+        kind: FieldKind.linkOption,
+        type: 'Trivial?',
+        label: $SomethingRepository.linkTwoLabel,
+      ),
+      FieldInfo(
+        name: 'linkThree',
+        kind: FieldKind.multilinks,
+        type: 'Something',
+        label: $SomethingRepository.linkThreeLabel,
+      ),
+      FieldInfo(
+        name: 'backlink',
+        kind: FieldKind.backlinks,
+        type: 'Something',
+        label: null,
+      ),
+    ],
+  );
+
   static final Map<Id, WeakReference<NodeOption<Something>>> $entries = {};
 
   static bool $init = false;
@@ -206,6 +357,8 @@ class $SomethingRepository implements Repository<Something> {
   Schema init() {
     $init = true;
     return const Schema(
+      label: $SomethingRepository.Label,
+      model: manifest,
       stickyNodes: [$SomethingRepository.Label],
       stickyAtoms: [$SomethingRepository.atomOneLabel],
       stickyEdges: [$SomethingRepository.linkOneLabel],
@@ -219,9 +372,11 @@ class $SomethingRepository implements Repository<Something> {
   void $write(
     Id $id, {
     required String atomOne,
-    String? atomTwo,
+    Tag? atomTwo,
     required Trivial linkOne,
     Trivial? linkTwo,
+    Iterable<Something> linkThree = const Iterable.empty(),
+    Iterable<Something> backlink = const Iterable.empty(),
   }) {
     assert($init, 'Repository should be registered in `Dust.open`.');
     final $store = Dust.instance;
@@ -268,12 +423,23 @@ class $SomethingRepository implements Repository<Something> {
       );
     }
 
+    for (final item in linkThree) {
+      $store.setEdge(
+        $store.edgeId($id),
+        (
+          $id,
+          $SomethingRepository.linkThreeLabel,
+          const $SomethingRepository().id(item),
+        ),
+      );
+    }
+
     $store.barrier();
   }
 
   Something create({
     required String atomOne,
-    String? atomTwo,
+    Tag? atomTwo,
     required Trivial linkOne,
     Trivial? linkTwo,
     Iterable<Something> linkThree = const Iterable.empty(),
@@ -287,19 +453,84 @@ class $SomethingRepository implements Repository<Something> {
       atomTwo: atomTwo,
       linkOne: linkOne,
       linkTwo: linkTwo,
+      linkThree: linkThree,
+      backlink: backlink,
     );
-    final $res = $node.get(null)!;
-    for (final item in linkThree) {
-      $res.linkThree$.insert(item);
-    }
+    return $node.get(null)!;
+  }
 
-    return $res;
+  /// Same as [create], but returns only the ID of the new object, skipping
+  /// the construction of the object itself.
+  Id createId({
+    required String atomOne,
+    Tag? atomTwo,
+    required Trivial linkOne,
+    Trivial? linkTwo,
+    Iterable<Something> linkThree = const Iterable.empty(),
+    Iterable<Something> backlink = const Iterable.empty(),
+  }) {
+    final $id = Dust.instance.randomId();
+    $write(
+      $id,
+      atomOne: atomOne,
+      atomTwo: atomTwo,
+      linkOne: linkOne,
+      linkTwo: linkTwo,
+      linkThree: linkThree,
+      backlink: backlink,
+    );
+    return $id;
   }
 
+  /// Same as [createId], but returns a handle which reads the new object
+  /// back only on first access.
+  Created<Something> createLazy({
+    required String atomOne,
+    Tag? atomTwo,
+    required Trivial linkOne,
+    Trivial? linkTwo,
+    Iterable<Something> linkThree = const Iterable.empty(),
+    Iterable<Something> backlink = const Iterable.empty(),
+  }) =>
+      Created(
+          createId(
+            atomOne: atomOne,
+            atomTwo: atomTwo,
+            linkOne: linkOne,
+            linkTwo: linkTwo,
+            linkThree: linkThree,
+            backlink: backlink,
+          ),
+          const $SomethingRepository());
+
+  /// Same as calling [createId] on each of [$items], but with a single
+  /// [Dust.barrier] call, which is much cheaper for bulk imports.
+  List<Id> createBatch(
+    Iterable<
+            ({
+        String atomOne,
+        Tag? atomTwo,
+        Trivial linkOne,
+        Trivial? linkTwo,
+        Iterable<Something> linkThree,
+      })>
+        $items,
+  ) =>
+      Dust.instance.batch(() => [
+            for (final $item in $items)
+              createId(
+                atomOne: $item.atomOne,
+                atomTwo: $item.atomTwo,
+                linkOne: $item.linkOne,
+                linkTwo: $item.linkTwo,
+                linkThree: $item.linkThree,
+              ),
+          ]);
+
   NodeAuto<Something> auto(
     Id $id, {
     required String atomOne,
-    String? atomTwo,
+    Tag? atomTwo,
     required Trivial linkOne,
     Trivial? linkTwo,
     Iterable<Something> linkThree = const Iterable.empty(),
@@ -314,15 +545,129 @@ class $SomethingRepository implements Repository<Something> {
         atomTwo: atomTwo,
         linkOne: linkOne,
         linkTwo: linkTwo,
+        linkThree: linkThree,
+        backlink: backlink,
       ),
-      ($res) {
-        for (final item in linkThree) {
-          $res.linkThree$.insert(item);
-        }
-      },
     );
   }
 
+  /// Returns the object with given ID, first creating it from the given
+  /// fields if there is none. Same as calling `get` on [auto].
+  Something getOrCreate(
+    Id $id, {
+    required String atomOne,
+    Tag? atomTwo,
+    required Trivial linkOne,
+    Trivial? linkTwo,
+    Iterable<Something> linkThree = const Iterable.empty(),
+    Iterable<Something> backlink = const Iterable.empty(),
+  }) =>
+      auto(
+        $id,
+        atomOne: atomOne,
+        atomTwo: atomTwo,
+        linkOne: linkOne,
+        linkTwo: linkTwo,
+        linkThree: linkThree,
+        backlink: backlink,
+      ).get(null);
+
+  /// Creates a new [Something] with the same field values as [$model],
+  /// except for the given ones. Given `null`s are ignored, so nullable fields
+  /// can only be cleared after the copy has been created.
+  Something duplicate(
+    Something $model, {
+    String? atomOne,
+    Tag? atomTwo,
+    Trivial? linkOne,
+    Trivial? linkTwo,
+    Iterable<Something>? linkThree,
+  }) {
+    return create(
+      atomOne: atomOne ?? $model.atomOne$.get(null),
+      atomTwo: atomTwo ?? $model.atomTwo$.get(null),
+      linkOne: linkOne ?? $model.linkOne$.get(null),
+      linkTwo: linkTwo ?? $model.linkTwo$.get(null),
+      linkThree: linkThree ?? $model.linkThree$.get(null),
+    );
+  }
+
+  /// Returns if [$a] and [$b] have the same field values, regardless of
+  /// their IDs (e.g. to detect duplicates on import). Links are compared by
+  /// the IDs of their targets, multilinks and backlinks as sets of these.
+  bool contentEquals(Something $a, Something $b) =>
+      serializedEquals($SomethingRepository.atomOneSerializer,
+          $a.atomOne$.get(null), $b.atomOne$.get(null)) &&
+      serializedEquals($SomethingRepository.atomTwoSerializer,
+          $a.atomTwo$.get(null), $b.atomTwo$.get(null)) &&
+      $a.linkOne$.get(null).id == $b.linkOne$.get(null).id &&
+      $a.linkTwo$.get(null)?.id == $b.linkTwo$.get(null)?.id &&
+      idSetEquals($a.linkThree$.get(null).map((e) => e.id),
+          $b.linkThree$.get(null).map((e) => e.id)) &&
+      idSetEquals($a.backlink$.get(null).map((e) => e.id),
+          $b.backlink$.get(null).map((e) => e.id));
+
+  /// Writes the given fields of [$model] with a single barrier, leaving the
+  /// others (and their clocks) untouched. As with [duplicate], given `null`s
+  /// are ignored, so nullable fields can only be cleared by their setters.
+  void patch(
+    Something $model, {
+    String? atomOne,
+    Tag? atomTwo,
+    Trivial? linkOne,
+    Trivial? linkTwo,
+  }) {
+    final $id = $model.id;
+    final $store = Dust.instance;
+    if (atomOne != null) {
+      $store.setAtom(
+        $id ^ $SomethingRepository.atomOneLabel,
+        (
+          $id,
+          $SomethingRepository.atomOneLabel,
+          atomOne,
+          $SomethingRepository.atomOneSerializer,
+        ),
+      );
+    }
+
+    if (atomTwo != null) {
+      $store.setAtom(
+        $id ^ $SomethingRepository.atomTwoLabel,
+        (
+          $id,
+          $SomethingRepository.atomTwoLabel,
+          atomTwo,
+          $SomethingRepository.atomTwoSerializer,
+        ),
+      );
+    }
+
+    if (linkOne != null) {
+      $store.setEdge(
+        $id ^ $SomethingRepository.linkOneLabel,
+        (
+          $id,
+          $SomethingRepository.linkOneLabel,
+          linkOne.id,
+        ),
+      );
+    }
+
+    if (linkTwo != null) {
+      $store.setEdge(
+        $id ^ $SomethingRepository.linkTwoLabel,
+        (
+          $id,
+          $SomethingRepository.linkTwoLabel,
+          linkTwo.id,
+        ),
+      );
+    }
+
+    $store.barrier();
+  }
+
   @override
   NodeOption<Something> get(Id $id) {
     final $existing = $entries[$id]?.target;
@@ -335,7 +680,7 @@ class $SomethingRepository implements Repository<Something> {
         $SomethingRepository.atomOneLabel,
         $SomethingRepository.atomOneSerializer,
       ),
-      atomTwo$: AtomOption<String?>(
+      atomTwo$: AtomOption<Tag?>(
         $id ^ $SomethingRepository.atomTwoLabel,
         $id,
         $SomethingRepository.atomTwoLabel,
@@ -369,16 +714,2285 @@ class $SomethingRepository implements Repository<Something> {
     return $entry;
   }
 
+  Map<$SomethingField, Object?> getFields(Id $id, Iterable<$SomethingField> $fields) {
+    final $store = Dust.instance;
+    final $res = <$SomethingField, Object?>{};
+    for (final $field in $fields) {
+      switch ($field) {
+        case $SomethingField.atomOne:
+          {
+            $store.getAtomById(
+                $id ^ $SomethingRepository.atomOneLabel,
+                (slv) => $res[$field] = (slv == null)
+                    ? null
+                    : $SomethingRepository.atomOneSerializer
+                        .deserialize(BytesReader(slv.$3)));
+          }
+        case $SomethingField.atomTwo:
+          {
+            $store.getAtomById(
+                $id ^ $SomethingRepository.atomTwoLabel,
+                (slv) => $res[$field] = (slv == null)
+                    ? null
+                    : $SomethingRepository.atomTwoSerializer
+                        .deserialize(BytesReader(slv.$3)));
+          }
+        case $SomethingField.linkOne:
+          {
+            $store.getEdgeById($id ^ $SomethingRepository.linkOneLabel,
+                (sld) => $res[$field] = sld?.$3);
+          }
+        case $SomethingField.linkTwo:
+          {
+            $store.getEdgeById($id ^ $SomethingRepository.linkTwoLabel,
+                (sld) => $res[$field] = sld?.$3);
+          }
+        case $SomethingField.linkThree:
+          {
+            final $dsts = <Id>[];
+            $store.getEdgeDstBySrcLabel($id, $SomethingRepository.linkThreeLabel,
+                (_, dst) => $dsts.add(dst));
+            $res[$field] = $dsts;
+          }
+        case $SomethingField.backlink:
+          {
+            final $srcs = <Id>[];
+            $store.getEdgeSrcByDstLabel($id, $SomethingRepository.linkThreeLabel,
+                (_, src) => $srcs.add(src));
+            $res[$field] = $srcs;
+          }
+      }
+    }
+    return $res;
+  }
+
+  /// Returns the required fields of the object with given ID which have no
+  /// value (e.g. because they have not been fully synchronised yet). If this
+  /// is non-empty, reading these fields of the object will fail.
+  List<$SomethingField> missingFields(Id $id) {
+    final $fields = getFields($id, const [
+      $SomethingField.atomOne,
+      $SomethingField.linkOne,
+    ]);
+    return [
+      for (final MapEntry(:key, :value) in $fields.entries)
+        if (value == null) key
+    ];
+  }
+
   @override
   void delete(Something $model) {
     assert($init, 'Repository should be registered in `Dust.open`.');
-    final $id = $model.id;
+    $delete($model.id, {});
+    Dust.instance.barrier();
+  }
+
+  /// Deletes the object with given ID without a barrier, together with the
+  /// objects it owns through `@Cascade()` fields, skipping the ones in
+  /// [$visited] (so that cycles end).
+  void $delete(Id $id, Set<Id> $visited) {
+    if (!$visited.add($id)) return;
     final $store = Dust.instance;
     $entries.remove($id);
     $store.setNode($id, null);
-    $store.barrier();
   }
 
   NodesByLabel<Something> all() =>
       NodesByLabel($SomethingRepository.Label, const $SomethingRepository());
+
+  /// Returns the IDs of all objects, without constructing or subscribing to
+  /// any of them (unlike [all]).
+  List<Id> allIds() {
+    final $res = <Id>[];
+    Dust.instance.getNodeByLabel($SomethingRepository.Label, $res.add);
+    return $res;
+  }
+}
+
+/// The fields of [Something], see [$SomethingRepository.getFields].
+enum $SomethingField { atomOne, atomTwo, linkOne, linkTwo, linkThree, backlink }
+
+// ignore_for_file: duplicate_ignore, unused_local_variable, non_constant_identifier_names, constant_identifier_names, invalid_use_of_protected_member, unnecessary_cast, prefer_const_constructors, lines_longer_than_80_chars, require_trailing_commas, inference_failure_on_function_invocation, unnecessary_parenthesis, unnecessary_raw_strings, unnecessary_null_checks, join_return_with_assignment, prefer_final_locals, avoid_js_rounded_ints, avoid_positional_boolean_parameters, always_specify_types
+// coverage:ignore-file
+
+mixin _$Stamped {
+  Id get id;
+  Atom<String> get name$;
+  Atom<int> get createdAt$;
+  Atom<int> get updatedAt$;
+
+  void delete();
+
+  /// Calls [f] whenever any field of this object changes, until the returned
+  /// subscription is cancelled (or garbage collected). Changes to backlinks
+  /// are not included.
+  Subscription subscribe(void Function(Stamped) f);
+
+  /// Returns a stream which emits this object on listen and whenever
+  /// [subscribe] would call back. Changes while paused are coalesced.
+  Stream<Stamped> watch();
+}
+
+final class _Stamped extends Stamped {
+  @override
+  final Id id;
+
+  _Stamped._(this.id,
+      {required this.name$, required this.createdAt$, required this.updatedAt$})
+      : super._();
+
+  factory _Stamped({
+    required String name,
+  }) {
+    return const $StampedRepository().create(
+      name: name,
+    ) as _Stamped;
+  }
+
+  @override
+  final Atom<String> name$;
+
+  @override
+  final Atom<int> createdAt$;
+
+  @override
+  final Atom<int> updatedAt$;
+
+  @override
+  void delete() => const $StampedRepository().delete(this);
+
+  @override
+  Subscription subscribe(void Function(Stamped) f) =>
+      Subscription((o) {
+        name$.connect(o);
+        createdAt$.connect(o);
+        updatedAt$.connect(o);
+      }, () => f(this));
+
+  @override
+  Stream<Stamped> watch() => watchStream(this, subscribe);
+
+  @override
+  bool operator ==(Object other) => other is Stamped && other.id == id;
+
+  @override
+  int get hashCode => id.hashCode;
+
+  @override
+  String toString() => name$.get(null);
 }
+
+class $StampedRepository implements Repository<Stamped> {
+  const $StampedRepository();
+
+  static const int Label = -2401994526286841355;
+  static const int nameLabel = 5206772021501947976;
+  static const int createdAtLabel = -5841228839232040424;
+  static const int updatedAtLabel = -5429234062900474643;
+
+  static const nameSerializer = StringSerializer();
+  static const createdAtSerializer = IntSerializer();
+  static const updatedAtSerializer = IntSerializer();
+
+  /// The shape of [Stamped], see [Dust.manifest].
+  static const manifest = ModelInfo(
+    name: 'Stamped',
+    label: $StampedRepository.Label,
+    fields: [
+      FieldInfo(
+        name: 'name',
+        kind: FieldKind.atom,
+        type: 'String',
+        label: $StampedRepository.nameLabel,
+      ),
+      FieldInfo(
+        name: 'createdAt',
+        kind: FieldKind.timestamp,
+        type: 'int',
+        label: $StampedRepository.createdAtLabel,
+      ),
+      FieldInfo(
+        name: 'updatedAt',
+        kind: FieldKind.timestamp,
+        type: 'int',
+        label: $StampedRepository.updatedAtLabel,
+      ),
+    ],
+  );
+
+  static final Map<Id, WeakReference<NodeOption<Stamped>>> $entries = {};
+
+  static bool $init = false;
+
+  @override
+  Schema init() {
+    $init = true;
+    return const Schema(
+      label: $StampedRepository.Label,
+      model: manifest,
+      stickyNodes: [$StampedRepository.Label],
+      stickyAtoms: [
+        $StampedRepository.nameLabel,
+        $StampedRepository.createdAtLabel,
+        $StampedRepository.updatedAtLabel
+      ],
+      stickyEdges: [],
+      acyclicEdges: [],
+    );
+  }
+
+  @override
+  Id id(Stamped $model) => $model.id;
+
+  void $write(
+    Id $id, {
+    required String name,
+  }) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    final $store = Dust.instance;
+    final $now = Dust.clock();
+    $store.setNode($id, $StampedRepository.Label);
+    $store.setAtom(
+      $id ^ $StampedRepository.nameLabel,
+      (
+        $id,
+        $StampedRepository.nameLabel,
+        name,
+        $StampedRepository.nameSerializer,
+      ),
+    );
+
+    $store.setAtom(
+      $id ^ $StampedRepository.createdAtLabel,
+      (
+        $id,
+        $StampedRepository.createdAtLabel,
+        $now,
+        $StampedRepository.createdAtSerializer,
+      ),
+    );
+
+    $store.setAtom(
+      $id ^ $StampedRepository.updatedAtLabel,
+      (
+        $id,
+        $StampedRepository.updatedAtLabel,
+        $now,
+        $StampedRepository.updatedAtSerializer,
+      ),
+    );
+
+    $store.barrier();
+  }
+
+  Stamped create({
+    required String name,
+  }) {
+    final $id = Dust.instance.randomId();
+    final $node = get($id);
+    $write(
+      $id,
+      name: name,
+    );
+    return $node.get(null)!;
+  }
+
+  /// Same as [create], but returns only the ID of the new object, skipping
+  /// the construction of the object itself.
+  Id createId({
+    required String name,
+  }) {
+    final $id = Dust.instance.randomId();
+    $write(
+      $id,
+      name: name,
+    );
+    return $id;
+  }
+
+  /// Same as [createId], but returns a handle which reads the new object
+  /// back only on first access.
+  Created<Stamped> createLazy({
+    required String name,
+  }) =>
+      Created(
+          createId(
+            name: name,
+          ),
+          const $StampedRepository());
+
+  /// Same as calling [createId] on each of [$items], but with a single
+  /// [Dust.barrier] call, which is much cheaper for bulk imports.
+  List<Id> createBatch(
+    Iterable<
+            ({
+        String name,
+      })>
+        $items,
+  ) =>
+      Dust.instance.batch(() => [
+            for (final $item in $items)
+              createId(
+                name: $item.name,
+              ),
+          ]);
+
+  NodeAuto<Stamped> auto(
+    Id $id, {
+    required String name,
+  }) {
+    final $node = get($id);
+    return NodeAuto(
+      $node,
+      () => $write(
+        $id,
+        name: name,
+      ),
+    );
+  }
+
+  /// Returns the object with given ID, first creating it from the given
+  /// fields if there is none. Same as calling `get` on [auto].
+  Stamped getOrCreate(
+    Id $id, {
+    required String name,
+  }) =>
+      auto(
+        $id,
+        name: name,
+      ).get(null);
+
+  /// Creates a new [Stamped] with the same field values as [$model],
+  /// except for the given ones. Given `null`s are ignored, so nullable fields
+  /// can only be cleared after the copy has been created.
+  Stamped duplicate(
+    Stamped $model, {
+    String? name,
+  }) {
+    return create(
+      name: name ?? $model.name$.get(null),
+    );
+  }
+
+  /// Returns if [$a] and [$b] have the same field values, regardless of
+  /// their IDs (e.g. to detect duplicates on import). Links are compared by
+  /// the IDs of their targets, multilinks and backlinks as sets of these.
+  bool contentEquals(Stamped $a, Stamped $b) =>
+      serializedEquals($StampedRepository.nameSerializer,
+          $a.name$.get(null), $b.name$.get(null));
+
+  /// Writes the given fields of [$model] with a single barrier, leaving the
+  /// others (and their clocks) untouched. As with [duplicate], given `null`s
+  /// are ignored, so nullable fields can only be cleared by their setters.
+  void patch(
+    Stamped $model, {
+    String? name,
+  }) {
+    final $id = $model.id;
+    final $store = Dust.instance;
+    if (name != null) {
+      $store.setAtom(
+        $id ^ $StampedRepository.nameLabel,
+        (
+          $id,
+          $StampedRepository.nameLabel,
+          name,
+          $StampedRepository.nameSerializer,
+        ),
+      );
+    }
+
+    if (name != null) $touch($id);
+
+    $store.barrier();
+  }
+
+  void $touch(Id $id) {
+    Dust.instance.setAtom(
+      $id ^ $StampedRepository.updatedAtLabel,
+      (
+        $id,
+        $StampedRepository.updatedAtLabel,
+        Dust.clock(),
+        $StampedRepository.updatedAtSerializer,
+      ),
+    );
+  }
+
+  @override
+  NodeOption<Stamped> get(Id $id) {
+    final $existing = $entries[$id]?.target;
+    if ($existing != null) return $existing;
+    final $model = _Stamped._(
+      $id,
+      name$: Atom<String>(
+        $id ^ $StampedRepository.nameLabel,
+        $id,
+        $StampedRepository.nameLabel,
+        $StampedRepository.nameSerializer,
+        lazy: false,
+        onSet: () => $touch($id),
+      ),
+      createdAt$: Atom<int>(
+        $id ^ $StampedRepository.createdAtLabel,
+        $id,
+        $StampedRepository.createdAtLabel,
+        $StampedRepository.createdAtSerializer,
+      ),
+      updatedAt$: Atom<int>(
+        $id ^ $StampedRepository.updatedAtLabel,
+        $id,
+        $StampedRepository.updatedAtLabel,
+        $StampedRepository.updatedAtSerializer,
+      ),
+    );
+    final $entry = NodeOption($id, $StampedRepository.Label, $model);
+    $entries[$id] = WeakReference($entry);
+    return $entry;
+  }
+
+  Map<$StampedField, Object?> getFields(Id $id, Iterable<$StampedField> $fields) {
+    final $store = Dust.instance;
+    final $res = <$StampedField, Object?>{};
+    for (final $field in $fields) {
+      switch ($field) {
+        case $StampedField.name:
+          {
+            $store.getAtomById(
+                $id ^ $StampedRepository.nameLabel,
+                (slv) => $res[$field] = (slv == null)
+                    ? null
+                    : $StampedRepository.nameSerializer
+                        .deserialize(BytesReader(slv.$3)));
+          }
+        case $StampedField.createdAt:
+          {
+            $store.getAtomById(
+                $id ^ $StampedRepository.createdAtLabel,
+                (slv) => $res[$field] = (slv == null)
+                    ? null
+                    : $StampedRepository.createdAtSerializer
+                        .deserialize(BytesReader(slv.$3)));
+          }
+        case $StampedField.updatedAt:
+          {
+            $store.getAtomById(
+                $id ^ $StampedRepository.updatedAtLabel,
+                (slv) => $res[$field] = (slv == null)
+                    ? null
+                    : $StampedRepository.updatedAtSerializer
+                        .deserialize(BytesReader(slv.$3)));
+          }
+      }
+    }
+    return $res;
+  }
+
+  /// Returns the required fields of the object with given ID which have no
+  /// value (e.g. because they have not been fully synchronised yet). If this
+  /// is non-empty, reading these fields of the object will fail.
+  List<$StampedField> missingFields(Id $id) {
+    final $fields = getFields($id, const [
+      $StampedField.name,
+      $StampedField.createdAt,
+      $StampedField.updatedAt,
+    ]);
+    return [
+      for (final MapEntry(:key, :value) in $fields.entries)
+        if (value == null) key
+    ];
+  }
+
+  @override
+  void delete(Stamped $model) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    $delete($model.id, {});
+    Dust.instance.barrier();
+  }
+
+  /// Deletes the object with given ID without a barrier, together with the
+  /// objects it owns through `@Cascade()` fields, skipping the ones in
+  /// [$visited] (so that cycles end).
+  void $delete(Id $id, Set<Id> $visited) {
+    if (!$visited.add($id)) return;
+    final $store = Dust.instance;
+    $entries.remove($id);
+    $store.setNode($id, null);
+  }
+
+  NodesByLabel<Stamped> all() =>
+      NodesByLabel($StampedRepository.Label, const $StampedRepository());
+
+  /// Returns the IDs of all objects, without constructing or subscribing to
+  /// any of them (unlike [all]).
+  List<Id> allIds() {
+    final $res = <Id>[];
+    Dust.instance.getNodeByLabel($StampedRepository.Label, $res.add);
+    return $res;
+  }
+}
+
+/// The fields of [Stamped], see [$StampedRepository.getFields].
+enum $StampedField { name, createdAt, updatedAt }
+
+// ignore_for_file: duplicate_ignore, unused_local_variable, non_constant_identifier_names, constant_identifier_names, invalid_use_of_protected_member, unnecessary_cast, prefer_const_constructors, lines_longer_than_80_chars, require_trailing_commas, inference_failure_on_function_invocation, unnecessary_parenthesis, unnecessary_raw_strings, unnecessary_null_checks, join_return_with_assignment, prefer_final_locals, avoid_js_rounded_ints, avoid_positional_boolean_parameters, always_specify_types
+// coverage:ignore-file
+
+mixin _$Flagged {
+  Id get id;
+  AtomDefault<bool> get done$;
+
+  void delete();
+
+  /// Calls [f] whenever any field of this object changes, until the returned
+  /// subscription is cancelled (or garbage collected). Changes to backlinks
+  /// are not included.
+  Subscription subscribe(void Function(Flagged) f);
+
+  /// Returns a stream which emits this object on listen and whenever
+  /// [subscribe] would call back. Changes while paused are coalesced.
+  Stream<Flagged> watch();
+}
+
+final class _Flagged extends Flagged {
+  @override
+  final Id id;
+
+  _Flagged._(this.id, {required this.done$}) : super._();
+
+  factory _Flagged({
+    bool? done,
+  }) {
+    return const $FlaggedRepository().create(
+      done: done,
+    ) as _Flagged;
+  }
+
+  @override
+  final AtomDefault<bool> done$;
+
+  @override
+  void delete() => const $FlaggedRepository().delete(this);
+
+  @override
+  Subscription subscribe(void Function(Flagged) f) =>
+      Subscription((o) {
+        done$.connect(o);
+      }, () => f(this));
+
+  @override
+  Stream<Flagged> watch() => watchStream(this, subscribe);
+
+  @override
+  bool operator ==(Object other) => other is Flagged && other.id == id;
+
+  @override
+  int get hashCode => id.hashCode;
+}
+
+class $FlaggedRepository implements Repository<Flagged> {
+  const $FlaggedRepository();
+
+  static const int Label = -4009163917743778671;
+  static const int doneLabel = 4547261891652920177;
+
+  static const doneSerializer = BoolSerializer();
+
+  /// The shape of [Flagged], see [Dust.manifest].
+  static const manifest = ModelInfo(
+    name: 'Flagged',
+    label: $FlaggedRepository.Label,
+    fields: [
+      FieldInfo(
+        name: 'done',
+        kind: FieldKind.atomDefault,
+        type: 'bool',
+        label: $FlaggedRepository.doneLabel,
+      ),
+    ],
+  );
+
+  static final Map<Id, WeakReference<NodeOption<Flagged>>> $entries = {};
+
+  static bool $init = false;
+
+  @override
+  Schema init() {
+    $init = true;
+    return const Schema(
+      label: $FlaggedRepository.Label,
+      model: manifest,
+      stickyNodes: [$FlaggedRepository.Label],
+      stickyAtoms: [],
+      stickyEdges: [],
+      acyclicEdges: [],
+    );
+  }
+
+  @override
+  Id id(Flagged $model) => $model.id;
+
+  void $write(
+    Id $id, {
+    bool? done,
+  }) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    final $store = Dust.instance;
+
+    $store.setNode($id, $FlaggedRepository.Label);
+    $store.setAtom(
+      $id ^ $FlaggedRepository.doneLabel,
+      (
+        $id,
+        $FlaggedRepository.doneLabel,
+        done ?? false,
+        $FlaggedRepository.doneSerializer,
+      ),
+    );
+
+    $store.barrier();
+  }
+
+  Flagged create({
+    bool? done,
+  }) {
+    final $id = Dust.instance.randomId();
+    final $node = get($id);
+    $write(
+      $id,
+      done: done,
+    );
+    return $node.get(null)!;
+  }
+
+  /// Same as [create], but returns only the ID of the new object, skipping
+  /// the construction of the object itself.
+  Id createId({
+    bool? done,
+  }) {
+    final $id = Dust.instance.randomId();
+    $write(
+      $id,
+      done: done,
+    );
+    return $id;
+  }
+
+  /// Same as [createId], but returns a handle which reads the new object
+  /// back only on first access.
+  Created<Flagged> createLazy({
+    bool? done,
+  }) =>
+      Created(
+          createId(
+            done: done,
+          ),
+          const $FlaggedRepository());
+
+  /// Same as calling [createId] on each of [$items], but with a single
+  /// [Dust.barrier] call, which is much cheaper for bulk imports.
+  List<Id> createBatch(
+    Iterable<
+            ({
+        bool? done,
+      })>
+        $items,
+  ) =>
+      Dust.instance.batch(() => [
+            for (final $item in $items)
+              createId(
+                done: $item.done,
+              ),
+          ]);
+
+  NodeAuto<Flagged> auto(
+    Id $id, {
+    bool? done,
+  }) {
+    final $node = get($id);
+    return NodeAuto(
+      $node,
+      () => $write(
+        $id,
+        done: done,
+      ),
+    );
+  }
+
+  /// Returns the object with given ID, first creating it from the given
+  /// fields if there is none. Same as calling `get` on [auto].
+  Flagged getOrCreate(
+    Id $id, {
+    bool? done,
+  }) =>
+      auto(
+        $id,
+        done: done,
+      ).get(null);
+
+  /// Creates a new [Flagged] with the same field values as [$model],
+  /// except for the given ones. Given `null`s are ignored, so nullable fields
+  /// can only be cleared after the copy has been created.
+  Flagged duplicate(
+    Flagged $model, {
+    bool? done,
+  }) {
+    return create(
+      done: done ?? $model.done$.get(null),
+    );
+  }
+
+  /// Returns if [$a] and [$b] have the same field values, regardless of
+  /// their IDs (e.g. to detect duplicates on import). Links are compared by
+  /// the IDs of their targets, multilinks and backlinks as sets of these.
+  bool contentEquals(Flagged $a, Flagged $b) =>
+      serializedEquals($FlaggedRepository.doneSerializer,
+          $a.done$.get(null), $b.done$.get(null));
+
+  /// Writes the given fields of [$model] with a single barrier, leaving the
+  /// others (and their clocks) untouched. As with [duplicate], given `null`s
+  /// are ignored, so nullable fields can only be cleared by their setters.
+  void patch(
+    Flagged $model, {
+    bool? done,
+  }) {
+    final $id = $model.id;
+    final $store = Dust.instance;
+    if (done != null) {
+      $store.setAtom(
+        $id ^ $FlaggedRepository.doneLabel,
+        (
+          $id,
+          $FlaggedRepository.doneLabel,
+          done,
+          $FlaggedRepository.doneSerializer,
+        ),
+      );
+    }
+
+    $store.barrier();
+  }
+
+  @override
+  NodeOption<Flagged> get(Id $id) {
+    final $existing = $entries[$id]?.target;
+    if ($existing != null) return $existing;
+    final $model = _Flagged._(
+      $id,
+      done$: AtomDefault<bool>(
+        $id ^ $FlaggedRepository.doneLabel,
+        $id,
+        $FlaggedRepository.doneLabel,
+        $FlaggedRepository.doneSerializer,
+        false,
+        lazy: false,
+      ),
+    );
+    final $entry = NodeOption($id, $FlaggedRepository.Label, $model);
+    $entries[$id] = WeakReference($entry);
+    return $entry;
+  }
+
+  Map<$FlaggedField, Object?> getFields(Id $id, Iterable<$FlaggedField> $fields) {
+    final $store = Dust.instance;
+    final $res = <$FlaggedField, Object?>{};
+    for (final $field in $fields) {
+      switch ($field) {
+        case $FlaggedField.done:
+          {
+            $store.getAtomById(
+                $id ^ $FlaggedRepository.doneLabel,
+                (slv) => $res[$field] = (slv == null)
+                    ? false
+                    : $FlaggedRepository.doneSerializer
+                        .deserialize(BytesReader(slv.$3)));
+          }
+      }
+    }
+    return $res;
+  }
+
+  /// Returns the required fields of the object with given ID which have no
+  /// value (e.g. because they have not been fully synchronised yet). If this
+  /// is non-empty, reading these fields of the object will fail.
+  List<$FlaggedField> missingFields(Id $id) {
+    final $fields = getFields($id, const []);
+    return [
+      for (final MapEntry(:key, :value) in $fields.entries)
+        if (value == null) key
+    ];
+  }
+
+  @override
+  void delete(Flagged $model) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    $delete($model.id, {});
+    Dust.instance.barrier();
+  }
+
+  /// Deletes the object with given ID without a barrier, together with the
+  /// objects it owns through `@Cascade()` fields, skipping the ones in
+  /// [$visited] (so that cycles end).
+  void $delete(Id $id, Set<Id> $visited) {
+    if (!$visited.add($id)) return;
+    final $store = Dust.instance;
+    $entries.remove($id);
+    $store.setNode($id, null);
+  }
+
+  NodesByLabel<Flagged> all() =>
+      NodesByLabel($FlaggedRepository.Label, const $FlaggedRepository());
+
+  /// Returns the IDs of all objects, without constructing or subscribing to
+  /// any of them (unlike [all]).
+  List<Id> allIds() {
+    final $res = <Id>[];
+    Dust.instance.getNodeByLabel($FlaggedRepository.Label, $res.add);
+    return $res;
+  }
+}
+
+/// The fields of [Flagged], see [$FlaggedRepository.getFields].
+enum $FlaggedField { done }
+
+mixin _$Counter {
+  Id get id;
+  Observable<int> get count$;
+  AtomDefault<int> get _count$;
+
+  void delete();
+
+  /// Calls [f] whenever any field of this object changes, until the returned
+  /// subscription is cancelled (or garbage collected). Changes to backlinks
+  /// are not included.
+  Subscription subscribe(void Function(Counter) f);
+
+  /// Returns a stream which emits this object on listen and whenever
+  /// [subscribe] would call back. Changes while paused are coalesced.
+  Stream<Counter> watch();
+}
+
+final class _Counter extends Counter {
+  @override
+  final Id id;
+
+  _Counter._(this.id, {required AtomDefault<int> count$})
+      : _count$ = count$,
+        super._();
+
+  factory _Counter({
+    int? count,
+  }) {
+    return const $CounterRepository().create(
+      count: count,
+    ) as _Counter;
+  }
+
+  @override
+  final AtomDefault<int> _count$;
+
+  @override
+  Observable<int> get count$ => _count$;
+
+  @override
+  void delete() => const $CounterRepository().delete(this);
+
+  @override
+  Subscription subscribe(void Function(Counter) f) =>
+      Subscription((o) {
+        count$.connect(o);
+      }, () => f(this));
+
+  @override
+  Stream<Counter> watch() => watchStream(this, subscribe);
+
+  @override
+  bool operator ==(Object other) => other is Counter && other.id == id;
+
+  @override
+  int get hashCode => id.hashCode;
+}
+
+class $CounterRepository implements Repository<Counter> {
+  const $CounterRepository();
+
+  static const int Label = -7115751624304594645;
+  static const int countLabel = 2483543033975209796;
+
+  static const countSerializer = IntSerializer();
+
+  /// The shape of [Counter], see [Dust.manifest].
+  static const manifest = ModelInfo(
+    name: 'Counter',
+    label: $CounterRepository.Label,
+    fields: [
+      FieldInfo(
+        name: 'count',
+        kind: FieldKind.atomDefault,
+        type: 'int',
+        label: $CounterRepository.countLabel,
+      ),
+    ],
+  );
+
+  static final Map<Id, WeakReference<NodeOption<Counter>>> $entries = {};
+
+  static bool $init = false;
+
+  @override
+  Schema init() {
+    $init = true;
+    return const Schema(
+      label: $CounterRepository.Label,
+      model: manifest,
+      stickyNodes: [$CounterRepository.Label],
+      stickyAtoms: [],
+      stickyEdges: [],
+      acyclicEdges: [],
+    );
+  }
+
+  @override
+  Id id(Counter $model) => $model.id;
+
+  void $write(
+    Id $id, {
+    int? count,
+  }) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    final $store = Dust.instance;
+
+    $store.setNode($id, $CounterRepository.Label);
+    $store.setAtom(
+      $id ^ $CounterRepository.countLabel,
+      (
+        $id,
+        $CounterRepository.countLabel,
+        count ?? 0,
+        $CounterRepository.countSerializer,
+      ),
+    );
+
+    $store.barrier();
+  }
+
+  Counter create({
+    int? count,
+  }) {
+    final $id = Dust.instance.randomId();
+    final $node = get($id);
+    $write(
+      $id,
+      count: count,
+    );
+    return $node.get(null)!;
+  }
+
+  /// Same as [create], but returns only the ID of the new object, skipping
+  /// the construction of the object itself.
+  Id createId({
+    int? count,
+  }) {
+    final $id = Dust.instance.randomId();
+    $write(
+      $id,
+      count: count,
+    );
+    return $id;
+  }
+
+  /// Same as [createId], but returns a handle which reads the new object
+  /// back only on first access.
+  Created<Counter> createLazy({
+    int? count,
+  }) =>
+      Created(
+          createId(
+            count: count,
+          ),
+          const $CounterRepository());
+
+  /// Same as calling [createId] on each of [$items], but with a single
+  /// [Dust.barrier] call, which is much cheaper for bulk imports.
+  List<Id> createBatch(
+    Iterable<
+            ({
+        int? count,
+      })>
+        $items,
+  ) =>
+      Dust.instance.batch(() => [
+            for (final $item in $items)
+              createId(
+                count: $item.count,
+              ),
+          ]);
+
+  NodeAuto<Counter> auto(
+    Id $id, {
+    int? count,
+  }) {
+    final $node = get($id);
+    return NodeAuto(
+      $node,
+      () => $write(
+        $id,
+        count: count,
+      ),
+    );
+  }
+
+  /// Returns the object with given ID, first creating it from the given
+  /// fields if there is none. Same as calling `get` on [auto].
+  Counter getOrCreate(
+    Id $id, {
+    int? count,
+  }) =>
+      auto(
+        $id,
+        count: count,
+      ).get(null);
+
+  /// Creates a new [Counter] with the same field values as [$model],
+  /// except for the given ones. Given `null`s are ignored, so nullable fields
+  /// can only be cleared after the copy has been created.
+  Counter duplicate(
+    Counter $model, {
+    int? count,
+  }) {
+    return create(
+      count: count ?? $model.count$.get(null),
+    );
+  }
+
+  /// Returns if [$a] and [$b] have the same field values, regardless of
+  /// their IDs (e.g. to detect duplicates on import). Links are compared by
+  /// the IDs of their targets, multilinks and backlinks as sets of these.
+  bool contentEquals(Counter $a, Counter $b) =>
+      serializedEquals($CounterRepository.countSerializer,
+          $a.count$.get(null), $b.count$.get(null));
+
+  /// Writes the given fields of [$model] with a single barrier, leaving the
+  /// others (and their clocks) untouched. As with [duplicate], given `null`s
+  /// are ignored, so nullable fields can only be cleared by their setters.
+  void patch(
+    Counter $model, {
+    int? count,
+  }) {
+    final $id = $model.id;
+    final $store = Dust.instance;
+    if (count != null) {
+      $store.setAtom(
+        $id ^ $CounterRepository.countLabel,
+        (
+          $id,
+          $CounterRepository.countLabel,
+          count,
+          $CounterRepository.countSerializer,
+        ),
+      );
+    }
+
+    $store.barrier();
+  }
+
+  @override
+  NodeOption<Counter> get(Id $id) {
+    final $existing = $entries[$id]?.target;
+    if ($existing != null) return $existing;
+    final $model = _Counter._(
+      $id,
+      count$: AtomDefault<int>(
+        $id ^ $CounterRepository.countLabel,
+        $id,
+        $CounterRepository.countLabel,
+        $CounterRepository.countSerializer,
+        0,
+        lazy: false,
+      ),
+    );
+    final $entry = NodeOption($id, $CounterRepository.Label, $model);
+    $entries[$id] = WeakReference($entry);
+    return $entry;
+  }
+
+  Map<$CounterField, Object?> getFields(Id $id, Iterable<$CounterField> $fields) {
+    final $store = Dust.instance;
+    final $res = <$CounterField, Object?>{};
+    for (final $field in $fields) {
+      switch ($field) {
+        case $CounterField.count:
+          {
+            $store.getAtomById(
+                $id ^ $CounterRepository.countLabel,
+                (slv) => $res[$field] = (slv == null)
+                    ? 0
+                    : $CounterRepository.countSerializer
+                        .deserialize(BytesReader(slv.$3)));
+          }
+      }
+    }
+    return $res;
+  }
+
+  /// Returns the required fields of the object with given ID which have no
+  /// value (e.g. because they have not been fully synchronised yet). If this
+  /// is non-empty, reading these fields of the object will fail.
+  List<$CounterField> missingFields(Id $id) {
+    final $fields = getFields($id, const []);
+    return [
+      for (final MapEntry(:key, :value) in $fields.entries)
+        if (value == null) key
+    ];
+  }
+
+  @override
+  void delete(Counter $model) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    $delete($model.id, {});
+    Dust.instance.barrier();
+  }
+
+  /// Deletes the object with given ID without a barrier, together with the
+  /// objects it owns through `@Cascade()` fields, skipping the ones in
+  /// [$visited] (so that cycles end).
+  void $delete(Id $id, Set<Id> $visited) {
+    if (!$visited.add($id)) return;
+    final $store = Dust.instance;
+    $entries.remove($id);
+    $store.setNode($id, null);
+  }
+
+  NodesByLabel<Counter> all() =>
+      NodesByLabel($CounterRepository.Label, const $CounterRepository());
+
+  /// Returns the IDs of all objects, without constructing or subscribing to
+  /// any of them (unlike [all]).
+  List<Id> allIds() {
+    final $res = <Id>[];
+    Dust.instance.getNodeByLabel($CounterRepository.Label, $res.add);
+    return $res;
+  }
+}
+
+/// The fields of [Counter], see [$CounterRepository.getFields].
+enum $CounterField { count }
+
+mixin _$Board {
+  Id get id;
+  Multilinks<Trivial> get columns$;
+  Multilinks<Board> get children$;
+
+  void delete();
+
+  /// Calls [f] whenever any field of this object changes, until the returned
+  /// subscription is cancelled (or garbage collected). Changes to backlinks
+  /// are not included.
+  Subscription subscribe(void Function(Board) f);
+
+  /// Returns a stream which emits this object on listen and whenever
+  /// [subscribe] would call back. Changes while paused are coalesced.
+  Stream<Board> watch();
+}
+
+final class _Board extends Board {
+  @override
+  final Id id;
+
+  _Board._(this.id, {required this.columns$, required this.children$})
+      : super._();
+
+  factory _Board({
+    Iterable<Trivial> columns = const Iterable.empty(),
+    Iterable<Board> children = const Iterable.empty(),
+  }) {
+    return const $BoardRepository().create(
+      columns: columns,
+      children: children,
+    ) as _Board;
+  }
+
+  @override
+  final Multilinks<Trivial> columns$;
+
+  @override
+  final Multilinks<Board> children$;
+
+  @override
+  void delete() => const $BoardRepository().delete(this);
+
+  @override
+  Subscription subscribe(void Function(Board) f) =>
+      Subscription((o) {
+        columns$.connect(o);
+        children$.connect(o);
+      }, () => f(this));
+
+  @override
+  Stream<Board> watch() => watchStream(this, subscribe);
+
+  @override
+  bool operator ==(Object other) => other is Board && other.id == id;
+
+  @override
+  int get hashCode => id.hashCode;
+}
+
+class $BoardRepository implements Repository<Board> {
+  const $BoardRepository();
+
+  static const int Label = -7470692561692760273;
+  static const int columnsLabel = 7855173215209265888;
+  static const int columnsOrderLabel = 5735398329838916918;
+  static const int childrenLabel = -5747832535487509486;
+
+  /// The shape of [Board], see [Dust.manifest].
+  static const manifest = ModelInfo(
+    name: 'Board',
+    label: $BoardRepository.Label,
+    fields: [
+      FieldInfo(
+        name: 'columns',
+        kind: FieldKind.multilinks,
+        type: 'Trivial',
+        label: $BoardRepository.columnsLabel,
+      ),
+      FieldInfo(
+        name: 'children',
+        kind: FieldKind.multilinks,
+        type: 'Board',
+        label: $BoardRepository.childrenLabel,
+      ),
+    ],
+  );
+
+  static final Map<Id, WeakReference<NodeOption<Board>>> $entries = {};
+
+  static bool $init = false;
+
+  @override
+  Schema init() {
+    $init = true;
+    return const Schema(
+      label: $BoardRepository.Label,
+      model: manifest,
+      stickyNodes: [$BoardRepository.Label],
+      stickyAtoms: [],
+      stickyEdges: [],
+      acyclicEdges: [],
+    );
+  }
+
+  @override
+  Id id(Board $model) => $model.id;
+
+  void $write(
+    Id $id, {
+    Iterable<Trivial> columns = const Iterable.empty(),
+    Iterable<Board> children = const Iterable.empty(),
+  }) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    final $store = Dust.instance;
+    $store.setNode($id, $BoardRepository.Label);
+    Multilinks.write(
+      $id,
+      $BoardRepository.columnsLabel,
+      columns.map(const $TrivialRepository().id),
+      orderLabel: $BoardRepository.columnsOrderLabel,
+    );
+    for (final item in children) {
+      $store.setEdge(
+        $store.edgeId($id),
+        (
+          $id,
+          $BoardRepository.childrenLabel,
+          const $BoardRepository().id(item),
+        ),
+      );
+    }
+
+    $store.barrier();
+  }
+
+  Board create({
+    Iterable<Trivial> columns = const Iterable.empty(),
+    Iterable<Board> children = const Iterable.empty(),
+  }) {
+    final $id = Dust.instance.randomId();
+    final $node = get($id);
+    $write(
+      $id,
+      columns: columns,
+      children: children,
+    );
+    return $node.get(null)!;
+  }
+
+  /// Same as [create], but returns only the ID of the new object, skipping
+  /// the construction of the object itself.
+  Id createId({
+    Iterable<Trivial> columns = const Iterable.empty(),
+    Iterable<Board> children = const Iterable.empty(),
+  }) {
+    final $id = Dust.instance.randomId();
+    $write(
+      $id,
+      columns: columns,
+      children: children,
+    );
+    return $id;
+  }
+
+  /// Same as [createId], but returns a handle which reads the new object
+  /// back only on first access.
+  Created<Board> createLazy({
+    Iterable<Trivial> columns = const Iterable.empty(),
+    Iterable<Board> children = const Iterable.empty(),
+  }) =>
+      Created(
+          createId(
+            columns: columns,
+            children: children,
+          ),
+          const $BoardRepository());
+
+  /// Same as calling [createId] on each of [$items], but with a single
+  /// [Dust.barrier] call, which is much cheaper for bulk imports.
+  List<Id> createBatch(
+    Iterable<
+            ({
+        Iterable<Trivial> columns,
+        Iterable<Board> children,
+      })>
+        $items,
+  ) =>
+      Dust.instance.batch(() => [
+            for (final $item in $items)
+              createId(
+                columns: $item.columns,
+                children: $item.children,
+              ),
+          ]);
+
+  NodeAuto<Board> auto(
+    Id $id, {
+    Iterable<Trivial> columns = const Iterable.empty(),
+    Iterable<Board> children = const Iterable.empty(),
+  }) {
+    final $node = get($id);
+    return NodeAuto(
+      $node,
+      () => $write(
+        $id,
+        columns: columns,
+        children: children,
+      ),
+    );
+  }
+
+  /// Returns the object with given ID, first creating it from the given
+  /// fields if there is none. Same as calling `get` on [auto].
+  Board getOrCreate(
+    Id $id, {
+    Iterable<Trivial> columns = const Iterable.empty(),
+    Iterable<Board> children = const Iterable.empty(),
+  }) =>
+      auto(
+        $id,
+        columns: columns,
+        children: children,
+      ).get(null);
+
+  /// Creates a new [Board] with the same field values as [$model],
+  /// except for the given ones. Given `null`s are ignored, so nullable fields
+  /// can only be cleared after the copy has been created.
+  Board duplicate(
+    Board $model, {
+    Iterable<Trivial>? columns,
+    Iterable<Board>? children,
+  }) {
+    return create(
+      columns: columns ?? $model.columns$.get(null),
+      children: children ?? $model.children$.get(null),
+    );
+  }
+
+  /// Returns if [$a] and [$b] have the same field values, regardless of
+  /// their IDs (e.g. to detect duplicates on import). Links are compared by
+  /// the IDs of their targets, multilinks and backlinks as sets of these.
+  bool contentEquals(Board $a, Board $b) =>
+      idSetEquals($a.columns$.get(null).map((e) => e.id),
+          $b.columns$.get(null).map((e) => e.id)) &&
+      idSetEquals($a.children$.get(null).map((e) => e.id),
+          $b.children$.get(null).map((e) => e.id));
+
+  @override
+  NodeOption<Board> get(Id $id) {
+    final $existing = $entries[$id]?.target;
+    if ($existing != null) return $existing;
+    final $model = _Board._(
+      $id,
+      columns$: Multilinks<Trivial>(
+        $id,
+        $BoardRepository.columnsLabel,
+        const $TrivialRepository(),
+        orderLabel: $BoardRepository.columnsOrderLabel,
+      ),
+      children$: Multilinks<Board>(
+        $id,
+        $BoardRepository.childrenLabel,
+        const $BoardRepository(),
+      ),
+    );
+    final $entry = NodeOption($id, $BoardRepository.Label, $model);
+    $entries[$id] = WeakReference($entry);
+    return $entry;
+  }
+
+  Map<$BoardField, Object?> getFields(Id $id, Iterable<$BoardField> $fields) {
+    final $store = Dust.instance;
+    final $res = <$BoardField, Object?>{};
+    for (final $field in $fields) {
+      switch ($field) {
+        case $BoardField.columns:
+          {
+            final $dsts = <Id>[];
+            $store.getEdgeDstBySrcLabel($id, $BoardRepository.columnsLabel,
+                (_, dst) => $dsts.add(dst));
+            $res[$field] = $dsts;
+          }
+        case $BoardField.children:
+          {
+            final $dsts = <Id>[];
+            $store.getEdgeDstBySrcLabel($id, $BoardRepository.childrenLabel,
+                (_, dst) => $dsts.add(dst));
+            $res[$field] = $dsts;
+          }
+      }
+    }
+    return $res;
+  }
+
+  /// Returns the required fields of the object with given ID which have no
+  /// value (e.g. because they have not been fully synchronised yet). If this
+  /// is non-empty, reading these fields of the object will fail.
+  List<$BoardField> missingFields(Id $id) {
+    final $fields = getFields($id, const []);
+    return [
+      for (final MapEntry(:key, :value) in $fields.entries)
+        if (value == null) key
+    ];
+  }
+
+  @override
+  void delete(Board $model) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    $delete($model.id, {});
+    Dust.instance.barrier();
+  }
+
+  /// Deletes the object with given ID without a barrier, together with the
+  /// objects it owns through `@Cascade()` fields, skipping the ones in
+  /// [$visited] (so that cycles end).
+  void $delete(Id $id, Set<Id> $visited) {
+    if (!$visited.add($id)) return;
+    final $store = Dust.instance;
+    final $owned = <void Function()>[];
+    $store.getEdgeDstBySrcLabel(
+        $id,
+        $BoardRepository.childrenLabel,
+        (_, target) => $owned.add(
+            () => const $BoardRepository().$delete(target, $visited)));
+    $entries.remove($id);
+    $store.setNode($id, null);
+    for (final $f in $owned) $f();
+  }
+
+  NodesByLabel<Board> all() =>
+      NodesByLabel($BoardRepository.Label, const $BoardRepository());
+
+  /// Returns the IDs of all objects, without constructing or subscribing to
+  /// any of them (unlike [all]).
+  List<Id> allIds() {
+    final $res = <Id>[];
+    Dust.instance.getNodeByLabel($BoardRepository.Label, $res.add);
+    return $res;
+  }
+}
+
+/// The fields of [Board], see [$BoardRepository.getFields].
+enum $BoardField { columns, children }
+
+mixin _$Account {
+  Id get id;
+  Atom<String> get email$;
+  AtomOption<String?> get nickname$;
+
+  void delete();
+
+  /// Calls [f] whenever any field of this object changes, until the returned
+  /// subscription is cancelled (or garbage collected). Changes to backlinks
+  /// are not included.
+  Subscription subscribe(void Function(Account) f);
+
+  /// Returns a stream which emits this object on listen and whenever
+  /// [subscribe] would call back. Changes while paused are coalesced.
+  Stream<Account> watch();
+}
+
+final class _Account extends Account {
+  @override
+  final Id id;
+
+  _Account._(this.id, {required this.email$, required this.nickname$})
+      : super._();
+
+  factory _Account({
+    required String email,
+    String? nickname,
+  }) {
+    return const $AccountRepository().create(
+      email: email,
+      nickname: nickname,
+    ) as _Account;
+  }
+
+  @override
+  final Atom<String> email$;
+
+  @override
+  final AtomOption<String?> nickname$;
+
+  @override
+  void delete() => const $AccountRepository().delete(this);
+
+  @override
+  Subscription subscribe(void Function(Account) f) =>
+      Subscription((o) {
+        email$.connect(o);
+        nickname$.connect(o);
+      }, () => f(this));
+
+  @override
+  Stream<Account> watch() => watchStream(this, subscribe);
+
+  @override
+  bool operator ==(Object other) => other is Account && other.id == id;
+
+  @override
+  int get hashCode => id.hashCode;
+}
+
+class $AccountRepository implements Repository<Account> {
+  const $AccountRepository();
+
+  static const int Label = -6122850595538657426;
+  static const int emailLabel = -6230687659001303988;
+  static const int nicknameLabel = -235932824372850344;
+
+  static const emailSerializer = StringSerializer();
+  static const nicknameSerializer = OptionSerializer(StringSerializer());
+
+  /// The shape of [Account], see [Dust.manifest].
+  static const manifest = ModelInfo(
+    name: 'Account',
+    label: $AccountRepository.Label,
+    fields: [
+      FieldInfo(
+        name: 'email',
+        kind: FieldKind.atom,
+        type: 'String',
+        label: $AccountRepository.emailLabel,
+      ),
+      FieldInfo(
+        name: 'nickname',
+        kind: FieldKind.atomOption,
+        type: 'String?',
+        label: $AccountRepository.nicknameLabel,
+      ),
+    ],
+  );
+
+  static final Map<Id, WeakReference<NodeOption<Account>>> $entries = {};
+
+  static bool $init = false;
+
+  @override
+  Schema init() {
+    $init = true;
+    return const Schema(
+      label: $AccountRepository.Label,
+      model: manifest,
+      stickyNodes: [$AccountRepository.Label],
+      stickyAtoms: [$AccountRepository.emailLabel],
+      stickyEdges: [],
+      acyclicEdges: [],
+    );
+  }
+
+  @override
+  Id id(Account $model) => $model.id;
+
+  /// Derives the ID of a new [Account] from its key fields.
+  Id $idFrom(String email) => Id.hash($AccountRepository.Label, ($builder) {
+        $AccountRepository.emailSerializer.serialize(email, $builder);
+      });
+
+  void $write(
+    Id $id, {
+    required String email,
+    String? nickname,
+  }) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    final $store = Dust.instance;
+    $store.setNode($id, $AccountRepository.Label);
+    $store.setAtom(
+      $id ^ $AccountRepository.emailLabel,
+      (
+        $id,
+        $AccountRepository.emailLabel,
+        email,
+        $AccountRepository.emailSerializer,
+      ),
+    );
+
+    if (nickname != null) {
+      $store.setAtom(
+        $id ^ $AccountRepository.nicknameLabel,
+        (
+          $id,
+          $AccountRepository.nicknameLabel,
+          nickname,
+          $AccountRepository.nicknameSerializer,
+        ),
+      );
+    }
+
+    $store.barrier();
+  }
+
+  Account create({
+    required String email,
+    String? nickname,
+  }) {
+    final $id = $idFrom(email);
+    final $node = get($id);
+    $write(
+      $id,
+      email: email,
+      nickname: nickname,
+    );
+    return $node.get(null)!;
+  }
+
+  /// Same as [create], but returns only the ID of the new object, skipping
+  /// the construction of the object itself.
+  Id createId({
+    required String email,
+    String? nickname,
+  }) {
+    final $id = $idFrom(email);
+    $write(
+      $id,
+      email: email,
+      nickname: nickname,
+    );
+    return $id;
+  }
+
+  /// Same as [createId], but returns a handle which reads the new object
+  /// back only on first access.
+  Created<Account> createLazy({
+    required String email,
+    String? nickname,
+  }) =>
+      Created(
+          createId(
+            email: email,
+            nickname: nickname,
+          ),
+          const $AccountRepository());
+
+  /// Same as calling [createId] on each of [$items], but with a single
+  /// [Dust.barrier] call, which is much cheaper for bulk imports.
+  List<Id> createBatch(
+    Iterable<
+            ({
+        String email,
+        String? nickname,
+      })>
+        $items,
+  ) =>
+      Dust.instance.batch(() => [
+            for (final $item in $items)
+              createId(
+                email: $item.email,
+                nickname: $item.nickname,
+              ),
+          ]);
+
+  NodeAuto<Account> auto(
+    Id $id, {
+    required String email,
+    String? nickname,
+  }) {
+    final $node = get($id);
+    return NodeAuto(
+      $node,
+      () => $write(
+        $id,
+        email: email,
+        nickname: nickname,
+      ),
+    );
+  }
+
+  /// Returns the object with given ID, first creating it from the given
+  /// fields if there is none. Same as calling `get` on [auto].
+  Account getOrCreate(
+    Id $id, {
+    required String email,
+    String? nickname,
+  }) =>
+      auto(
+        $id,
+        email: email,
+        nickname: nickname,
+      ).get(null);
+
+  /// Creates a new [Account] with the same field values as [$model],
+  /// except for the given ones. Given `null`s are ignored, so nullable fields
+  /// can only be cleared after the copy has been created.
+  Account duplicate(
+    Account $model, {
+    String? email,
+    String? nickname,
+  }) {
+    return create(
+      email: email ?? $model.email$.get(null),
+      nickname: nickname ?? $model.nickname$.get(null),
+    );
+  }
+
+  /// Returns if [$a] and [$b] have the same field values, regardless of
+  /// their IDs (e.g. to detect duplicates on import). Links are compared by
+  /// the IDs of their targets, multilinks and backlinks as sets of these.
+  bool contentEquals(Account $a, Account $b) =>
+      serializedEquals($AccountRepository.emailSerializer,
+          $a.email$.get(null), $b.email$.get(null)) &&
+      serializedEquals($AccountRepository.nicknameSerializer,
+          $a.nickname$.get(null), $b.nickname$.get(null));
+
+  /// Writes the given fields of [$model] with a single barrier, leaving the
+  /// others (and their clocks) untouched. As with [duplicate], given `null`s
+  /// are ignored, so nullable fields can only be cleared by their setters.
+  void patch(
+    Account $model, {
+    String? nickname,
+  }) {
+    final $id = $model.id;
+    final $store = Dust.instance;
+    if (nickname != null) {
+      $store.setAtom(
+        $id ^ $AccountRepository.nicknameLabel,
+        (
+          $id,
+          $AccountRepository.nicknameLabel,
+          nickname,
+          $AccountRepository.nicknameSerializer,
+        ),
+      );
+    }
+
+    $store.barrier();
+  }
+
+  @override
+  NodeOption<Account> get(Id $id) {
+    final $existing = $entries[$id]?.target;
+    if ($existing != null) return $existing;
+    final $model = _Account._(
+      $id,
+      email$: Atom<String>(
+        $id ^ $AccountRepository.emailLabel,
+        $id,
+        $AccountRepository.emailLabel,
+        $AccountRepository.emailSerializer,
+      ),
+      nickname$: AtomOption<String?>(
+        $id ^ $AccountRepository.nicknameLabel,
+        $id,
+        $AccountRepository.nicknameLabel,
+        $AccountRepository.nicknameSerializer,
+      ),
+    );
+    final $entry = NodeOption($id, $AccountRepository.Label, $model);
+    $entries[$id] = WeakReference($entry);
+    return $entry;
+  }
+
+  Map<$AccountField, Object?> getFields(Id $id, Iterable<$AccountField> $fields) {
+    final $store = Dust.instance;
+    final $res = <$AccountField, Object?>{};
+    for (final $field in $fields) {
+      switch ($field) {
+        case $AccountField.email:
+          {
+            $store.getAtomById(
+                $id ^ $AccountRepository.emailLabel,
+                (slv) => $res[$field] = (slv == null)
+                    ? null
+                    : $AccountRepository.emailSerializer
+                        .deserialize(BytesReader(slv.$3)));
+          }
+        case $AccountField.nickname:
+          {
+            $store.getAtomById(
+                $id ^ $AccountRepository.nicknameLabel,
+                (slv) => $res[$field] = (slv == null)
+                    ? null
+                    : $AccountRepository.nicknameSerializer
+                        .deserialize(BytesReader(slv.$3)));
+          }
+      }
+    }
+    return $res;
+  }
+
+  /// Returns the required fields of the object with given ID which have no
+  /// value (e.g. because they have not been fully synchronised yet). If this
+  /// is non-empty, reading these fields of the object will fail.
+  List<$AccountField> missingFields(Id $id) {
+    final $fields = getFields($id, const [
+      $AccountField.email,
+    ]);
+    return [
+      for (final MapEntry(:key, :value) in $fields.entries)
+        if (value == null) key
+    ];
+  }
+
+  @override
+  void delete(Account $model) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    $delete($model.id, {});
+    Dust.instance.barrier();
+  }
+
+  /// Deletes the object with given ID without a barrier, together with the
+  /// objects it owns through `@Cascade()` fields, skipping the ones in
+  /// [$visited] (so that cycles end).
+  void $delete(Id $id, Set<Id> $visited) {
+    if (!$visited.add($id)) return;
+    final $store = Dust.instance;
+    $entries.remove($id);
+    $store.setNode($id, null);
+  }
+
+  NodesByLabel<Account> all() =>
+      NodesByLabel($AccountRepository.Label, const $AccountRepository());
+
+  /// Returns the IDs of all objects, without constructing or subscribing to
+  /// any of them (unlike [all]).
+  List<Id> allIds() {
+    final $res = <Id>[];
+    Dust.instance.getNodeByLabel($AccountRepository.Label, $res.add);
+    return $res;
+  }
+}
+
+/// The fields of [Account], see [$AccountRepository.getFields].
+enum $AccountField { email, nickname }
+
+mixin _$Migrated {
+  Id get id;
+  Atom<String> get name$;
+  Atom<String> get note$;
+
+  void delete();
+
+  /// Calls [f] whenever any field of this object changes, until the returned
+  /// subscription is cancelled (or garbage collected). Changes to backlinks
+  /// are not included.
+  Subscription subscribe(void Function(Migrated) f);
+
+  /// Returns a stream which emits this object on listen and whenever
+  /// [subscribe] would call back. Changes while paused are coalesced.
+  Stream<Migrated> watch();
+}
+
+final class _Migrated extends Migrated {
+  @override
+  final Id id;
+
+  _Migrated._(this.id, {required this.name$, required this.note$})
+      : super._();
+
+  factory _Migrated({
+    required String name,
+    required String note,
+  }) {
+    return const $MigratedRepository().create(
+      name: name,
+      note: note,
+    ) as _Migrated;
+  }
+
+  @override
+  final Atom<String> name$;
+
+  @override
+  final Atom<String> note$;
+
+  @override
+  void delete() => const $MigratedRepository().delete(this);
+
+  @override
+  Subscription subscribe(void Function(Migrated) f) =>
+      Subscription((o) {
+        name$.connect(o);
+        note$.connect(o);
+      }, () => f(this));
+
+  @override
+  Stream<Migrated> watch() => watchStream(this, subscribe);
+
+  @override
+  bool operator ==(Object other) => other is Migrated && other.id == id;
+
+  @override
+  int get hashCode => id.hashCode;
+}
+
+class $MigratedRepository implements Repository<Migrated> {
+  const $MigratedRepository();
+
+  static const int Label = -4570302554373199624;
+  static const int nameLabel = 7704249460296938787;
+  static const int noteLabel = 7710165932367206826;
+
+  static const nameSerializer = StringSerializer();
+  static const noteSerializer = StringSerializer();
+
+  /// The shape of [Migrated], see [Dust.manifest].
+  static const manifest = ModelInfo(
+    name: 'Migrated',
+    label: $MigratedRepository.Label,
+    fields: [
+      FieldInfo(
+        name: 'name',
+        kind: FieldKind.atom,
+        type: 'String',
+        label: $MigratedRepository.nameLabel,
+        readonly: true,
+      ),
+      FieldInfo(
+        name: 'note',
+        kind: FieldKind.atom,
+        type: 'String',
+        label: $MigratedRepository.noteLabel,
+        hidden: true,
+      ),
+    ],
+  );
+
+  static final Map<Id, WeakReference<NodeOption<Migrated>>> $entries = {};
+
+  static bool $init = false;
+
+  @override
+  Schema init() {
+    $init = true;
+    return const Schema(
+      label: $MigratedRepository.Label,
+      model: manifest,
+      stickyNodes: [$MigratedRepository.Label],
+      stickyAtoms: [
+        $MigratedRepository.nameLabel,
+        $MigratedRepository.noteLabel
+      ],
+      stickyEdges: [],
+      acyclicEdges: [],
+    );
+  }
+
+  @override
+  Id id(Migrated $model) => $model.id;
+
+  void $write(
+    Id $id, {
+    required String name,
+    required String note,
+  }) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    final $store = Dust.instance;
+    $store.setNode($id, $MigratedRepository.Label);
+    $store.setAtom(
+      $id ^ $MigratedRepository.nameLabel,
+      (
+        $id,
+        $MigratedRepository.nameLabel,
+        name,
+        $MigratedRepository.nameSerializer,
+      ),
+    );
+
+    $store.setAtom(
+      $id ^ $MigratedRepository.noteLabel,
+      (
+        $id,
+        $MigratedRepository.noteLabel,
+        note,
+        $MigratedRepository.noteSerializer,
+      ),
+    );
+
+    $store.barrier();
+  }
+
+  Migrated create({
+    required String name,
+    required String note,
+  }) {
+    final $id = Dust.instance.randomId();
+    final $node = get($id);
+    $write(
+      $id,
+      name: name,
+      note: note,
+    );
+    return $node.get(null)!;
+  }
+
+  /// Same as [create], but returns only the ID of the new object, skipping
+  /// the construction of the object itself.
+  Id createId({
+    required String name,
+    required String note,
+  }) {
+    final $id = Dust.instance.randomId();
+    $write(
+      $id,
+      name: name,
+      note: note,
+    );
+    return $id;
+  }
+
+  /// Same as [createId], but returns a handle which reads the new object
+  /// back only on first access.
+  Created<Migrated> createLazy({
+    required String name,
+    required String note,
+  }) =>
+      Created(
+          createId(
+            name: name,
+            note: note,
+          ),
+          const $MigratedRepository());
+
+  /// Same as calling [createId] on each of [$items], but with a single
+  /// [Dust.barrier] call, which is much cheaper for bulk imports.
+  List<Id> createBatch(
+    Iterable<
+            ({
+        String name,
+        String note,
+      })>
+        $items,
+  ) =>
+      Dust.instance.batch(() => [
+            for (final $item in $items)
+              createId(
+                name: $item.name,
+                note: $item.note,
+              ),
+          ]);
+
+  NodeAuto<Migrated> auto(
+    Id $id, {
+    required String name,
+    required String note,
+  }) {
+    final $node = get($id);
+    return NodeAuto(
+      $node,
+      () => $write(
+        $id,
+        name: name,
+        note: note,
+      ),
+    );
+  }
+
+  /// Returns the object with given ID, first creating it from the given
+  /// fields if there is none. Same as calling `get` on [auto].
+  Migrated getOrCreate(
+    Id $id, {
+    required String name,
+    required String note,
+  }) =>
+      auto(
+        $id,
+        name: name,
+        note: note,
+      ).get(null);
+
+  /// Creates a new [Migrated] with the same field values as [$model],
+  /// except for the given ones. Given `null`s are ignored, so nullable fields
+  /// can only be cleared after the copy has been created.
+  Migrated duplicate(
+    Migrated $model, {
+    String? name,
+    String? note,
+  }) {
+    return create(
+      name: name ?? $model.name$.get(null),
+      note: note ?? $model.note$.get(null),
+    );
+  }
+
+  /// Returns if [$a] and [$b] have the same field values, regardless of
+  /// their IDs (e.g. to detect duplicates on import). Links are compared by
+  /// the IDs of their targets, multilinks and backlinks as sets of these.
+  bool contentEquals(Migrated $a, Migrated $b) =>
+      serializedEquals($MigratedRepository.nameSerializer,
+          $a.name$.get(null), $b.name$.get(null)) &&
+      serializedEquals($MigratedRepository.noteSerializer,
+          $a.note$.get(null), $b.note$.get(null));
+
+  /// Writes the given fields of [$model] with a single barrier, leaving the
+  /// others (and their clocks) untouched. As with [duplicate], given `null`s
+  /// are ignored, so nullable fields can only be cleared by their setters.
+  void patch(
+    Migrated $model, {
+    String? name,
+    String? note,
+  }) {
+    final $id = $model.id;
+    final $store = Dust.instance;
+    if (name != null) {
+      $store.setAtom(
+        $id ^ $MigratedRepository.nameLabel,
+        (
+          $id,
+          $MigratedRepository.nameLabel,
+          name,
+          $MigratedRepository.nameSerializer,
+        ),
+      );
+    }
+
+    if (note != null) {
+      $store.setAtom(
+        $id ^ $MigratedRepository.noteLabel,
+        (
+          $id,
+          $MigratedRepository.noteLabel,
+          note,
+          $MigratedRepository.noteSerializer,
+        ),
+      );
+    }
+
+    $store.barrier();
+  }
+
+  @override
+  NodeOption<Migrated> get(Id $id) {
+    final $existing = $entries[$id]?.target;
+    if ($existing != null) return $existing;
+    final $model = _Migrated._(
+      $id,
+      name$: Atom<String>(
+        $id ^ $MigratedRepository.nameLabel,
+        $id,
+        $MigratedRepository.nameLabel,
+        $MigratedRepository.nameSerializer,
+      ),
+      note$: Atom<String>(
+        $id ^ $MigratedRepository.noteLabel,
+        $id,
+        $MigratedRepository.noteLabel,
+        $MigratedRepository.noteSerializer,
+        fallback: 'none',
+      ),
+    );
+    final $entry = NodeOption($id, $MigratedRepository.Label, $model);
+    $entries[$id] = WeakReference($entry);
+    return $entry;
+  }
+
+  Map<$MigratedField, Object?> getFields(Id $id, Iterable<$MigratedField> $fields) {
+    final $store = Dust.instance;
+    final $res = <$MigratedField, Object?>{};
+    for (final $field in $fields) {
+      switch ($field) {
+        case $MigratedField.name:
+          {
+            $store.getAtomById(
+                $id ^ $MigratedRepository.nameLabel,
+                (slv) => $res[$field] = (slv == null)
+                    ? null
+                    : $MigratedRepository.nameSerializer
+                        .deserialize(BytesReader(slv.$3)));
+          }
+        case $MigratedField.note:
+          {
+            $store.getAtomById(
+                $id ^ $MigratedRepository.noteLabel,
+                (slv) => $res[$field] = (slv == null)
+                    ? 'none'
+                    : $MigratedRepository.noteSerializer
+                        .deserialize(BytesReader(slv.$3)));
+          }
+      }
+    }
+    return $res;
+  }
+
+  /// Returns the required fields of the object with given ID which have no
+  /// value (e.g. because they have not been fully synchronised yet). If this
+  /// is non-empty, reading these fields of the object will fail.
+  List<$MigratedField> missingFields(Id $id) {
+    final $fields = getFields($id, const [
+      $MigratedField.name,
+    ]);
+    return [
+      for (final MapEntry(:key, :value) in $fields.entries)
+        if (value == null) key
+    ];
+  }
+
+  @override
+  void delete(Migrated $model) {
+    assert($init, 'Repository should be registered in `Dust.open`.');
+    $delete($model.id, {});
+    Dust.instance.barrier();
+  }
+
+  /// Deletes the object with given ID without a barrier, together with the
+  /// objects it owns through `@Cascade()` fields, skipping the ones in
+  /// [$visited] (so that cycles end).
+  void $delete(Id $id, Set<Id> $visited) {
+    if (!$visited.add($id)) return;
+    final $store = Dust.instance;
+    $entries.remove($id);
+    $store.setNode($id, null);
+  }
+
+  NodesByLabel<Migrated> all() =>
+      NodesByLabel($MigratedRepository.Label, const $MigratedRepository());
+
+  /// Returns the IDs of all objects, without constructing or subscribing to
+  /// any of them (unlike [all]).
+  List<Id> allIds() {
+    final $res = <Id>[];
+    Dust.instance.getNodeByLabel($MigratedRepository.Label, $res.add);
+    return $res;
+  }
+}
+
+/// The fields of [Migrated], see [$MigratedRepository.getFields].
+enum $MigratedField { name, note }
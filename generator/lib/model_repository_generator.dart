@@ -16,6 +16,14 @@ const kSerializerAnnot = TypeChecker.fromRuntime(Serializer);
 const kAcyclicAnnot = TypeChecker.fromRuntime(Acyclic);
 const kDefaultAnnot = TypeChecker.fromRuntime(Dft);
 const kGlobalAnnot = TypeChecker.fromRuntime(Glb);
+const kLazyAnnot = TypeChecker.fromRuntime(Lazy);
+const kDisplayAnnot = TypeChecker.fromRuntime(Display);
+const kOrderedAnnot = TypeChecker.fromRuntime(Ordered);
+const kDistinctAnnot = TypeChecker.fromRuntime(Distinct);
+const kAddedInAnnot = TypeChecker.fromRuntime(AddedIn);
+const kSensitiveAnnot = TypeChecker.fromRuntime(Sensitive);
+const kBindingsAnnot = TypeChecker.fromRuntime(Bindings);
+const kCascadeAnnot = TypeChecker.fromRuntime(Cascade);
 
 const kActiveName = 'Active';
 const kAtomName = 'Atom';
@@ -26,6 +34,21 @@ const kLinkName = 'Link';
 const kLinkOptionName = 'LinkOption';
 const kMultilinksName = 'Multilinks';
 
+/// Names of the fields added by `@Model(timestamps: true)`.
+const kCreatedAtName = 'createdAt';
+const kUpdatedAtName = 'updatedAt';
+const kTimestampNames = [kCreatedAtName, kUpdatedAtName];
+
+/// Names of the fields which are generated for a model with the given
+/// options, and so cannot be declared, mapped to why.
+Map<String, String> reservedFieldNames({required bool timestamps}) => {
+      'id': 'It will be automatically generated for you.',
+      if (timestamps)
+        for (final name in kTimestampNames)
+          name: 'It is automatically generated when timestamps are enabled '
+              '(`@Model(timestamps: true)`).',
+    };
+
 /// Suppressed lints.
 const kIgnoreForFile = [
   'duplicate_ignore',
@@ -53,11 +76,29 @@ Field? convertField(ParameterElement elem) {
   final name = elem.name;
   final type = convertType(elem);
   print('Field: $name, $type');
-  return Field(name, type);
+  final bindings = kBindingsAnnot.firstAnnotationOfExact(elem);
+  final cascade = kCascadeAnnot.hasAnnotationOfExact(elem);
+  if (cascade &&
+      type is! LinkType &&
+      type is! LinkOptionType &&
+      type is! MultilinksType &&
+      type is! BacklinksType) {
+    fail('Cascade annotation is only supported for links and backlinks.', elem);
+  }
+  return Field(name, type,
+      readonly: bindings?.getField('readonly')?.toBoolValue() ?? false,
+      hidden: bindings?.getField('hidden')?.toBoolValue() ?? false,
+      cascade: cascade);
 }
 
 /// Converts [ClassElement] to [Struct].
-Future<Struct> convertStruct(ClassElement elem, BuildStep step) async {
+Future<Struct> convertStruct(
+  ClassElement elem,
+  BuildStep step, {
+  required bool timestamps,
+  required bool encapsulate,
+  required List<String> idFrom,
+}) async {
   Never failUnnamedPubFactory() {
     fail(
       'Class must have a public unnamed factory redirecting to `_${elem.name}`.',
@@ -88,18 +129,41 @@ Future<Struct> convertStruct(ClassElement elem, BuildStep step) async {
 
   final cstor = unnamedPublicFactory;
   final fields = <Field>[];
-  if (cstor.parameters.any((element) => element.name == 'id')) {
-    fail(
-      'Field `id` is reserved and cannot be used. It will be automatically '
-      'generated for you.',
-      cstor,
-    );
-  }
+  final reserved = reservedFieldNames(timestamps: timestamps);
+  String? display;
   for (final (_, e) in cstor.parameters.indexed) {
+    if (reserved[e.name] case final reason?) {
+      fail('Field `${e.name}` is reserved and cannot be used. $reason', e);
+    }
     final field = convertField(e);
     if (field != null) fields.add(field);
+    if (kDisplayAnnot.hasAnnotationOfExact(e)) {
+      if (display != null) {
+        fail('Only one field may be annotated with @Display().', e);
+      }
+      if (field?.type case AtomType(:final type) when type.isDartCoreString) {
+        display = e.name;
+      } else {
+        fail('Display annotation is only supported for `String` atoms.', e);
+      }
+    }
+  }
+  if (timestamps) {
+    for (final name in kTimestampNames) {
+      fields.add(Field(name, TimestampType(elem.library.typeProvider.intType)));
+    }
+  }
+  for (final key in idFrom) {
+    final field = fields.where((e) => e.name == key).firstOrNull;
+    if (field?.type is! AtomType) {
+      fail('Field `$key` in `idFrom` must be a required atom field.', elem);
+    }
   }
-  return Struct(name, fields);
+  return Struct(name, fields,
+      timestamps: timestamps,
+      encapsulate: encapsulate,
+      display: display,
+      idFrom: idFrom);
 }
 
 /// Converts [DartType] to [FieldType].
@@ -111,8 +175,14 @@ FieldType convertType(ParameterElement elem) {
   final type = resolve(elem.type, elem, allowNullable: true);
   final sticky = kStickyAnnot.hasAnnotationOfExact(elem);
   final acyclic = kAcyclicAnnot.hasAnnotationOfExact(elem);
+  final lazy = kLazyAnnot.hasAnnotationOfExact(elem);
+  final sensitive = kSensitiveAnnot.hasAnnotationOfExact(elem);
+  final ordered = kOrderedAnnot.hasAnnotationOfExact(elem);
+  final distinct = kDistinctAnnot.hasAnnotationOfExact(elem);
   final dft = kDefaultAnnot.checkExtractOneOrNull(elem, typeName: 'Dft');
   final ln = kLinkAnnot.checkExtractOneOrNull(elem, typeName: 'Ln');
+  final addedIn =
+      kAddedInAnnot.checkExtractOneOrNull(elem, typeName: 'AddedIn');
   if (dft == null && !elem.isRequired && !type.isNullable && ln == null) {
     fail(
       'Field must have a default value if it is not required and not nullable.',
@@ -126,14 +196,45 @@ FieldType convertType(ParameterElement elem) {
     return (value, ty);
   });
   if (ln != null) {
+    if (addedIn != null) {
+      fail('AddedIn annotation is only supported for required atoms.', elem);
+    }
+    if (lazy) {
+      fail('Lazy annotation is only supported for atoms.', elem);
+    }
+    if (sensitive) {
+      fail('Sensitive annotation is only supported for atoms.', elem);
+    }
     return convertLinkType(ln, type, elem,
-        fieldOpt: fieldOpt, sticky: sticky, acyclic: acyclic);
+        fieldOpt: fieldOpt,
+        sticky: sticky,
+        acyclic: acyclic,
+        ordered: ordered,
+        distinct: distinct);
   }
   if (acyclic) {
     fail('Acyclic annotation is only supported for links.', elem);
   }
+  if (ordered) {
+    fail('Ordered annotation is only supported for multilinks.', elem);
+  }
+  if (distinct) {
+    fail('Distinct annotation is only supported for multilinks.', elem);
+  }
   // TODO: add better support for list types.
   final serializer = tryConvertSerializer(serializers, type, elem);
+  if (addedIn != null) {
+    if (fieldOpt || !elem.isRequired || dft != null) {
+      fail('AddedIn annotation is only supported for required atoms.', elem);
+    }
+    final value = addedIn.getField('defaultValue');
+    final fallback = (value != null) ? construct(value, elem) : null;
+    if (fallback == null) {
+      fail('Default value must be specified!', elem);
+    }
+    return AtomType(type, serializer,
+        lazy: lazy, sensitive: sensitive, fallback: fallback);
+  }
   if (dft != null) {
     final value = dft.getField('defaultValue');
     // print out the value
@@ -141,12 +242,38 @@ FieldType convertType(ParameterElement elem) {
     if (defaultValue == null) {
       fail('Default value must be specified!', elem);
     }
-    return AtomDefaultType(type, serializer, defaultValue, sticky: sticky);
+    return AtomDefaultType(type, serializer, defaultValue,
+        sticky: sticky, lazy: lazy, sensitive: sensitive);
   }
   if (fieldOpt) {
-    return AtomOptionType(type, serializer, sticky: sticky);
+    return AtomOptionType(type, serializer,
+        sticky: sticky, lazy: lazy, sensitive: sensitive);
+  }
+  return AtomType(type, serializer, lazy: lazy, sensitive: sensitive);
+}
+
+/// Checks that each of [fields] is a (forward) link field of [inner], so that
+/// typos in `backTo` are reported at build time, instead of silently giving
+/// empty backlinks.
+void checkBacklinkTargets(
+    InterfaceType inner, List<String> fields, ParameterElement elem) {
+  final cstor = inner.element.constructors
+      .where((e) => e.name == '' && e.isPublic)
+      .firstOrNull;
+  // A missing constructor is reported when generating for [inner] itself.
+  if (cstor == null) return;
+  for (final name in fields) {
+    final param = cstor.parameters.where((e) => e.name == name).firstOrNull;
+    final ln = (param == null) ? null : kLinkAnnot.firstAnnotationOfExact(param);
+    if (ln == null ||
+        ln.getField('backTo')?.isNull != true ||
+        ln.getField('backToAny')?.isNull != true) {
+      fail(
+          'Backlink target `${inner.element.name}.$name` does not exist or is '
+          'not a link field.',
+          elem);
+    }
   }
-  return AtomType(type, serializer);
 }
 
 FieldType convertLinkType(
@@ -156,10 +283,28 @@ FieldType convertLinkType(
   required bool fieldOpt,
   required bool sticky,
   required bool acyclic,
+  required bool ordered,
+  required bool distinct,
 }) {
   final backTo = ln.getField('backTo');
+  final backToAny = ln.getField('backToAny');
+  if (backTo?.isNull != true && backToAny?.isNull != true) {
+    fail('At most one of `backTo` and `backToAny` may be specified.', elem);
+  }
+  if (ordered &&
+      (!type.isDartCoreList ||
+          backTo?.isNull != true ||
+          backToAny?.isNull != true)) {
+    fail('Ordered annotation is only supported for multilinks.', elem);
+  }
+  if (distinct &&
+      (!type.isDartCoreList ||
+          backTo?.isNull != true ||
+          backToAny?.isNull != true)) {
+    fail('Distinct annotation is only supported for multilinks.', elem);
+  }
   if (!type.isDartCoreList) {
-    if (backTo?.isNull != true) {
+    if (backTo?.isNull != true || backToAny?.isNull != true) {
       fail('Backlinks must be a list of objects, but found: $backTo', elem);
     }
     if (fieldOpt) {
@@ -185,10 +330,24 @@ FieldType convertLinkType(
           'string of the name of the field to which the backlink points.',
           elem);
     }
-    return BacklinksType(inner, s);
+    checkBacklinkTargets(inner, [s], elem);
+    return BacklinksType(inner, [s]);
+  }
+  if (backToAny?.isNull != true) {
+    final fields =
+        backToAny?.toListValue()?.map((e) => e.toStringValue()).toList();
+    if (fields == null || fields.isEmpty || fields.contains(null)) {
+      fail(
+          'In a @Ln annotation, when `backToAny` is specified, it must be a '
+          'non-empty list of names of the fields to which the backlink points.',
+          elem);
+    }
+    checkBacklinkTargets(inner, fields.cast<String>(), elem);
+    return BacklinksType(inner, fields.cast<String>());
   }
   // TODO: add support for optionality, not terrible important right now
-  return MultilinksType(inner, sticky: sticky, acyclic: acyclic);
+  return MultilinksType(inner,
+      sticky: sticky, acyclic: acyclic, ordered: ordered, distinct: distinct);
 }
 
 /// The [annots] are the list of annotations that are serializers and are
@@ -301,6 +460,14 @@ String emitAllFunction(Struct struct) {
   return '''
     NodesByLabel<${struct.name}> all() => NodesByLabel(${label(struct.name)}, 
       const ${repository(struct.name)}());
+
+    /// Returns the IDs of all objects, without constructing or subscribing to
+    /// any of them (unlike [all]).
+    List<Id> allIds() {
+      final \$res = <Id>[];
+      Dust.instance.getNodeByLabel(${label(struct.name)}, \$res.add);
+      return \$res;
+    }
   ''';
 }
 
@@ -321,35 +488,48 @@ String emitCreateFunctionArgs(Struct struct, {required bool includeLinks}) {
           sb.write('$name: $name,');
         }
         break;
+      case TimestampType():
+        break;
     }
   }
   return sb.toString();
 }
 
+/// The named argument passing `@Sensitive()` on to `Dust.setAtom`, if set.
+String sensitiveArg(bool sensitive) => sensitive ? ' sensitive: true,' : '';
+
 String emitCreateFunctionBody(Struct struct) {
   final sb = StringBuffer();
   for (final field in struct.fields) {
     final name = field.name;
     final lab = label(struct.name, field.name);
     switch (field.type) {
-      case AtomType():
+      case AtomType(:final sensitive):
         sb.writeln(
           '''
           \$store.setAtom(\$id ^ $lab, (\$id, $lab, $name, 
-          ${serializer(struct.name, field.name)},),);
+          ${serializer(struct.name, field.name)},),${sensitiveArg(sensitive)});
           ''',
         );
         break;
-      case AtomOptionType() || AtomDefaultType():
+      case AtomOptionType(:final sensitive):
         sb.writeln(
           '''
           if ($name != null) {
             \$store.setAtom(\$id ^ $lab, (\$id, $lab, $name, 
-            ${serializer(struct.name, field.name)},),);
+            ${serializer(struct.name, field.name)},),${sensitiveArg(sensitive)});
           }
           ''',
         );
         break;
+      case AtomDefaultType(:final defaultValue, :final sensitive):
+        sb.writeln(
+          '''
+          \$store.setAtom(\$id ^ $lab, (\$id, $lab, $name ?? $defaultValue, 
+          ${serializer(struct.name, field.name)},),${sensitiveArg(sensitive)});
+          ''',
+        );
+        break;
       case LinkType():
         sb.writeln(
           '''
@@ -366,6 +546,14 @@ String emitCreateFunctionBody(Struct struct) {
           ''',
         );
         break;
+      case TimestampType():
+        sb.writeln(
+          '''
+          \$store.setAtom(\$id ^ $lab, (\$id, $lab, \$now, 
+          ${serializer(struct.name, field.name)},),);
+          ''',
+        );
+        break;
       default:
         // do nothing
         break;
@@ -380,10 +568,13 @@ String emitCreateFunctionParams(Struct struct, {required bool includeLinks}) {
     final name = field.name;
     switch (field.type) {
       case AtomOptionType(type: final inner) ||
-            AtomDefaultType(type: final inner) ||
             LinkOptionType(type: final inner):
         sb.write('$inner $name,');
         break;
+      case AtomDefaultType(type: final inner):
+        // Omitted arguments are initialised with the default value.
+        sb.write('${inner.isNullable ? '$inner' : '$inner?'} $name,');
+        break;
       case LinkType(type: final inner) || AtomType(type: final inner):
         sb.write('required $inner $name,');
         break;
@@ -393,6 +584,8 @@ String emitCreateFunctionParams(Struct struct, {required bool includeLinks}) {
           sb.write('Iterable<$inner> $name = const Iterable.empty(),');
         }
         break;
+      case TimestampType():
+        break;
     }
   }
   if (sb.length == 1) return '';
@@ -400,16 +593,27 @@ String emitCreateFunctionParams(Struct struct, {required bool includeLinks}) {
   return sb.toString();
 }
 
-String emitCreateFunctionLinksLogic(String res, Struct struct) {
+/// Creates the edges for multilinks fields, in the same barrier as the node.
+String emitCreateFunctionLinksBody(Struct struct) {
   final sb = StringBuffer();
   for (final field in struct.fields) {
     switch (field.type) {
-      case MultilinksType():
+      case MultilinksType(type: final inner, ordered: true):
         final name = field.name;
+        final lab = label(struct.name, name);
+        sb.writeln(
+          'Multilinks.write(\$id, $lab, '
+          '$name.map(const ${repository(inner.element.name)}().id), '
+          'orderLabel: ${orderLabel(struct.name, name)},);',
+        );
+      case MultilinksType(type: final inner):
+        final name = field.name;
+        final lab = label(struct.name, name);
         sb.writeln(
           '''
           for (final item in $name) {
-            $res.$name\$.insert(item);
+            \$store.setEdge(\$store.edgeId(\$id), (\$id, $lab, 
+            const ${repository(inner.element.name)}().id(item),),);
           }
           ''',
         );
@@ -420,53 +624,277 @@ String emitCreateFunctionLinksLogic(String res, Struct struct) {
   return sb.toString();
 }
 
+/// Creates the function deriving IDs from the `idFrom` fields, if any.
+String emitIdFrom(Struct struct) {
+  if (struct.idFrom.isEmpty) return '';
+  final params = struct.idFrom.map((name) {
+    final type = struct.fields.firstWhere((e) => e.name == name).type;
+    return '${(type as AtomType).type} $name';
+  });
+  final writes = struct.idFrom.map(
+      (name) => '${serializer(struct.name, name)}.serialize($name, \$builder);');
+  return '''
+    /// Derives the ID of a new [${struct.name}] from its key fields.
+    Id \$idFrom(${params.join(', ')}) =>
+        Id.hash(${label(struct.name)}, (\$builder) {
+          ${writes.join('\n')}
+        });
+  ''';
+}
+
 /// Creates the functions that create new [struct]s.
 String emitCreateFunctions(Struct struct) {
-  final essentialParams = emitCreateFunctionParams(struct, includeLinks: false);
-  final essentialArgs = emitCreateFunctionArgs(struct, includeLinks: false);
   final allParams = emitCreateFunctionParams(struct, includeLinks: true);
-  final linksCreationBody = emitCreateFunctionLinksLogic('\$res', struct);
+  final allArgs = emitCreateFunctionArgs(struct, includeLinks: true);
+  final newId = struct.idFrom.isEmpty
+      ? 'Dust.instance.randomId()'
+      : '\$idFrom(${struct.idFrom.join(', ')})';
   return '''
-    void \$write(Id \$id, $essentialParams) {
+    ${emitIdFrom(struct)}
+
+    void \$write(Id \$id, $allParams) {
       assert(\$init, 'Repository should be registered in `Dust.open`.');
       final \$store = Dust.instance;
+      ${struct.timestamps ? 'final \$now = Dust.clock();' : ''}
       \$store.setNode(\$id, ${label(struct.name)});
       ${emitCreateFunctionBody(struct)}
+      ${emitCreateFunctionLinksBody(struct)}
       \$store.barrier();
     }
 
     ${struct.name} create($allParams) {
-      final \$id = Dust.instance.randomId();
+      final \$id = $newId;
       final \$node = get(\$id);
-      \$write(\$id, $essentialArgs);
-      final \$res = \$node.get(null)!;
-      $linksCreationBody
-      return \$res;
+      \$write(\$id, $allArgs);
+      return \$node.get(null)!;
     }
 
+    /// Same as [create], but returns only the ID of the new object, skipping
+    /// the construction of the object itself.
+    Id createId($allParams) {
+      final \$id = $newId;
+      \$write(\$id, $allArgs);
+      return \$id;
+    }
+
+    /// Same as [createId], but returns a handle which reads the new object
+    /// back only on first access.
+    Created<${struct.name}> createLazy($allParams) =>
+        Created(createId($allArgs), const ${repository(struct.name)}());
+
     NodeAuto<${struct.name}> auto(Id \$id, $allParams) {
       final \$node = get(\$id);
-      return NodeAuto(
-        \$node, 
-        () => \$write(\$id, $essentialArgs),
-        (\$res) {
-          $linksCreationBody
-        },
-      );
+      return NodeAuto(\$node, () => \$write(\$id, $allArgs));
+    }
+
+    /// Returns the object with given ID, first creating it from the given
+    /// fields if there is none. Same as calling `get` on [auto].
+    ${struct.name} getOrCreate(Id \$id, $allParams) =>
+        auto(\$id, $allArgs).get(null);
+  ''';
+}
+
+/// Creates the function that creates many objects in one [Dust.batch], taking
+/// a record of the creation arguments for each of them.
+String emitCreateBatchFunction(Struct struct) {
+  final types = StringBuffer();
+  final args = StringBuffer();
+  for (final field in struct.fields) {
+    final name = field.name;
+    switch (field.type) {
+      case AtomType(type: final inner) ||
+            AtomOptionType(type: final inner) ||
+            LinkType(type: final inner) ||
+            LinkOptionType(type: final inner):
+        types.write('$inner $name,');
+      case AtomDefaultType(type: final inner):
+        types.write('${inner.isNullable ? '$inner' : '$inner?'} $name,');
+      case MultilinksType(type: final inner):
+        types.write('Iterable<$inner> $name,');
+      case BacklinksType() || TimestampType():
+        continue;
+    }
+    args.write('$name: \$item.$name,');
+  }
+  if (types.isEmpty) return '';
+  return '''
+    /// Same as calling [createId] on each of [\$items], but with a single
+    /// [Dust.barrier] call, which is much cheaper for bulk imports.
+    List<Id> createBatch(Iterable<({$types})> \$items) =>
+        Dust.instance.batch(() => [
+              for (final \$item in \$items) createId($args),
+            ]);
+  ''';
+}
+
+/// Creates the function that copies an existing struct into a new one, with
+/// some fields optionally replaced. Multilinks are copied as new edges, while
+/// backlinks and timestamps are not copied.
+String emitDuplicateFunction(Struct struct) {
+  final params = StringBuffer();
+  final args = StringBuffer();
+  for (final field in struct.fields) {
+    final name = field.name;
+    switch (field.type) {
+      case AtomOptionType(type: final inner) ||
+            LinkOptionType(type: final inner):
+        params.write('$inner $name,');
+        args.write('$name: $name ?? \$model.$name\$.get(null),');
+        break;
+      case AtomType(type: final inner) ||
+            AtomDefaultType(type: final inner) ||
+            LinkType(type: final inner):
+        params.write('${inner.isNullable ? '$inner' : '$inner?'} $name,');
+        args.write('$name: $name ?? \$model.$name\$.get(null),');
+        break;
+      case MultilinksType(type: final inner):
+        params.write('Iterable<$inner>? $name,');
+        args.write('$name: $name ?? \$model.$name\$.get(null),');
+        break;
+      case BacklinksType() || TimestampType():
+        break;
+    }
+  }
+  final allParams = params.isEmpty ? '' : ', {$params}';
+  return '''
+    /// Creates a new [${struct.name}] with the same field values as [\$model],
+    /// except for the given ones. Given `null`s are ignored, so nullable fields
+    /// can only be cleared after the copy has been created.
+    ${struct.name} duplicate(${struct.name} \$model$allParams) {
+      return create($args);
+    }
+  ''';
+}
+
+/// Creates the function comparing the field values of two structs, ignoring
+/// their IDs.
+String emitContentEqualsFunction(Struct struct) {
+  final conds = <String>[];
+  for (final field in struct.fields) {
+    final name = field.name;
+    final lhs = '\$a.$name\$.get(null)';
+    final rhs = '\$b.$name\$.get(null)';
+    switch (field.type) {
+      case AtomType() || AtomOptionType() || AtomDefaultType():
+        conds.add(
+            'serializedEquals(${serializer(struct.name, name)}, $lhs, $rhs)');
+      case LinkType():
+        conds.add('$lhs.id == $rhs.id');
+      case LinkOptionType():
+        conds.add('$lhs?.id == $rhs?.id');
+      case MultilinksType() || BacklinksType():
+        conds.add('idSetEquals($lhs.map((e) => e.id), $rhs.map((e) => e.id))');
+      case TimestampType():
+        break;
+    }
+  }
+  final body = conds.isEmpty ? 'true' : conds.join(' && ');
+  return '''
+    /// Returns if [\$a] and [\$b] have the same field values, regardless of
+    /// their IDs (e.g. to detect duplicates on import). Links are compared by
+    /// the IDs of their targets, multilinks and backlinks as sets of these.
+    bool contentEquals(${struct.name} \$a, ${struct.name} \$b) => $body;
+  ''';
+}
+
+/// Creates the function that writes some fields of an existing struct, taking
+/// `null` for the ones to leave unchanged.
+String emitPatchFunction(Struct struct) {
+  final params = StringBuffer();
+  final body = StringBuffer();
+  final given = <String>[];
+  for (final field in struct.fields) {
+    final name = field.name;
+    if (struct.idFrom.contains(name)) continue;
+    final lab = label(struct.name, name);
+    switch (field.type) {
+      case AtomType(type: final inner, :final sensitive) ||
+            AtomOptionType(type: final inner, :final sensitive) ||
+            AtomDefaultType(type: final inner, :final sensitive):
+        params.write('${inner.isNullable ? '$inner' : '$inner?'} $name,');
+        body.writeln(
+          '''
+          if ($name != null) {
+            \$store.setAtom(\$id ^ $lab, (\$id, $lab, $name, 
+            ${serializer(struct.name, name)},),${sensitiveArg(sensitive)});
+          }
+          ''',
+        );
+        break;
+      case LinkType(type: final inner) || LinkOptionType(type: final inner):
+        params.write('${inner.isNullable ? '$inner' : '$inner?'} $name,');
+        body.writeln(
+          '''
+          if ($name != null) {
+            \$store.setEdge(\$id ^ $lab, (\$id, $lab, $name.id,),);
+          }
+          ''',
+        );
+        break;
+      case MultilinksType() || BacklinksType() || TimestampType():
+        continue;
+    }
+    given.add('$name != null');
+  }
+  if (given.isEmpty) return '';
+  final touch = struct.timestamps
+      ? 'if (${given.join(' || ')}) \$touch(\$id);'
+      : '';
+  return '''
+    /// Writes the given fields of [\$model] with a single barrier, leaving the
+    /// others (and their clocks) untouched. As with [duplicate], given `null`s
+    /// are ignored, so nullable fields can only be cleared by their setters.
+    void patch(${struct.name} \$model, {$params}) {
+      final \$id = \$model.id;
+      final \$store = Dust.instance;
+      $body
+      $touch
+      \$store.barrier();
     }
   ''';
 }
 
 /// Creates the function that deletes an existing struct.
 String emitDeleteFunction(Struct struct) {
+  final sb = StringBuffer();
+  void write(String query, String lab, InterfaceType inner) => sb.write('''
+    \$store.$query(\$id, $lab, (_, target) => \$owned.add(() =>
+      const ${repository(inner.element.name)}().\$delete(target, \$visited)));
+  ''');
+  for (final field in struct.fields.where((e) => e.cascade)) {
+    switch (field.type) {
+      case LinkType(type: final inner) ||
+            LinkOptionType(type: final inner) ||
+            MultilinksType(type: final inner):
+        write('getEdgeDstBySrcLabel', label(struct.name, field.name), inner);
+      case BacklinksType(type: final inner, :final fields):
+        for (final backTo in fields) {
+          write('getEdgeSrcByDstLabel', label(inner.element.name, backTo),
+              inner);
+        }
+      default:
+        break;
+    }
+  }
+  final owned = sb.isEmpty ? '' : 'final \$owned = <void Function()>[];$sb';
+  final cascade = sb.isEmpty ? '' : 'for (final \$f in \$owned) \$f();';
   return '''
     void delete(${struct.name} \$model) {
       assert(\$init, 'Repository should be registered in `Dust.open`.');
-      final \$id = \$model.id;
+      \$delete(\$model.id, {});
+      Dust.instance.barrier();
+    }
+
+    /// Deletes the object with given ID without a barrier, together with the
+    /// objects it owns through `@Cascade()` fields, skipping the ones in
+    /// [\$visited] (so that cycles end).
+    void \$delete(Id \$id, Set<Id> \$visited) {
+      if (!\$visited.add(\$id)) return;
       final \$store = Dust.instance;
+      $owned
       \$entries.remove(\$id);
       \$store.setNode(\$id, null);
-      \$store.barrier();
+      $cascade
     }
   ''';
 }
@@ -488,37 +916,172 @@ String emitGetFunction(Struct struct) {
 
 String emitGetFunctionCtorArgs(Struct struct) {
   final sb = StringBuffer();
+  final onSet = struct.timestamps ? ' onSet: () => \$touch(\$id),' : '';
   for (final field in struct.fields) {
     final name = field.name;
     final lab = label(struct.name, name);
     switch (field.type) {
-      case AtomType(type: final inner):
+      case AtomType(
+          type: final inner,
+          :final lazy,
+          :final sensitive,
+          :final fallback
+        ):
+        final dft = (fallback == null) ? '' : ' fallback: $fallback,';
         sb.write('$name\$: Atom<$inner>(\$id ^ $lab, \$id, $lab, '
-            '${serializer(struct.name, name)},),');
-      case AtomOptionType(type: final inner):
+            '${serializer(struct.name, name)}, lazy: $lazy,$onSet$dft'
+            '${sensitiveArg(sensitive)}),');
+      case AtomOptionType(type: final inner, :final lazy, :final sensitive):
         sb.write('$name\$: AtomOption<$inner>(\$id ^ $lab, \$id, $lab, '
-            '${serializer(struct.name, name)},),');
-      case AtomDefaultType(type: final inner, :final defaultValue):
+            '${serializer(struct.name, name)}, lazy: $lazy,$onSet'
+            '${sensitiveArg(sensitive)}),');
+      case AtomDefaultType(
+          type: final inner,
+          :final defaultValue,
+          :final lazy,
+          :final sensitive
+        ):
         sb.write('$name\$: AtomDefault<$inner>(\$id ^ $lab, \$id, $lab, '
-            '${serializer(struct.name, name)}, $defaultValue,),');
+            '${serializer(struct.name, name)}, $defaultValue, lazy: $lazy,'
+            '$onSet${sensitiveArg(sensitive)}),');
       case LinkType(type: final inner):
         sb.write('$name\$: Link<$inner>(\$id ^ $lab, \$id, $lab,'
-            ' const ${repository(inner.element.name)}(),),');
+            ' const ${repository(inner.element.name)}(),$onSet),');
       case LinkOptionType(type: final inner):
         sb.write('$name\$: LinkOption<$inner>(\$id ^ $lab, \$id, $lab, '
-            'const ${repository(inner.element.name)}(),),');
-      case MultilinksType(type: final inner):
+            'const ${repository(inner.element.name)}(),$onSet),');
+      case MultilinksType(type: final inner, :final ordered, :final distinct):
+        final order =
+            ordered ? ' orderLabel: ${orderLabel(struct.name, name)},' : '';
+        final unique = distinct ? ' distinct: true,' : '';
         sb.write('$name\$: Multilinks<$inner>(\$id, $lab, '
-            'const ${repository(inner.element.name)}(),),');
-      case BacklinksType(type: final inner, field: final field):
+            'const ${repository(inner.element.name)}(),$onSet$order$unique),');
+      case BacklinksType(type: final inner, fields: [final field]):
         sb.write(
             '$name\$: Backlinks<$inner>(\$id, ${label(inner.element.name, field)},'
             ' const ${repository(inner.element.name)}(),),');
+      case BacklinksType(type: final inner, fields: final fields):
+        final labels = fields.map((e) => label(inner.element.name, e));
+        sb.write('$name\$: Backlinks<$inner>.any(\$id, [${labels.join(', ')}],'
+            ' const ${repository(inner.element.name)}(),),');
+      case TimestampType(type: final inner):
+        sb.write('$name\$: Atom<$inner>(\$id ^ $lab, \$id, $lab, '
+            '${serializer(struct.name, name)},),');
     }
   }
   return sb.toString();
 }
 
+/// Creates the enum of all fields, to be used with [emitGetFieldsFunction].
+String emitFieldEnum(Struct struct) {
+  if (struct.fields.isEmpty) return '';
+  return '''
+    /// The fields of [${struct.name}], see [${repository(struct.name)}.getFields].
+    enum ${field(struct.name)} { ${struct.fields.map((e) => e.name).join(', ')} }
+  ''';
+}
+
+/// Creates the function that reads a subset of fields by ID, without
+/// constructing the model or subscribing to anything. Links are returned as
+/// the IDs of their targets.
+String emitGetFieldsFunction(Struct struct) {
+  if (struct.fields.isEmpty) return '';
+  final sb = StringBuffer();
+  for (final f in struct.fields) {
+    final name = f.name;
+    final lab = label(struct.name, name);
+    sb.write('case ${field(struct.name)}.$name: {');
+    switch (f.type) {
+      case AtomType(fallback: final String defaultValue):
+        sb.write('''
+          \$store.getAtomById(\$id ^ $lab, (slv) => \$res[\$field] = (slv == null) 
+            ? $defaultValue : ${serializer(struct.name, name)}.deserialize(BytesReader(slv.\$3)));
+        ''');
+      case AtomType() || AtomOptionType() || TimestampType():
+        sb.write('''
+          \$store.getAtomById(\$id ^ $lab, (slv) => \$res[\$field] = (slv == null) 
+            ? null : ${serializer(struct.name, name)}.deserialize(BytesReader(slv.\$3)));
+        ''');
+      case AtomDefaultType(:final defaultValue):
+        sb.write('''
+          \$store.getAtomById(\$id ^ $lab, (slv) => \$res[\$field] = (slv == null) 
+            ? $defaultValue : ${serializer(struct.name, name)}.deserialize(BytesReader(slv.\$3)));
+        ''');
+      case LinkType() || LinkOptionType():
+        sb.write('''
+          \$store.getEdgeById(\$id ^ $lab, (sld) => \$res[\$field] = sld?.\$3);
+        ''');
+      case MultilinksType():
+        sb.write('''
+          final \$dsts = <Id>[];
+          \$store.getEdgeDstBySrcLabel(\$id, $lab, (_, dst) => \$dsts.add(dst));
+          \$res[\$field] = \$dsts;
+        ''');
+      case BacklinksType(type: final inner, fields: [final backTo]):
+        sb.write('''
+          final \$srcs = <Id>[];
+          \$store.getEdgeSrcByDstLabel(\$id, ${label(inner.element.name, backTo)}, (_, src) => \$srcs.add(src));
+          \$res[\$field] = \$srcs;
+        ''');
+      case BacklinksType(type: final inner, fields: final fields):
+        sb.write('final \$srcs = <Id>{};');
+        for (final backTo in fields) {
+          sb.write('''
+            \$store.getEdgeSrcByDstLabel(\$id, ${label(inner.element.name, backTo)}, (_, src) => \$srcs.add(src));
+          ''');
+        }
+        sb.write('\$res[\$field] = \$srcs.toList();');
+    }
+    sb.write('}');
+  }
+  return '''
+    Map<${field(struct.name)}, Object?> getFields(Id \$id, Iterable<${field(struct.name)}> \$fields) {
+      final \$store = Dust.instance;
+      final \$res = <${field(struct.name)}, Object?>{};
+      for (final \$field in \$fields) {
+        switch (\$field) {
+          ${sb.toString()}
+        }
+      }
+      return \$res;
+    }
+  ''';
+}
+
+/// Creates the function that lists the required fields which have no value
+/// yet, e.g. because they have not been synchronised.
+String emitMissingFieldsFunction(Struct struct) {
+  if (struct.fields.isEmpty) return '';
+  final required = struct.fields
+      .where((e) => switch (e.type) {
+            AtomType(fallback: null) || LinkType() || TimestampType() => true,
+            _ => false,
+          })
+      .map((e) => '${field(struct.name)}.${e.name}');
+  return '''
+    /// Returns the required fields of the object with given ID which have no
+    /// value (e.g. because they have not been fully synchronised yet). If this
+    /// is non-empty, reading these fields of the object will fail.
+    List<${field(struct.name)}> missingFields(Id \$id) {
+      final \$fields = getFields(\$id, const [${required.join(', ')}]);
+      return [for (final MapEntry(:key, :value) in \$fields.entries) if (value == null) key];
+    }
+  ''';
+}
+
+/// Creates the function that bumps the `updatedAt` timestamp, to be called
+/// before the barrier of each modification.
+String emitTouchFunction(Struct struct) {
+  if (!struct.timestamps) return '';
+  final lab = label(struct.name, kUpdatedAtName);
+  return '''
+    void \$touch(Id \$id) {
+      Dust.instance.setAtom(\$id ^ $lab, (\$id, $lab, Dust.clock(), 
+      ${serializer(struct.name, kUpdatedAtName)},),);
+    }
+  ''';
+}
+
 /// Generate deterministic ID for global object constructors.
 ///
 /// TODO: check if this fits the change of the API.
@@ -583,6 +1146,8 @@ String emitInitFunction(Struct struct) {
       case MultilinksType(:final sticky, :final acyclic):
         if (sticky) stickyEdges.add(lab);
         if (acyclic) acyclicEdges.add(lab);
+      case TimestampType():
+        stickyAtoms.add(lab);
       default:
         break;
     }
@@ -592,6 +1157,8 @@ String emitInitFunction(Struct struct) {
     Schema init() {
       \$init = true;
       return const Schema(
+        label: ${label(struct.name)},
+        model: manifest,
         stickyNodes: [${stickyNodes.join(', ')}],
         stickyAtoms: [${stickyAtoms.join(', ')}],
         stickyEdges: [${stickyEdges.join(', ')}],
@@ -601,6 +1168,42 @@ String emitInitFunction(Struct struct) {
   ''';
 }
 
+/// Creates the constant describing the shape of the [struct].
+String emitManifestDecl(Struct struct) {
+  final sb = StringBuffer();
+  for (final field in struct.fields) {
+    final (kind, type) = switch (field.type) {
+      AtomType(:final type) => ('atom', type),
+      AtomOptionType(:final type) => ('atomOption', type),
+      AtomDefaultType(:final type) => ('atomDefault', type),
+      LinkType(:final type) => ('link', type),
+      LinkOptionType(:final type) => ('linkOption', type),
+      MultilinksType(:final type) => ('multilinks', type),
+      BacklinksType(:final type) => ('backlinks', type),
+      TimestampType(:final type) => ('timestamp', type),
+    };
+    final lab = field.type is BacklinksType
+        ? 'null'
+        : label(struct.name, field.name);
+    sb.write('''
+      FieldInfo(
+        name: '${field.name}',
+        kind: FieldKind.$kind,
+        type: '$type',
+        label: $lab,${field.readonly ? ' readonly: true,' : ''}${field.hidden ? ' hidden: true,' : ''}
+      ),
+    ''');
+  }
+  return '''
+    /// The shape of [${struct.name}], see [Dust.manifest].
+    static const manifest = ModelInfo(
+      name: '${struct.name}',
+      label: ${label(struct.name)},
+      fields: [$sb],
+    );
+  ''';
+}
+
 /// Creates the label constants for the [struct].
 String emitLabelDecls(Struct struct) {
   bool shouldEmit(FieldType type) {
@@ -620,6 +1223,10 @@ String emitLabelDecls(Struct struct) {
       final value = fnv64Hash('${struct.name}.${field.name}');
       res += 'static const int ${field.name}Label = $value;';
     }
+    if (field.type case MultilinksType(ordered: true)) {
+      final value = fnv64Hash('${struct.name}.${field.name}.order');
+      res += 'static const int ${field.name}OrderLabel = $value;';
+    }
   }
   return res;
 }
@@ -634,6 +1241,9 @@ String emitSerializerDecls(Struct struct) {
             AtomDefaultType(serializer: final serializer):
         sb.writeln('static const ${field.name}Serializer = $serializer;');
         break;
+      case TimestampType():
+        sb.writeln('static const ${field.name}Serializer = IntSerializer();');
+        break;
       default:
         // do nothing
         break;
@@ -682,7 +1292,13 @@ String emitParentDecls(Struct struct) {
   ]) {
     return (type, name) {
       final contained = containedTypeMapper?.call(type) ?? type.toString();
-      sb.writeln('$containerName<$contained> get $name\$;');
+      if (struct.encapsulate) {
+        final value = mapper?.call(type) ?? type.toString();
+        sb.writeln('Observable<$value> get $name\$;');
+        sb.writeln('$containerName<$contained> get _$name\$;');
+      } else {
+        sb.writeln('$containerName<$contained> get $name\$;');
+      }
     };
   }
 
@@ -721,12 +1337,25 @@ String emitParentDecls(Struct struct) {
       case BacklinksType(type: final inner):
         writeBacklinks(inner, field.name);
         break;
+      case TimestampType(type: final inner):
+        writeAtom(inner, field.name);
+        break;
     }
   }
   return sb.toString();
 }
 
 String emitChildCstors(Struct struct) {
+  if (struct.encapsulate && struct.fields.isNotEmpty) {
+    final params = struct.fields
+        .map((e) => 'required ${wrapperType(e.type)} ${e.name}\$')
+        .join(', ');
+    final inits =
+        struct.fields.map((e) => '_${e.name}\$ = ${e.name}\$').join(', ');
+    return '''
+    ${child(struct.name)}._(this.id, {$params}) : $inits, super._();
+    ''';
+  }
   final names =
       struct.fields.map((e) => 'required this.${e.name}\$').join(', ');
   if (names.isEmpty) {
@@ -750,6 +1379,22 @@ String emitChildDecls(Struct struct) {
     } else {
       innerString = inner.toString();
     }
+    if (struct.encapsulate) {
+      final value = switch (wrapperName) {
+        kMultilinksName || kBacklinksName => 'List<$innerString>',
+        _ => innerString,
+      };
+      sb.writeln(
+        '''
+        @override
+        final $wrapperName<$innerString> _$name\$;
+
+        @override
+        Observable<$value> get $name\$ => _$name\$;
+        ''',
+      );
+      return;
+    }
     sb.writeln(
       '''
       @override
@@ -781,6 +1426,9 @@ String emitChildDecls(Struct struct) {
       case BacklinksType(type: final inner):
         write(kBacklinksName, inner, field.name);
         break;
+      case TimestampType(type: final inner):
+        write(kAtomName, inner, field.name);
+        break;
     }
   }
   return sb.toString();
@@ -799,10 +1447,70 @@ String emitDeleteFunctionApiImpl(Struct struct) {
   ''';
 }
 
+String emitSubscribeFunctionApi(Struct struct) {
+  return '''
+  /// Calls [f] whenever any field of this object changes, until the returned
+  /// subscription is cancelled (or garbage collected). Changes to backlinks
+  /// are not included.
+  Subscription subscribe(void Function(${struct.name}) f);
+
+  /// Returns a stream which emits this object on listen and whenever
+  /// [subscribe] would call back. Changes while paused are coalesced.
+  Stream<${struct.name}> watch();
+  ''';
+}
+
+String emitSubscribeFunctionApiImpl(Struct struct) {
+  final sb = StringBuffer();
+  for (final field in struct.fields) {
+    switch (field.type) {
+      case BacklinksType():
+        break;
+      default:
+        sb.write('${field.name}\$.connect(o);');
+    }
+  }
+  return '''
+  @override
+  Subscription subscribe(void Function(${struct.name}) f) =>
+    Subscription((o) { $sb }, () => f(this));
+
+  @override
+  Stream<${struct.name}> watch() => watchStream(this, subscribe);
+  ''';
+}
+
+/// Creates equality and hashing by ID, consistent with [Repository.get]
+/// returning the same object for the same ID.
+String emitEqualityImpl(Struct struct) {
+  return '''
+  @override
+  bool operator ==(Object other) => other is ${struct.name} && other.id == id;
+
+  @override
+  int get hashCode => id.hashCode;
+  ''';
+}
+
+/// Creates `toString` returning the field annotated with `@Display()`.
+String emitDisplayImpl(Struct struct) {
+  final display = struct.display;
+  if (display == null) return '';
+  return '''
+  @override
+  String toString() => $display\$.get(null);
+  ''';
+}
+
 /// Returns the corresponding label constant name.
 String label(String type, [String? field]) =>
     '\$${type}Repository.${field ?? ''}Label';
 
+/// Returns the corresponding order key label constant name, for ordered
+/// multilinks.
+String orderLabel(String type, String field) =>
+    '\$${type}Repository.${field}OrderLabel';
+
 /// Returns the corresponding parent class name.
 String parent(String name) => '_\$$name';
 
@@ -812,22 +1520,61 @@ String child(String name) => '_$name';
 /// Returns the corresponding repository class name.
 String repository(String name) => '\$${name}Repository';
 
+/// Returns the corresponding field enum name.
+String field(String name) => '\$${name}Field';
+
 /// Returns the corresponding serializer constant name.
 String serializer(String type, String field) =>
     '\$${type}Repository.${field}Serializer';
 
+/// Returns the type of the wrapper holding a field of the given type.
+String wrapperType(FieldType type) => switch (type) {
+      AtomType(type: final inner) ||
+      TimestampType(type: final inner) =>
+        '$kAtomName<$inner>',
+      AtomOptionType(type: final inner) => '$kAtomOptionName<$inner>',
+      AtomDefaultType(type: final inner) => '$kAtomDefaultName<$inner>',
+      LinkType(type: final inner) => '$kLinkName<$inner>',
+      LinkOptionType(type: final inner) => '$kLinkOptionName<$inner>',
+      MultilinksType(type: final inner) => '$kMultilinksName<$inner>',
+      BacklinksType(type: final inner) => '$kBacklinksName<$inner>',
+    };
+
 /// A struct to be mapped.
 final class Struct {
   final String name;
   final List<Field> fields;
-  Struct(this.name, this.fields);
+  final bool timestamps;
+
+  /// If set, the wrappers are library-private and only exposed as read-only
+  /// [Observable]s.
+  final bool encapsulate;
+
+  /// The name of the field annotated with `@Display()`, if any.
+  final String? display;
+
+  /// The names of the fields from which IDs are derived, see [emitIdFrom].
+  final List<String> idFrom;
+  Struct(this.name, this.fields,
+      {this.timestamps = false,
+      this.encapsulate = false,
+      this.display,
+      this.idFrom = const []});
 }
 
 /// A field to be mapped.
 final class Field {
   final String name;
   final FieldType type;
-  Field(this.name, this.type);
+
+  /// Set through `@Bindings()`, see [emitManifestDecl].
+  final bool readonly;
+  final bool hidden;
+
+  /// Set through `@Cascade()`, see [emitDeleteFunction].
+  final bool cascade;
+  Field(this.name, this.type,
+      {this.readonly = false, this.hidden = false, this.cascade = false});
 }
 
 /// All supported field types.
@@ -837,13 +1584,25 @@ final class MultilinksType extends FieldType {
   final InterfaceType type;
   final bool sticky;
   final bool acyclic;
-  MultilinksType(this.type, {required this.sticky, required this.acyclic});
+  final bool ordered;
+  final bool distinct;
+  MultilinksType(this.type,
+      {required this.sticky,
+      required this.acyclic,
+      required this.ordered,
+      required this.distinct});
 }
 
 final class AtomType extends FieldType {
   final InterfaceType type;
   final String serializer;
-  AtomType(this.type, this.serializer);
+  final bool lazy;
+  final bool sensitive;
+
+  /// The value read when there is none, see `AddedIn`.
+  final String? fallback;
+  AtomType(this.type, this.serializer,
+      {required this.lazy, required this.sensitive, this.fallback});
 }
 
 final class AtomDefaultType extends FieldType {
@@ -851,21 +1610,26 @@ final class AtomDefaultType extends FieldType {
   final String serializer;
   final String defaultValue;
   final bool sticky;
+  final bool lazy;
+  final bool sensitive;
   AtomDefaultType(this.type, this.serializer, this.defaultValue,
-      {required this.sticky});
+      {required this.sticky, required this.lazy, required this.sensitive});
 }
 
 final class AtomOptionType extends FieldType {
   final InterfaceType type;
   final String serializer;
   final bool sticky;
-  AtomOptionType(this.type, this.serializer, {required this.sticky});
+  final bool lazy;
+  final bool sensitive;
+  AtomOptionType(this.type, this.serializer,
+      {required this.sticky, required this.lazy, required this.sensitive});
 }
 
 final class BacklinksType extends FieldType {
   final InterfaceType type;
-  final String field;
-  BacklinksType(this.type, this.field);
+  final List<String> fields;
+  BacklinksType(this.type, this.fields);
 }
 
 final class LinkType extends FieldType {
@@ -874,6 +1638,12 @@ final class LinkType extends FieldType {
   LinkType(this.type, {required this.acyclic});
 }
 
+/// Automatically maintained timestamps, see [kTimestampNames].
+final class TimestampType extends FieldType {
+  final InterfaceType type;
+  TimestampType(this.type);
+}
+
 final class LinkOptionType extends FieldType {
   final InterfaceType type;
   final bool sticky;
@@ -896,7 +1666,17 @@ class ModelRepositoryGenerator extends GeneratorForAnnotation<Model> {
         element is MixinElement) {
       fail('Only classes may be annotated with @Model().', element);
     }
-    final struct = await convertStruct(element, buildStep);
+    final struct = await convertStruct(
+      element,
+      buildStep,
+      timestamps: annotation.read('timestamps').boolValue,
+      encapsulate: annotation.read('encapsulate').boolValue,
+      idFrom: annotation
+          .read('idFrom')
+          .listValue
+          .map((e) => e.toStringValue()!)
+          .toList(),
+    );
     return '''
       // ignore_for_file: ${kIgnoreForFile.join(', ')}
       // coverage:ignore-file
@@ -905,6 +1685,8 @@ class ModelRepositoryGenerator extends GeneratorForAnnotation<Model> {
         ${emitParentDecls(struct)}
 
         ${emitDeleteFunctionApi(struct)}
+
+        ${emitSubscribeFunctionApi(struct)}
       }
 
       final class ${child(struct.name)} extends ${struct.name} {
@@ -918,6 +1700,12 @@ class ModelRepositoryGenerator extends GeneratorForAnnotation<Model> {
         ${emitChildDecls(struct)}
 
         ${emitDeleteFunctionApiImpl(struct)}
+
+        ${emitSubscribeFunctionApiImpl(struct)}
+
+        ${emitEqualityImpl(struct)}
+
+        ${emitDisplayImpl(struct)}
       }
 
       class ${repository(struct.name)} implements Repository<${struct.name}> {
@@ -927,6 +1715,8 @@ class ModelRepositoryGenerator extends GeneratorForAnnotation<Model> {
 
         ${emitSerializerDecls(struct)}
 
+        ${emitManifestDecl(struct)}
+
         static final Map<Id, WeakReference<NodeOption<${struct.name}>>> \$entries = {};
 
         static bool \$init = false;
@@ -939,15 +1729,31 @@ class ModelRepositoryGenerator extends GeneratorForAnnotation<Model> {
 
         ${emitCreateFunctions(struct)}
 
+        ${emitCreateBatchFunction(struct)}
+
+        ${emitDuplicateFunction(struct)}
+
+        ${emitContentEqualsFunction(struct)}
+
+        ${emitPatchFunction(struct)}
+
+        ${emitTouchFunction(struct)}
+
         @override
         ${emitGetFunction(struct)}
 
+        ${emitGetFieldsFunction(struct)}
+
+        ${emitMissingFieldsFunction(struct)}
+
         @override
         ${emitDeleteFunction(struct)}
 
         ${emitAllFunction(struct)}
       }
 
+      ${emitFieldEnum(struct)}
+
       ${emitGlobalIds(struct, element)}
     ''';
   }
@@ -43,15 +43,12 @@ InterfaceType resolve(
   if (type.isNullable && !allowNullable) {
     fail('Type `$type` should not be nullable.', elem);
   }
-  final alias = type.alias;
-  if (alias != null) {
-    return resolve(alias.element.aliasedType, elem);
-  } else {
-    if (type is! InterfaceType) {
-      fail('Type `$type` should be an object type (class or interface).', elem);
-    }
-    return type;
+  // Type aliases are already expanded here (with type arguments substituted
+  // and nullability applied), so they need no special treatment.
+  if (type is! InterfaceType) {
+    fail('Type `$type` should be an object type (class or interface).', elem);
   }
+  return type;
 }
 
 const kRecordChecker = TypeChecker.fromRuntime(Record);
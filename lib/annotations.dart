@@ -16,9 +16,22 @@ export 'src/serializer.dart' show Serializer;
 
 /// The annotation for a model class. A model is an object that can be
 /// serialized and persisted to the database.
+///
+/// Only the parameters of the factory constructor are persisted. Values
+/// derived from them need no annotation: declare them as members of the model
+/// class, e.g. with `Reactive` so that observers are notified when the
+/// underlying fields change:
+///
+/// ```dart
+/// late final fullName$ =
+///     Reactive((o) => '${first$.get(o)} ${last$.get(o)}');
+/// ```
 class Model {
   const Model({
     this.generateForwarding = false,
+    this.timestamps = false,
+    this.encapsulate = false,
+    this.idFrom = const [],
   });
 
   /// This field is used to indicate whether if the generated model would have
@@ -66,6 +79,51 @@ class Model {
   /// opt-in for forwarding and which to opt-out. Therefore, this method shall
   /// be applied to all the fields in the model.
   final bool generateForwarding;
+
+  /// If set to true, the model gets two additional atoms `createdAt$` and
+  /// `updatedAt$`, holding milliseconds since epoch as given by `Dust.clock`.
+  /// Both are set on creation, and `updatedAt$` is bumped on every
+  /// modification made through the fields of the model.
+  final bool timestamps;
+
+  /// If set to true, the writable fields (e.g. `_name$` of type
+  /// `Atom<String>`) are private to the library declaring the model, and only
+  /// read-only views (e.g. `name$` of type `Observable<String>`) are public.
+  /// Modifications from other libraries must then go through methods written
+  /// on the model class, which can enforce invariants:
+  ///
+  /// ```dart
+  /// @Model(encapsulate: true)
+  /// abstract class Counter with _$Counter {
+  ///   Counter._();
+  ///
+  ///   factory Counter({@Dft(0) int count}) = _Counter;
+  ///
+  ///   void increment() => _count$.set(_count$.peek() + 1);
+  /// }
+  /// ```
+  final bool encapsulate;
+
+  /// If non-empty, the ID of a new model is derived from a hash of the given
+  /// required atom fields (and the model type) instead of being random, so
+  /// that creating the same logical record twice (e.g. when re-importing)
+  /// updates a single node instead of making a duplicate:
+  ///
+  /// ```dart
+  /// @Model(idFrom: ['email'])
+  /// abstract class User with _$User {
+  ///   User._();
+  ///
+  ///   factory User({required String email, String? name}) = _User;
+  /// }
+  /// ```
+  ///
+  /// Note that multilinks given on creation are added to the existing ones.
+  ///
+  /// The hash (see `Id.hash`) is not collision resistant: distinct keys are
+  /// very unlikely to share an ID by chance, but keys can be crafted to do
+  /// so. Do not derive IDs from untrusted input where that matters.
+  final List<String> idFrom;
 }
 
 /// The annotation for providing a default value to a field. We would love to
@@ -78,21 +136,103 @@ class Dft<T> {
   const Dft(this.defaultValue);
 }
 
+/// The annotation for marking a required atom field as added in a later
+/// schema [version], e.g. `@AddedIn(2, 'free') required String plan`. New
+/// objects must still be created with a value, but objects created before
+/// the field existed (which have no value for it) read [defaultValue]
+/// instead of failing to load.
+class AddedIn<T> {
+  /// The schema version which added this field, for reference only.
+  final int version;
+
+  /// The value read for objects created before this field was added.
+  final T defaultValue;
+
+  const AddedIn(this.version, this.defaultValue);
+}
+
 /// The annotation for marking a field as a link to another node. If the
 /// [backTo] is specified, then this link is a backlink to another model that
 /// links to this model. Otherwise, this link is a regular link to another
 /// model.
 ///
+/// If [backToAny] is specified instead, then this link is a backlink from
+/// any of the given fields, e.g. `@Ln(backToAny: ['parent', 'related'])`.
+///
 /// TODO: think of a better name.
 class Ln {
   final String? backTo;
-  const Ln({this.backTo});
+  final List<String>? backToAny;
+  const Ln({this.backTo, this.backToAny});
+}
+
+/// The annotation for marking a multilinks field as user-orderable, e.g. for
+/// drag-to-reorder lists. Each link then carries an order key (a fractional
+/// index stored as an atom), targets are returned sorted by it, and
+/// `Multilinks.moveBefore` and `Multilinks.moveAfter` can be used to change
+/// the order.
+class Ordered {
+  const Ordered();
+}
+
+/// The annotation for marking a multilinks field as holding each target at
+/// most once. Edges to the same target (e.g. inserted concurrently by two
+/// devices) are then read as one, inserting a present target does nothing,
+/// and removing a target removes all edges to it.
+class Distinct {
+  const Distinct();
 }
 
 class Glb {
   const Glb();
 }
 
+/// The annotation for marking an atom field as lazily loaded. The value of a
+/// lazy field is not read from the database until it is first accessed, which
+/// keeps loading models with large but rarely displayed fields cheap.
+class Lazy {
+  const Lazy();
+}
+
+/// The annotation for marking an atom field as holding sensitive data (e.g.
+/// access tokens). Buffers holding its serialized value are zeroed after each
+/// write, on a best-effort basis (see `Dust.setAtom`).
+class Sensitive {
+  const Sensitive();
+}
+
+/// The annotation for marking a required `String` atom field as the display
+/// name of a model. The generated `toString` of the model then returns the
+/// current value of that field. At most one field may be marked.
+class Display {
+  const Display();
+}
+
+/// The annotation for marking a link, multilinks or backlinks field as owning
+/// its targets: deleting a model then deletes them too, together with the
+/// targets they own in turn (each model is deleted at most once, so cycles
+/// are fine), e.g. deleting a board deletes its cards:
+///
+/// ```dart
+/// factory Board({@Ln() @Cascade() List<Card> cards}) = _Board;
+/// ```
+///
+/// Only targets known at the time of deletion are deleted.
+class Cascade {
+  const Cascade();
+}
+
+/// The annotation for restricting how a field is exposed by bindings
+/// generated for other languages from the model manifest (see `ModelInfo`):
+/// [readonly] fields get no setters, and [hidden] fields are left out
+/// entirely. The field is recorded accordingly in the manifest; the generated
+/// Dart API keeps full access to it.
+class Bindings {
+  final bool readonly;
+  final bool hidden;
+  const Bindings({this.readonly = false, this.hidden = false});
+}
+
 /// Traditional SQL databases have the ability to enforce "referential
 /// integrity": you will never get unexpected null values or broken links when
 /// reading non-nullable fields and relationships. For example, if a folder's
@@ -33,9 +33,14 @@ class Ffi {
     _ => throw UnimplementedError('Unsupported platform'),
   };
 
-  static void init(String databasePath, List<Repository> repositories) {
+  /// Initialises all [repositories] and opens the database, returning the
+  /// schema of each repository.
+  static List<Schema> init(
+      String databasePath, List<Repository> repositories) {
+    final schemas = <Schema>[];
     for (final repository in repositories) {
       final schema = repository.init();
+      schemas.add(schema);
       for (final label in schema.stickyNodes) {
         bindings.dust_add_sticky_node(label);
       }
@@ -52,5 +57,6 @@ class Ffi {
     final ptr = databasePath.toNativeUtf8(allocator: malloc);
     bindings.dust_open(ptr.length, ptr.cast<Uint8>());
     malloc.free(ptr);
+    return schemas;
   }
 }
@@ -96,6 +96,18 @@ class NativeBindings {
   late final _dust_atom = _dust_atomPtr
       .asFunction<CResultOptionAtom Function(int, int)>(isLeaf: true);
 
+  int dust_atom_clock(int idh, int idl) {
+    final res = _dust_atom_clock(idh, idl);
+    if (res.tag != 0) _err(res.body.err);
+    return res.body.ok;
+  }
+
+  late final _dust_atom_clockPtr =
+      _lookup<NativeFunction<CResultUint64 Function(Uint64, Uint64)>>(
+          'dust_atom_clock');
+  late final _dust_atom_clock = _dust_atom_clockPtr
+      .asFunction<CResultUint64 Function(int, int)>(isLeaf: true);
+
   CArrayTripleIdUint64ArrayUint8 dust_atom_id_label_value_by_src(
       int srch, int srcl) {
     final res = _dust_atom_id_label_value_by_src(srch, srcl);
@@ -316,6 +328,30 @@ class NativeBindings {
   late final _dust_edge = _dust_edgePtr
       .asFunction<CResultOptionEdge Function(int, int)>(isLeaf: true);
 
+  bool dust_edge_written(int idh, int idl) {
+    final res = _dust_edge_written(idh, idl);
+    if (res.tag != 0) _err(res.body.err);
+    return res.body.ok;
+  }
+
+  late final _dust_edge_writtenPtr =
+      _lookup<NativeFunction<CResultBool Function(Uint64, Uint64)>>(
+          'dust_edge_written');
+  late final _dust_edge_written = _dust_edge_writtenPtr
+      .asFunction<CResultBool Function(int, int)>(isLeaf: true);
+
+  int dust_edge_clock(int idh, int idl) {
+    final res = _dust_edge_clock(idh, idl);
+    if (res.tag != 0) _err(res.body.err);
+    return res.body.ok;
+  }
+
+  late final _dust_edge_clockPtr =
+      _lookup<NativeFunction<CResultUint64 Function(Uint64, Uint64)>>(
+          'dust_edge_clock');
+  late final _dust_edge_clock = _dust_edge_clockPtr
+      .asFunction<CResultUint64 Function(int, int)>(isLeaf: true);
+
   CArrayPairIdId dust_edge_id_dst_by_src_label(int srch, int srcl, int label) {
     final res = _dust_edge_id_dst_by_src_label(srch, srcl, label);
     if (res.tag != 0) _err(res.body.err);
@@ -370,6 +406,18 @@ class NativeBindings {
       .asFunction<CResultArrayTripleIdIdUint64 Function(int, int)>(
           isLeaf: true);
 
+  CUnit dust_enable_node_label_cache() {
+    final res = _dust_enable_node_label_cache();
+    if (res.tag != 0) _err(res.body.err);
+    return res.body.ok;
+  }
+
+  late final _dust_enable_node_label_cachePtr =
+      _lookup<NativeFunction<CResultUnit Function()>>(
+          'dust_enable_node_label_cache');
+  late final _dust_enable_node_label_cache = _dust_enable_node_label_cachePtr
+      .asFunction<CResultUnit Function()>(isLeaf: true);
+
   COptionNode dust_node(int idh, int idl) {
     final res = _dust_node(idh, idl);
     if (res.tag != 0) _err(res.body.err);
@@ -326,3 +326,27 @@ final class UnnamedUnion14 extends Union {
   external CArrayUint8 ok;
   external CArrayUint8 err;
 }
+
+final class CResultBool extends Struct {
+  @Uint8()
+  external int tag;
+  external UnnamedUnion15 body;
+}
+
+final class UnnamedUnion15 extends Union {
+  @Bool()
+  external bool ok;
+  external CArrayUint8 err;
+}
+
+final class CResultUint64 extends Struct {
+  @Uint8()
+  external int tag;
+  external UnnamedUnion16 body;
+}
+
+final class UnnamedUnion16 extends Union {
+  @Uint64()
+  external int ok;
+  external CArrayUint8 err;
+}
@@ -274,3 +274,38 @@ class Comparer<T> with ObserverMixin implements Observer {
     }
   }
 }
+
+/// Executes a function whenever any [Observable] connected by a given
+/// function is notified, until [cancel] is called.
+///
+/// Observables only hold weak references to their observers, so the
+/// subscription must be kept alive for as long as it is needed.
+class Subscription with ObserverMixin implements Observer {
+  final void Function(Observer o) _connect;
+  final void Function() _callback;
+  bool _visited = false;
+  bool _cancelled = false;
+
+  Subscription(this._connect, this._callback) {
+    _connect(this);
+  }
+
+  /// Stops executing the function.
+  void cancel() {
+    _cancelled = true;
+  }
+
+  @override
+  void visit(List<void Function()> posts) {
+    super.visit(posts);
+    if (!_visited && !_cancelled) {
+      _visited = true;
+      posts.add(() {
+        _visited = false;
+        if (_cancelled) return;
+        _connect(this);
+        _callback();
+      });
+    }
+  }
+}
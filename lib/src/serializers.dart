@@ -24,7 +24,9 @@ export 'serializers/set.dart';
 export 'serializers/string.dart';
 export 'serializers/uint.dart';
 export 'serializers/uint8list.dart';
+export 'serializers/varint.dart';
 export 'serializers/bool.dart';
+export 'serializers/compressed.dart';
 export 'serializer.dart';
 
 import 'serializer.dart';
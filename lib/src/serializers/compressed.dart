@@ -0,0 +1,77 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+import 'dart:io';
+import 'dart:typed_data';
+
+import '../serializers.dart';
+
+/// A serializer which compresses the output of [inner] with DEFLATE, primed
+/// with a shared [dictionary] if given. Small values rarely compress well on
+/// their own, but many similar ones (e.g. JSON with the same keys) do once
+/// their common parts are in the dictionary.
+///
+/// # Usage
+///
+/// ```dart
+/// class NoteSerializer extends CompressedSerializer<String> {
+///   const NoteSerializer();
+///
+///   @override
+///   Serializer<String> get inner => const StringSerializer();
+///
+///   @override
+///   List<int> get dictionary => kNoteDictionary;
+/// }
+/// ```
+///
+/// Then annotate the fields with `@NoteSerializer()`. The dictionary must
+/// never change once values have been written with it, since they can only
+/// be read back with the same one.
+abstract class CompressedSerializer<T> implements Serializer<T> {
+  const CompressedSerializer();
+
+  Serializer<T> get inner;
+
+  List<int>? get dictionary => null;
+
+  ZLibCodec get _codec => ZLibCodec(raw: true, dictionary: dictionary);
+
+  @override
+  void serialize(T object, BytesBuilder builder) {
+    final plain = BytesBuilder();
+    inner.serialize(object, plain);
+    builder.writeBytes(_codec.encode(plain.takeBytes()));
+  }
+
+  @override
+  T deserialize(BytesReader reader) {
+    final plain = Uint8List.fromList(_codec.decode(reader.readBytes()));
+    return inner.deserialize(BytesReader(plain.buffer.asByteData()));
+  }
+
+  /// Builds a dictionary from [samples] of values serialized by [inner], by
+  /// keeping the last [size] bytes (at most the 32 KiB window of DEFLATE) of
+  /// their concatenation. Later samples are referenced most cheaply, so the
+  /// most typical ones should come last.
+  static Uint8List trainDictionary(Iterable<List<int>> samples,
+      {int size = 32768}) {
+    final builder = BytesBuilder();
+    for (final sample in samples) {
+      builder.add(sample);
+    }
+    final bytes = builder.takeBytes();
+    return bytes.sublist(bytes.length > size ? bytes.length - size : 0);
+  }
+}
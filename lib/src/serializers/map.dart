@@ -16,6 +16,8 @@ import 'dart:typed_data';
 
 import '../serializers.dart';
 
+/// Serializes entries ordered by the serialized bytes of their keys, so that
+/// equal maps always produce equal bytes regardless of insertion order.
 class MapSerializer<T, U> implements Serializer<Map<T, U>> {
   final Serializer<T> t;
   final Serializer<U> u;
@@ -23,10 +25,16 @@ class MapSerializer<T, U> implements Serializer<Map<T, U>> {
 
   @override
   void serialize(Map<T, U> object, BytesBuilder builder) {
-    builder.writeUint64(object.length);
-    for (final elem in object.entries) {
-      t.serialize(elem.key, builder);
-      u.serialize(elem.value, builder);
+    final entries = object.entries.map((elem) {
+      final key = BytesBuilder();
+      t.serialize(elem.key, key);
+      return (key.takeBytes(), elem.value);
+    }).toList();
+    entries.sort((a, b) => _compareBytes(a.$1, b.$1));
+    builder.writeUint64(entries.length);
+    for (final (key, value) in entries) {
+      builder.add(key);
+      u.serialize(value, builder);
     }
   }
 
@@ -42,3 +50,11 @@ class MapSerializer<T, U> implements Serializer<Map<T, U>> {
     return res;
   }
 }
+
+/// Lexicographic order on byte strings.
+int _compareBytes(Uint8List a, Uint8List b) {
+  for (var i = 0; i < a.length && i < b.length; i++) {
+    if (a[i] != b[i]) return a[i] - b[i];
+  }
+  return a.length - b.length;
+}
@@ -16,15 +16,23 @@ import 'dart:typed_data';
 
 import '../serializers.dart';
 
+/// Serializes elements ordered by their serialized bytes, so that equal sets
+/// always produce equal bytes regardless of insertion order.
 class SetSerializer<T> implements Serializer<Set<T>> {
   final Serializer<T> t;
   const SetSerializer(this.t);
 
   @override
   void serialize(Set<T> object, BytesBuilder builder) {
-    builder.writeUint64(object.length);
-    for (final elem in object) {
-      t.serialize(elem, builder);
+    final elems = object.map((elem) {
+      final bytes = BytesBuilder();
+      t.serialize(elem, bytes);
+      return bytes.takeBytes();
+    }).toList();
+    elems.sort(_compareBytes);
+    builder.writeUint64(elems.length);
+    for (final elem in elems) {
+      builder.add(elem);
     }
   }
 
@@ -38,3 +46,11 @@ class SetSerializer<T> implements Serializer<Set<T>> {
     return res;
   }
 }
+
+/// Lexicographic order on byte strings.
+int _compareBytes(Uint8List a, Uint8List b) {
+  for (var i = 0; i < a.length && i < b.length; i++) {
+    if (a[i] != b[i]) return a[i] - b[i];
+  }
+  return a.length - b.length;
+}
@@ -0,0 +1,50 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+import 'dart:typed_data';
+
+import '../serializers.dart';
+
+/// Serializes integers as ZigZag-encoded LEB128 varints, i.e. in 1 byte for
+/// values between -64 and 63, up to 10 bytes for the largest ones. The
+/// default [IntSerializer] always takes 8 bytes, which is smaller for large
+/// or random values and keeps the byte order of non-negative values.
+///
+/// Annotate a field with `@VarIntSerializer()` to use it. Values written with
+/// one serializer cannot be read with the other, so this must not be changed
+/// on fields that already hold data.
+class VarIntSerializer implements Serializer<int> {
+  const VarIntSerializer();
+
+  @override
+  void serialize(int object, BytesBuilder builder) {
+    var value = (object << 1) ^ (object >> 63);
+    while (value & ~0x7f != 0) {
+      builder.writeUint8((value & 0x7f) | 0x80);
+      value >>>= 7;
+    }
+    builder.writeUint8(value);
+  }
+
+  @override
+  int deserialize(BytesReader reader) {
+    var value = 0;
+    for (var shift = 0;; shift += 7) {
+      final byte = reader.readUint8();
+      value |= (byte & 0x7f) << shift;
+      if (byte & 0x80 == 0) break;
+    }
+    return (value >>> 1) ^ -(value & 1);
+  }
+}
@@ -23,19 +23,53 @@ import 'ffi.dart';
 import 'multimap.dart';
 import 'serializers.dart';
 import 'store/id.dart';
+import 'store/manifest.dart';
 import 'store/repository.dart';
 
 export 'store/id.dart';
 export 'store/schema.dart';
+export 'store/manifest.dart';
 export 'store/repository.dart';
 export 'store/node.dart';
 export 'store/atom.dart';
+export 'store/debounced.dart';
+export 'store/watch.dart';
 export 'store/link.dart';
 export 'store/multilinks.dart';
 export 'store/backlinks.dart';
 
 class AlreadyDeletedException<T> implements Exception {}
 
+/// Thrown when a link is resolved but its destination is a node of a
+/// different label than that of the linked model type, i.e. the data is
+/// inconsistent.
+class LinkTypeMismatchException implements Exception {
+  final Id link;
+  final Id dst;
+  final int expected;
+  final int actual;
+
+  LinkTypeMismatchException(this.link, this.dst, this.expected, this.actual);
+
+  @override
+  String toString() => 'LinkTypeMismatchException: link $link points to '
+      'node $dst with label $actual, not $expected';
+}
+
+/// Thrown when a model is created with an ID which already belongs to a node
+/// of a different label (i.e. a model of another type).
+class IdCollisionException implements Exception {
+  final Id id;
+  final int existing;
+  final int label;
+
+  IdCollisionException(this.id, this.existing, this.label);
+
+  @override
+  String toString() =>
+      'IdCollisionException: node $id has label $existing, not $label';
+}
+
 ByteData _view(CArrayUint8 array) =>
     array.ptr.asTypedList(array.len).buffer.asByteData();
 
@@ -75,6 +109,20 @@ typedef EdgeByDstLabelSubscription = (
   void Function(Id id, Id src),
   void Function(Id id)
 );
+typedef ChangeByLabelSubscription = void Function(Id id, ChangeKind kind);
+
+/// The kind of a change to a node, atom or edge, as reported by
+/// [Dust.subscribeChangeByLabel].
+enum ChangeKind {
+  /// The item did not exist (or had a different label) before.
+  created,
+
+  /// The item existed with the same label before and after.
+  updated,
+
+  /// The item no longer exists (or now has a different label).
+  deleted,
+}
 
 /// The main wrapper class around FFI functions.
 ///
@@ -95,6 +143,7 @@ class Dust {
   final edgeBySrcLabel = MultiMap<(Id, int), EdgeBySrcLabelSubscription>();
   final edgeByDst = MultiMap<Id, EdgeByDstSubscription>();
   final edgeByDstLabel = MultiMap<(Id, int), EdgeByDstLabelSubscription>();
+  final changeByLabel = MultiMap<int, ChangeByLabelSubscription>();
 
   late final _nodeByIdFinalizer =
       Finalizer<(Id, NodeByIdSubscription)>(_unsubscribeNodeById);
@@ -122,16 +171,30 @@ class Dust {
   late final _edgeByDstLabelFinalizer =
       Finalizer<((Id, int), EdgeByDstLabelSubscription)>(
           _unsubscribeEdgeByDstLabel);
+  late final _changeByLabelFinalizer =
+      Finalizer<(int, ChangeByLabelSubscription)>(_unsubscribeChangeByLabel);
+
+  /// The repositories given to [open], by the node label of their models.
+  final Map<int, Repository> _repositories;
+
+  /// The shapes of the models of all repositories given to [open].
+  final List<ModelInfo> manifest;
 
-  Dust._(this.bindings);
+  Dust._(this.bindings, this._repositories, this.manifest);
 
   /// The global [Dust] instance.
   static Dust? _instance;
 
   /// Initialises the global [Dust] instance.
   static void open(String databasePath, List<Repository> repositories) {
-    Ffi.init(databasePath, repositories);
-    _instance = Dust._(Ffi.bindings);
+    final schemas = Ffi.init(databasePath, repositories);
+    _instance = Dust._(Ffi.bindings, {
+      for (final (i, schema) in schemas.indexed)
+        if (schema.label case final label?) label: repositories[i],
+    }, [
+      for (final schema in schemas)
+        if (schema.model case final model?) model,
+    ]);
   }
 
   /// Disconnects the global [Dust] instance.
@@ -150,17 +213,46 @@ class Dust {
     return _instance!;
   }
 
+  /// The time source for automatic timestamps of models annotated with
+  /// `@Model(timestamps: true)`, in milliseconds since epoch. May be replaced,
+  /// e.g. in tests.
+  static int Function() clock = _systemClock;
+
+  static int _systemClock() => DateTime.now().millisecondsSinceEpoch;
+
   /// Makes a random 128-bit ID.
   Id randomId() {
     return Id.fromNative(bindings.dust_random_id());
   }
 
+  /// If set, [edgeId] makes IDs sharing the upper 64 bits of their source, so
+  /// that edges from the same node are stored close together.
+  bool clusterEdgeIds = false;
+
+  /// Makes an ID for a new edge starting from [src]. This is the same as
+  /// [randomId] unless [clusterEdgeIds] is set, in which case only the lower
+  /// 64 bits are random.
+  Id edgeId(Id src) {
+    final id = randomId();
+    return clusterEdgeIds ? Id(src.high, id.low) : id;
+  }
+
   /// Obtains node value.
   void getNodeById(Id id, void Function(int?) fn) {
     final data = bindings.dust_node(id.high, id.low);
     fn(data.tag == 0 ? null : data.some.label);
   }
 
+  /// Returns the model with given ID, read by the repository registered in
+  /// [open] for the label of its node, or `null` if there is no such node or
+  /// repository. This is useful when the type is not known statically (e.g.
+  /// for search results): use pattern matching to tell the types apart.
+  Object? resolve(Id id) {
+    int? label;
+    getNodeById(id, (l) => label = l);
+    return _repositories[label]?.get(id).get(null);
+  }
+
   /// Queries the reverse index.
   void getNodeByLabel(int label, void Function(Id) fn) {
     final data = bindings.dust_node_id_by_label(label);
@@ -171,8 +263,20 @@ class Dust {
     bindings.dust_drop_array_id(data);
   }
 
+  /// Keeps results of [getNodeByLabel] in memory until a node of that label
+  /// is created, deleted or changed (including by synchronisation), so that
+  /// repeated list queries between modifications do not hit the database.
+  void enableNodeLabelCache() {
+    bindings.dust_enable_node_label_cache();
+  }
+
+  /// The number of calls to [getAtomById] so far, e.g. for checking that
+  /// lazy fields are not read until they are accessed.
+  int atomReads = 0;
+
   /// Obtains atom value.
   void getAtomById(Id id, void Function((Id, int, ByteData)?) fn) {
+    atomReads++;
     final data = bindings.dust_atom(id.high, id.low);
     fn(data.tag == 0
         ? null
@@ -184,6 +288,13 @@ class Dust {
     bindings.dust_drop_option_atom(data);
   }
 
+  /// Returns the clock of the last modification of the atom (even if it was
+  /// a removal), or `null` if it has never been written.
+  int? getAtomClockById(Id id) {
+    final clock = bindings.dust_atom_clock(id.high, id.low);
+    return (clock == 0) ? null : clock;
+  }
+
   /// Queries the forward index.
   void getAtomLabelValueBySrc(Id src, void Function(Id, int, ByteData) fn) {
     final data = bindings.dust_atom_id_label_value_by_src(src.high, src.low);
@@ -236,6 +347,18 @@ class Dust {
           ));
   }
 
+  /// Returns if the edge has ever been written, even if it has since been
+  /// removed.
+  bool getEdgeWrittenById(Id id) =>
+      bindings.dust_edge_written(id.high, id.low);
+
+  /// Returns the clock of the last modification of the edge (even if it was
+  /// a removal), or `null` if it has never been written.
+  int? getEdgeClockById(Id id) {
+    final clock = bindings.dust_edge_clock(id.high, id.low);
+    return (clock == 0) ? null : clock;
+  }
+
   /// Queries the forward index.
   void getEdgeLabelDstBySrc(Id src, void Function(Id, int, Id) fn) {
     final data = bindings.dust_edge_id_label_dst_by_src(src.high, src.low);
@@ -289,7 +412,13 @@ class Dust {
   }
 
   /// Modifies atom value. Requires a [barrier] call to come into effect.
-  void setAtom<T>(Id id, (Id, int, T, Serializer<T>)? slv) {
+  ///
+  /// If [sensitive] is set, the buffers holding the serialized value are
+  /// zeroed once it has been passed to the store, so that e.g. tokens do not
+  /// linger in freed memory. This is best-effort: the deserialized value and
+  /// any copies made by the serializer are not affected.
+  void setAtom<T>(Id id, (Id, int, T, Serializer<T>)? slv,
+      {bool sensitive = false}) {
     if (slv == null) {
       bindings.dust_set_atom_none(id.high, id.low);
     } else {
@@ -303,6 +432,10 @@ class Dust {
       for (var i = 0; i < len; i++) (ptr + i).value = bytes[i];
       bindings.dust_set_atom_some(
           id.high, id.low, src.high, src.low, label, len, ptr);
+      if (sensitive) {
+        for (var i = 0; i < len; i++) (ptr + i).value = 0;
+        bytes.fillRange(0, len, 0);
+      }
       malloc.free(ptr);
     }
   }
@@ -490,6 +623,17 @@ class Dust {
     getEdgeSrcByDstLabel(dst, label, insert);
   }
 
+  /// Subscribes to changes of nodes, atoms and edges with given label, telling
+  /// creations and deletions apart from updates (e.g. to animate removed
+  /// rows). Unlike the other subscriptions, nothing is reported initially.
+  void subscribeChangeByLabel(
+      int label, void Function(Id id, ChangeKind kind) change, Object owner) {
+    final key = label;
+    final value = change;
+    changeByLabel.add(key, value);
+    _changeByLabelFinalizer.attach(owner, (key, value));
+  }
+
   void _unsubscribeNodeById((Id, NodeByIdSubscription) kv) =>
       nodeById.remove(kv.$1, kv.$2);
   void _unsubscribeNodeByLabel((int, NodeByLabelSubscription) kv) =>
@@ -513,9 +657,42 @@ class Dust {
       edgeByDst.remove(kv.$1, kv.$2);
   void _unsubscribeEdgeByDstLabel(((Id, int), EdgeByDstLabelSubscription) kv) =>
       edgeByDstLabel.remove(kv.$1, kv.$2);
+  void _unsubscribeChangeByLabel((int, ChangeByLabelSubscription) kv) =>
+      changeByLabel.remove(kv.$1, kv.$2);
+
+  /// Notifies [changeByLabel] of an item going from label [prev] to [curr],
+  /// where `null` means absent.
+  void _change(Id id, int? prev, int? curr) {
+    if (prev != null && prev == curr) {
+      for (final change in changeByLabel[prev]) change(id, ChangeKind.updated);
+      return;
+    }
+    if (prev != null) {
+      for (final change in changeByLabel[prev]) change(id, ChangeKind.deleted);
+    }
+    if (curr != null) {
+      for (final change in changeByLabel[curr]) change(id, ChangeKind.created);
+    }
+  }
+
+  int _batchDepth = 0;
+
+  /// Runs [f] with all [barrier] calls deferred to a single one at the end,
+  /// which makes bulk modifications (e.g. imports) much cheaper. Observers are
+  /// not notified until [f] returns, so models created inside [f] should be
+  /// referred to by ID only.
+  R batch<R>(R Function() f) {
+    _batchDepth++;
+    try {
+      return f();
+    } finally {
+      if (--_batchDepth == 0) barrier();
+    }
+  }
 
   /// Processes all events and invokes relevant observers.
   void barrier() {
+    if (_batchDepth > 0) return;
     final data = bindings.dust_barrier();
     for (var i = 0; i < data.len; i++) {
       final event = (data.ptr + i).ref;
@@ -535,6 +712,8 @@ class Dust {
           } else {
             for (final update in nodeById[id]) update(null);
           }
+          _change(id, prev.tag != 0 ? prev.some.label : null,
+              curr.tag != 0 ? curr.some.label : null);
         case 1:
           final id = Id.fromNative(event.body.atom.id);
           final prev = event.body.atom.prev;
@@ -560,6 +739,8 @@ class Dust {
           } else {
             for (final update in atomById[id]) update(null);
           }
+          _change(id, prev.tag != 0 ? prev.some.label : null,
+              curr.tag != 0 ? curr.some.label : null);
         case 2:
           final id = Id.fromNative(event.body.edge.id);
           final prev = event.body.edge.prev;
@@ -587,6 +768,8 @@ class Dust {
           } else {
             for (final update in edgeById[id]) update(null);
           }
+          _change(id, prev.tag != 0 ? prev.some.label : null,
+              curr.tag != 0 ? curr.some.label : null);
         default:
           throw UnimplementedError();
       }
@@ -23,20 +23,45 @@ class AtomOption<T> with ObservableMixin<T?> implements ObservableMut<T?> {
   final Id src;
   final int label;
   final Serializer<T> _serializer;
+  final void Function()? _onSet;
+  final bool _sensitive;
   T? _value;
+  bool _subscribed = false;
+
+  /// If [lazy] is set, the value is not read from the store until the first
+  /// call to [get]. If [onSet] is given, it is called on each [set], before
+  /// the barrier. If [sensitive] is set, see [Dust.setAtom].
+  AtomOption(this.id, this.src, this.label, this._serializer,
+      {bool lazy = false, void Function()? onSet, bool sensitive = false})
+      : _onSet = onSet,
+        _sensitive = sensitive {
+    if (!lazy) _subscribe();
+  }
 
-  AtomOption(this.id, this.src, this.label, this._serializer) {
+  void _subscribe() {
+    _subscribed = true;
     final weak = WeakReference(this);
     Dust.instance
         .subscribeAtomById(id, (slv) => weak.target?._update(slv), this);
   }
 
+  @override
+  void connect(Observer o) {
+    if (!_subscribed) _subscribe();
+    super.connect(o);
+  }
+
   @override
   T? get(Observer? o) {
+    if (!_subscribed) _subscribe();
     if (o != null) connect(o);
     return _value;
   }
 
+  /// Returns the clock of the last modification, or `null` if there has been
+  /// none. Clocks increase with each modification.
+  int? clock() => Dust.instance.getAtomClockById(id);
+
   void _update((Id, int, ByteData)? slv) {
     _value =
         (slv == null) ? null : _serializer.deserialize(BytesReader(slv.$3));
@@ -46,7 +71,9 @@ class AtomOption<T> with ObservableMixin<T?> implements ObservableMut<T?> {
   @override
   void set(T? value) {
     Dust.instance.setAtom<T>(
-        id, (value == null) ? null : (src, label, value, _serializer));
+        id, (value == null) ? null : (src, label, value, _serializer),
+        sensitive: _sensitive);
+    _onSet?.call();
     Dust.instance.barrier();
   }
 }
@@ -56,22 +83,56 @@ class Atom<T> with ObservableMixin<T> implements ObservableMut<T> {
   final Id src;
   final int label;
   final Serializer<T> _serializer;
+  final void Function()? _onSet;
+  final bool _sensitive;
+  final T? _fallback;
   T? _value;
+  bool _subscribed = false;
+
+  /// If [lazy] is set, the value is not read from the store until the first
+  /// call to [get]. If [onSet] is given, it is called on each [set], before
+  /// the barrier. If [fallback] is given, it is returned when there is no
+  /// value (e.g. for fields added after the object was created). If
+  /// [sensitive] is set, see [Dust.setAtom].
+  Atom(this.id, this.src, this.label, this._serializer,
+      {bool lazy = false,
+      void Function()? onSet,
+      T? fallback,
+      bool sensitive = false})
+      : _onSet = onSet,
+        _sensitive = sensitive,
+        _fallback = fallback {
+    if (!lazy) _subscribe();
+  }
 
-  Atom(this.id, this.src, this.label, this._serializer) {
+  void _subscribe() {
+    _subscribed = true;
     final weak = WeakReference(this);
     Dust.instance
         .subscribeAtomById(id, (slv) => weak.target?._update(slv), this);
   }
 
+  @override
+  void connect(Observer o) {
+    if (!_subscribed) _subscribe();
+    super.connect(o);
+  }
+
+  /// Returns the cached value, which is only deserialized again when the atom
+  /// changes, so repeated reads return the same instance without allocating.
   @override
   T get(Observer? o) {
+    if (!_subscribed) _subscribe();
     if (o != null) connect(o);
-    final value = this._value;
+    final value = this._value ?? _fallback;
     if (value == null) throw AlreadyDeletedException();
     return value;
   }
 
+  /// Returns the clock of the last modification, or `null` if there has been
+  /// none. Clocks increase with each modification.
+  int? clock() => Dust.instance.getAtomClockById(id);
+
   void _update((Id, int, ByteData)? slv) {
     _value =
         (slv == null) ? null : _serializer.deserialize(BytesReader(slv.$3));
@@ -80,7 +141,9 @@ class Atom<T> with ObservableMixin<T> implements ObservableMut<T> {
 
   @override
   void set(T value) {
-    Dust.instance.setAtom<T>(id, (src, label, value, _serializer));
+    Dust.instance.setAtom<T>(id, (src, label, value, _serializer),
+        sensitive: _sensitive);
+    _onSet?.call();
     Dust.instance.barrier();
   }
 }
@@ -92,8 +155,10 @@ class AtomDefault<T> implements ObservableMut<T> {
   final T _defaultValue;
 
   AtomDefault(
-      Id id, Id src, int label, Serializer<T> serializer, this._defaultValue)
-      : _inner = AtomOption(id, src, label, serializer);
+      Id id, Id src, int label, Serializer<T> serializer, this._defaultValue,
+      {bool lazy = false, void Function()? onSet, bool sensitive = false})
+      : _inner = AtomOption(id, src, label, serializer,
+            lazy: lazy, onSet: onSet, sensitive: sensitive);
 
   Id get id => _inner.id;
   Id get src => _inner.src;
@@ -105,6 +170,9 @@ class AtomDefault<T> implements ObservableMut<T> {
   @override
   T get(Observer? o) => _inner.get(o) ?? _defaultValue;
 
+  /// See [AtomOption.clock].
+  int? clock() => _inner.clock();
+
   @override
   void set(T? value) => _inner.set(value);
 }
@@ -17,33 +17,102 @@ import 'dart:collection';
 import '../reactive.dart';
 import '../store.dart';
 
-class Backlinks<T> with ObservableMixin<List<T>> implements ObservableSet<T> {
+/// The sources of all edges into [dst] with the given labels.
+///
+/// Iterating over it visits the current sources without observing them, same
+/// as `peek()`.
+class Backlinks<T>
+    with ObservableMixin<List<T>>, IterableMixin<T>
+    implements ObservableSet<T> {
   final Id dst;
-  final int label;
+  final List<int> labels;
   final Repository<T> _repository;
+  final bool _unique;
   final Map<Id, Id> _srcs = {};
 
-  Backlinks(this.dst, this.label, this._repository) {
+  Backlinks(Id dst, int label, Repository<T> repository)
+      : this._(dst, [label], repository, false);
+
+  /// Backlinks from edges with any of the given [labels]. A source linking
+  /// here through several of them appears only once.
+  Backlinks.any(Id dst, List<int> labels, Repository<T> repository)
+      : this._(dst, labels, repository, true);
+
+  Backlinks._(this.dst, this.labels, this._repository, this._unique) {
     final weak = WeakReference(this);
-    Dust.instance.subscribeEdgeByDstLabel(
-        dst,
-        label,
-        (id, src) => weak.target?._insert(id, src),
-        (id) => weak.target?._remove(id),
-        this);
+    for (final label in labels) {
+      Dust.instance.subscribeEdgeByDstLabel(
+          dst,
+          label,
+          (id, src) => weak.target?._insert(id, src),
+          (id) => weak.target?._remove(id),
+          this);
+    }
   }
 
+  /// The first of [labels], which is the only one unless created by
+  /// [Backlinks.any].
+  int get label => labels.first;
+
   @override
   List<T> get(Observer? o) {
     if (o != null) connect(o);
     final res = <T>[];
-    for (final src in _srcs.values) {
+    for (final src in _unique ? _srcs.values.toSet() : _srcs.values) {
       final item = _repository.get(src).get(o);
       if (item != null) res.add(item);
     }
     return UnmodifiableListView(res);
   }
 
+  @override
+  Iterator<T> get iterator => peek().iterator;
+
+  void _insert(Id id, Id src) {
+    _srcs[id] = src;
+    notifyAll();
+  }
+
+  void _remove(Id id) {
+    _srcs.remove(id);
+    notifyAll();
+  }
+}
+
+/// The sources of all edges into [dst] (through any link field of any model),
+/// grouped by the labels of the source nodes, e.g. for a "referenced by"
+/// panel. Sources are given as IDs, to be read through the repository whose
+/// `Label` is their key. Sources without nodes are left out.
+///
+/// Changes to the labels of sources alone are not observed; in practice,
+/// labels only change when nodes are removed, which removes their edges too.
+class Referrers
+    with ObservableMixin<Map<int, List<Id>>>
+    implements Observable<Map<int, List<Id>>> {
+  final Id dst;
+  final Map<Id, Id> _srcs = {};
+
+  Referrers(this.dst) {
+    final weak = WeakReference(this);
+    Dust.instance.subscribeEdgeByDst(
+        dst,
+        (id, src, label) => weak.target?._insert(id, src),
+        (id) => weak.target?._remove(id),
+        this);
+  }
+
+  @override
+  Map<int, List<Id>> get(Observer? o) {
+    if (o != null) connect(o);
+    final res = <int, List<Id>>{};
+    for (final src in _srcs.values.toSet()) {
+      Dust.instance.getNodeById(src, (label) {
+        if (label != null) (res[label] ??= []).add(src);
+      });
+    }
+    return UnmodifiableMapView(res);
+  }
+
   void _insert(Id id, Id src) {
     _srcs[id] = src;
     notifyAll();
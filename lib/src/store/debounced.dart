@@ -0,0 +1,70 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+import 'dart:async';
+
+import '../reactive.dart';
+
+/// Wraps an [ObservableMut] (usually an atom bound to a slider or a colour
+/// picker) so that rapid [set] calls are coalesced: the new value is visible
+/// to [get] and observers immediately, but only the last one within [window]
+/// is written to [inner], and so only that one is persisted and synchronised.
+class Debounced<T> with ObservableMixin<T> implements ObservableMut<T> {
+  final ObservableMut<T> inner;
+  final Duration window;
+  Timer? _timer;
+  (T,)? _pending;
+
+  Debounced(this.inner, this.window);
+
+  /// Returns if there is a value not yet written to [inner].
+  bool get isPending => _pending != null;
+
+  @override
+  void connect(Observer o) {
+    super.connect(o);
+    inner.connect(o);
+  }
+
+  @override
+  T get(Observer? o) {
+    if (o != null) connect(o);
+    final pending = _pending;
+    return (pending == null) ? inner.get(null) : pending.$1;
+  }
+
+  @override
+  void set(T value) {
+    _pending = (value,);
+    _timer?.cancel();
+    _timer = Timer(window, flush);
+    notifyAll();
+  }
+
+  /// Writes the pending value (if any) to [inner] right away, e.g. when the
+  /// control loses focus or the page is closed.
+  void flush() {
+    _timer?.cancel();
+    _timer = null;
+    final pending = _pending;
+    if (pending == null) return;
+    _pending = null;
+    inner.set(pending.$1);
+  }
+}
+
+extension DebouncedExtension<T> on ObservableMut<T> {
+  /// See [Debounced].
+  Debounced<T> debounced(Duration window) => Debounced(this, window);
+}
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+import 'dart:typed_data';
+
 import '../ffi/native_structs.dart';
 
 final class Id {
@@ -31,6 +33,29 @@ final class Id {
   @override
   int get hashCode => high ^ low;
 
+  /// Derives a deterministic ID from the bytes given by [write] (e.g. the
+  /// serialized key fields) and the [label] of the model. Both halves are
+  /// FNV-1 hashes, with different offset bases. This is not a cryptographic
+  /// hash, so colliding inputs are easy to construct on purpose.
+  factory Id.hash(int label, void Function(BytesBuilder builder) write) {
+    final builder = BytesBuilder();
+    write(builder);
+    final bytes = builder.takeBytes();
+    const prime = 1099511628211;
+    int fnv(int basis) {
+      var res = basis;
+      for (var i = 0; i < 64; i += 8) {
+        res = (res * prime) ^ ((label >> i) & 0xff);
+      }
+      for (final c in bytes) {
+        res = (res * prime) ^ c;
+      }
+      return res;
+    }
+
+    return Id(fnv(-3750763034362895579), fnv(7113472399480571277));
+  }
+
   /// This is used for generating a deterministic, unique ID for unique atoms/links.
   /// Since both entity ID and label are random, a simple bitwise XOR would suffice.
   Id operator ^(int rhs) => Id(high, low ^ rhs);
@@ -15,14 +15,73 @@
 import '../reactive.dart';
 import '../store.dart';
 
+/// The state of a [LinkOption], which also distinguishes a link that has
+/// never been set from one that has been explicitly cleared.
+sealed class LinkState<T> {
+  const LinkState();
+}
+
+/// The link has never been set.
+final class LinkUnset<T> extends LinkState<T> {
+  const LinkUnset();
+}
+
+/// The link has been set, but has since been cleared.
+final class LinkCleared<T> extends LinkState<T> {
+  const LinkCleared();
+}
+
+/// The link pointed to [target], which has since been deleted (and the link
+/// with it). This is only known while the [LinkOption] stays in memory, after
+/// which the link reads as [LinkCleared].
+final class LinkDangling<T> extends LinkState<T> {
+  final Id target;
+  const LinkDangling(this.target);
+}
+
+/// The link points to [target].
+final class LinkSome<T> extends LinkState<T> {
+  final T target;
+  const LinkSome(this.target);
+}
+
+/// Resolves the destination of link [id], throwing if it is a node of another
+/// type instead of silently returning `null`.
+T? _resolve<T>(Id id, Id dst, Repository<T> repository, Observer? o) {
+  final node = repository.get(dst);
+  final res = node.get(o);
+  final actual = node.storedLabel;
+  if (res == null && actual != null) {
+    throw LinkTypeMismatchException(id, dst, node.label, actual);
+  }
+  return res;
+}
+
+/// Throws unless [dst] is an existing node of the model type of [repository],
+/// see [Link.setId].
+void _checkTarget<T>(Id id, Id dst, Repository<T> repository) {
+  final node = repository.get(dst);
+  final actual = node.storedLabel;
+  if (actual == null) throw AlreadyDeletedException<T>();
+  if (actual != node.label) {
+    throw LinkTypeMismatchException(id, dst, node.label, actual);
+  }
+}
+
 class LinkOption<T> with ObservableMixin<T?> implements ObservableMut<T?> {
   final Id id;
   final Id src;
   final int label;
   final Repository<T> _repository;
+  final void Function()? _onSet;
   Id? _dst;
+  Id? _dangling;
+  bool _written = false;
 
-  LinkOption(this.id, this.src, this.label, this._repository) {
+  /// If [onSet] is given, it is called on each [set], before the barrier.
+  LinkOption(this.id, this.src, this.label, this._repository,
+      {void Function()? onSet})
+      : _onSet = onSet {
     final weak = WeakReference(this);
     Dust.instance
         .subscribeEdgeById(id, (sld) => weak.target?._update(sld), this);
@@ -32,11 +91,33 @@ class LinkOption<T> with ObservableMixin<T?> implements ObservableMut<T?> {
   T? get(Observer? o) {
     if (o != null) connect(o);
     final dst = this._dst;
-    return (dst == null) ? null : _repository.get(dst).get(o);
+    return (dst == null) ? null : _resolve(id, dst, _repository, o);
+  }
+
+  /// Same as [get], but also tells apart [LinkUnset] from [LinkCleared] and
+  /// [LinkDangling].
+  LinkState<T> state(Observer? o) {
+    final value = get(o);
+    if (value != null) return LinkSome(value);
+    if (_dangling case final target?) return LinkDangling(target);
+    return _written ? LinkCleared() : LinkUnset();
   }
 
+  /// Returns the clock of the last modification, or `null` if there has been
+  /// none. Clocks increase with each modification.
+  int? clock() => Dust.instance.getEdgeClockById(id);
+
   void _update((Id, int, Id)? sld) {
+    final prev = _dst;
     _dst = (sld == null) ? null : sld.$3;
+    // Edges are removed together with their destination nodes.
+    var exists = true;
+    if (sld == null && prev != null) {
+      Dust.instance.getNodeById(prev, (label) => exists = label != null);
+    }
+    _dangling = exists ? null : prev;
+    // An absent edge leaves a tombstone once written.
+    _written = (sld != null) || Dust.instance.getEdgeWrittenById(id);
     notifyAll();
   }
 
@@ -44,8 +125,24 @@ class LinkOption<T> with ObservableMixin<T?> implements ObservableMut<T?> {
   void set(T? value) {
     Dust.instance.setEdge(
         id, (value == null) ? null : (src, label, _repository.id(value)));
+    _onSet?.call();
     Dust.instance.barrier();
   }
+
+  /// Same as [set], but takes the ID of the target, e.g. one received from
+  /// elsewhere, without reading the target itself. See [Link.setId].
+  void setId(Id dst, {bool check = false}) {
+    if (check) _checkTarget(id, dst, _repository);
+    Dust.instance.setEdge(id, (src, label, dst));
+    _onSet?.call();
+    Dust.instance.barrier();
+  }
+
+  /// Sets this link to `null`. The edge keeps its ID and is left as a
+  /// written placeholder, so the field is still loaded (as [LinkCleared]) and
+  /// can be set again later. Use [Multilinks.remove] to drop an element of a
+  /// multilink instead.
+  void clear() => set(null);
 }
 
 class Link<T> with ObservableMixin<T> implements ObservableMut<T> {
@@ -53,9 +150,13 @@ class Link<T> with ObservableMixin<T> implements ObservableMut<T> {
   final Id src;
   final int label;
   final Repository<T> _repository;
+  final void Function()? _onSet;
   Id? _dst;
 
-  Link(this.id, this.src, this.label, this._repository) {
+  /// If [onSet] is given, it is called on each [set], before the barrier.
+  Link(this.id, this.src, this.label, this._repository,
+      {void Function()? onSet})
+      : _onSet = onSet {
     final weak = WeakReference(this);
     Dust.instance
         .subscribeEdgeById(id, (sld) => weak.target?._update(sld), this);
@@ -66,10 +167,14 @@ class Link<T> with ObservableMixin<T> implements ObservableMut<T> {
     if (o != null) connect(o);
     final dst = this._dst;
     if (dst == null) throw AlreadyDeletedException();
-    return _repository.get(dst).get(o)!;
+    return _resolve(id, dst, _repository, o)!;
     // This should never be `null`, and is guaranteed by stickiness constraints.
   }
 
+  /// Returns the clock of the last modification, or `null` if there has been
+  /// none. Clocks increase with each modification.
+  int? clock() => Dust.instance.getEdgeClockById(id);
+
   void _update((Id, int, Id)? sld) {
     _dst = (sld == null) ? null : sld.$3;
     notifyAll();
@@ -78,6 +183,20 @@ class Link<T> with ObservableMixin<T> implements ObservableMut<T> {
   @override
   void set(T value) {
     Dust.instance.setEdge(id, (src, label, _repository.id(value)));
+    _onSet?.call();
+    Dust.instance.barrier();
+  }
+
+  /// Same as [set], but takes the ID of the target, e.g. one received from
+  /// elsewhere, without reading the target itself. Links to missing nodes are
+  /// removed by the barrier (together with this object, since links are
+  /// sticky), so if [check] is set, this first throws an
+  /// [AlreadyDeletedException] if there is no such node, or a
+  /// [LinkTypeMismatchException] if it is of another model type.
+  void setId(Id dst, {bool check = false}) {
+    if (check) _checkTarget(id, dst, _repository);
+    Dust.instance.setEdge(id, (src, label, dst));
+    _onSet?.call();
     Dust.instance.barrier();
   }
 }
@@ -0,0 +1,68 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// The wrapper a field is generated with.
+enum FieldKind {
+  atom,
+  atomOption,
+  atomDefault,
+  link,
+  linkOption,
+  multilinks,
+  backlinks,
+  timestamp,
+}
+
+/// Describes a field of a model, see [ModelInfo].
+class FieldInfo {
+  final String name;
+  final FieldKind kind;
+
+  /// The declared value type, or the target model for links.
+  final String type;
+
+  /// The label of the atoms or edges of this field, or `null` for backlinks
+  /// (which read the labels of the fields they point back to).
+  final int? label;
+
+  /// Whether bindings for other languages should omit setters for this field,
+  /// see `Bindings`.
+  final bool readonly;
+
+  /// Whether bindings for other languages should omit this field, see `Bindings`.
+  final bool hidden;
+
+  const FieldInfo({
+    required this.name,
+    required this.kind,
+    required this.type,
+    required this.label,
+    this.readonly = false,
+    this.hidden = false,
+  });
+}
+
+/// Describes the shape of a model as generated, for tooling (e.g. generating
+/// code for other languages, or inspecting a database). See [Dust.manifest].
+class ModelInfo {
+  final String name;
+  final int label;
+  final List<FieldInfo> fields;
+
+  const ModelInfo({
+    required this.name,
+    required this.label,
+    required this.fields,
+  });
+}
@@ -13,19 +13,75 @@
 // limitations under the License.
 
 import 'dart:collection';
+import 'dart:typed_data';
 
 import '../reactive.dart';
+import '../serializers.dart';
 import '../store.dart';
 
+/// Returns a string strictly between [lower] and [upper] in lexicographic
+/// order, where `null` stands for the minimum or maximum respectively. Keys
+/// consist of lowercase letters and never end with `a`, so that there is
+/// always room for another key before any of them.
+String orderKeyBetween(String? lower, String? upper) {
+  const base = 0x61; // `a`
+  final sb = StringBuffer();
+  final lo = lower ?? '';
+  for (var i = 0;; i++) {
+    final l = i < lo.length ? lo.codeUnitAt(i) - base : 0;
+    final u = (upper != null && i < upper.length)
+        ? upper.codeUnitAt(i) - base
+        : 26;
+    final mid = (l + u) ~/ 2;
+    if (mid > l) {
+      sb.writeCharCode(base + mid);
+      return sb.toString();
+    }
+    sb.writeCharCode(base + l);
+    // Once below [upper] at this position, any suffix above [lower] will do.
+    if (u > l) upper = null;
+  }
+}
+
+/// A multiset of links from [src] with the same label.
+///
+/// Each target is stored as a separate edge with a random ID, so inserting a
+/// target twice (or two devices inserting it concurrently) gives two edges to
+/// it, and it is returned twice.
+///
+/// If [distinct] is set, these are read as one (at the position of the
+/// first), [insert] does nothing for targets already present, and [remove]
+/// removes all edges to a target that are known at the time. A concurrent
+/// insertion which has not been seen yet survives the removal, so insertions
+/// win.
+///
+/// Iterating over it (e.g. `for (final target in model.links$) {}`) visits
+/// the current targets without observing them, same as `peek()`.
 class Multilinks<T>
-    with ObservableMixin<List<T>>
+    with ObservableMixin<List<T>>, IterableMixin<T>
     implements ObservableMutSet<T> {
   final Id src;
   final int label;
+  final int? orderLabel;
+  final bool distinct;
   final Repository<T> _repository;
+  final void Function()? _onSet;
   final Map<Id, Id> _dsts = {};
+  final Map<Id, String> _keys = {};
 
-  Multilinks(this.src, this.label, this._repository) {
+  /// If [onSet] is given, it is called on each [insert], [extend] and
+  /// [remove], before the barrier.
+  ///
+  /// If [orderLabel] is given, each edge gets an order key, stored as a
+  /// `String` atom from [src] with that label at the edge ID `^ orderLabel`.
+  /// Targets are then returned sorted by their keys (ties broken by edge ID),
+  /// new ones are appended at the end, and [moveBefore] and [moveAfter] can be
+  /// used to reorder them.
+  ///
+  /// If [distinct] is set, each target is kept at most once (see above).
+  Multilinks(this.src, this.label, this._repository,
+      {void Function()? onSet, this.orderLabel, this.distinct = false})
+      : _onSet = onSet {
     final weak = WeakReference(this);
     Dust.instance.subscribeEdgeBySrcLabel(
         src,
@@ -33,19 +89,73 @@ class Multilinks<T>
         (id, dst) => weak.target?._insert(id, dst),
         (id) => weak.target?._remove(id),
         this);
+    final orderLabel = this.orderLabel;
+    if (orderLabel != null) {
+      Dust.instance.subscribeAtomBySrcLabel(
+          src,
+          orderLabel,
+          (id, value) => weak.target?._insertKey(id ^ orderLabel, value),
+          (id) => weak.target?._removeKey(id ^ orderLabel),
+          this);
+    }
+  }
+
+  /// Creates edges from [src] to each of [dsts], without calling
+  /// [Dust.barrier]. If [orderLabel] is given, they get order keys in the
+  /// given order, all after [lastKey].
+  static void write(Id src, int label, Iterable<Id> dsts,
+      {int? orderLabel, String? lastKey}) {
+    var key = lastKey;
+    for (final dst in dsts) {
+      final id = Dust.instance.edgeId(src);
+      Dust.instance.setEdge(id, (src, label, dst));
+      if (orderLabel != null) {
+        key = orderKeyBetween(key, null);
+        Dust.instance.setAtom(
+            id ^ orderLabel, (src, orderLabel, key, const StringSerializer()));
+      }
+    }
+  }
+
+  /// Returns edge IDs, sorted by their order keys if ordered.
+  List<Id> _edges() {
+    final res = _dsts.keys.toList();
+    if (orderLabel != null) {
+      int compareIds(Id a, Id b) =>
+          a.high != b.high ? a.high.compareTo(b.high) : a.low.compareTo(b.low);
+      res.sort((a, b) {
+        final ka = _keys[a] ?? '', kb = _keys[b] ?? '';
+        final c = ka.compareTo(kb);
+        return c != 0 ? c : compareIds(a, b);
+      });
+    }
+    return res;
+  }
+
+  /// Same as [_edges], but only the first edge to each target if [distinct].
+  List<Id> _unique() {
+    if (!distinct) return _edges();
+    final seen = <Id>{};
+    return [
+      for (final edge in _edges())
+        if (seen.add(_dsts[edge]!)) edge
+    ];
   }
 
   @override
   List<T> get(Observer? o) {
     if (o != null) connect(o);
     final res = <T>[];
-    for (final dst in _dsts.values) {
-      final item = _repository.get(dst).get(o);
+    for (final edge in _unique()) {
+      final item = _repository.get(_dsts[edge]!).get(o);
       if (item != null) res.add(item);
     }
     return UnmodifiableListView(res);
   }
 
+  @override
+  Iterator<T> get iterator => peek().iterator;
+
   void _insert(Id id, Id dst) {
     _dsts[id] = dst;
     notifyAll();
@@ -56,21 +166,111 @@ class Multilinks<T>
     notifyAll();
   }
 
+  void _insertKey(Id edge, ByteData value) {
+    _keys[edge] = const StringSerializer().deserialize(BytesReader(value));
+    notifyAll();
+  }
+
+  void _removeKey(Id edge) {
+    _keys.remove(edge);
+    notifyAll();
+  }
+
+  String? _lastKey() {
+    final edges = _edges();
+    return edges.isEmpty ? null : _keys[edges.last];
+  }
+
+  /// Does nothing if [distinct] and [value] is already present.
   @override
-  void insert(T value) {
-    Dust.instance
-        .setEdge(Dust.instance.randomId(), (src, label, _repository.id(value)));
+  void insert(T value) => extend([value]);
+
+  /// Inserts all of [values] with a single [Dust.barrier] call, which is
+  /// much cheaper than calling [insert] on each of them. If [distinct],
+  /// values which are already present (or repeated) are skipped.
+  void extend(Iterable<T> values) {
+    final seen = _dsts.values.toSet();
+    final dsts = values
+        .map(_repository.id)
+        .where((dst) => !distinct || seen.add(dst))
+        .toList();
+    if (dsts.isEmpty) return;
+    write(src, label, dsts, orderLabel: orderLabel, lastKey: _lastKey());
+    _onSet?.call();
     Dust.instance.barrier();
   }
 
+  /// Removes the first edge to [value] (all edges to it if [distinct]), if
+  /// any. Unlike [LinkOption.clear], the edges are not kept as placeholders:
+  /// they are removed from all indices (together with their order keys), so
+  /// nothing of them is loaded afterwards.
   @override
   void remove(T value) {
-    for (final entry in _dsts.entries) {
-      if (entry.value == _repository.id(value)) {
-        Dust.instance.setEdge(entry.key, null);
-        Dust.instance.barrier();
-        break;
+    final dst = _repository.id(value);
+    final edges = [
+      for (final MapEntry(:key, :value) in _dsts.entries)
+        if (value == dst) key
+    ];
+    if (!distinct && edges.length > 1) edges.length = 1;
+    if (edges.isEmpty) return;
+    _removeEdges(edges);
+    _onSet?.call();
+    Dust.instance.barrier();
+  }
+
+  /// Removes [edges] and their order keys, without calling [Dust.barrier].
+  void _removeEdges(Iterable<Id> edges) {
+    final orderLabel = this.orderLabel;
+    for (final edge in edges) {
+      Dust.instance.setEdge(edge, null);
+      if (orderLabel != null) {
+        Dust.instance.setAtom(edge ^ orderLabel, null);
       }
     }
   }
+
+  /// Moves [value] to just before [anchor]. Both must be present and
+  /// distinct (otherwise throws an [ArgumentError]), and the multilinks must
+  /// be ordered.
+  void moveBefore(T value, T anchor) => _move(value, anchor, after: false);
+
+  /// Moves [value] to just after [anchor]. Both must be present and distinct
+  /// (otherwise throws an [ArgumentError]), and the multilinks must be
+  /// ordered.
+  void moveAfter(T value, T anchor) => _move(value, anchor, after: true);
+
+  void _move(T value, T anchor, {required bool after}) {
+    final orderLabel = this.orderLabel;
+    assert(orderLabel != null, 'Multilinks must be ordered to be moved.');
+    if (orderLabel == null) return;
+    final valueId = _repository.id(value);
+    final anchorId = _repository.id(anchor);
+    if (valueId == anchorId) {
+      throw ArgumentError.value(anchor, 'anchor', 'Must differ from value');
+    }
+    final edges = _unique();
+    final valueIndex = edges.indexWhere((e) => _dsts[e] == valueId);
+    if (valueIndex < 0) {
+      throw ArgumentError.value(value, 'value', 'Not in the multilinks');
+    }
+    final anchorIndex = edges.indexWhere((e) => _dsts[e] == anchorId);
+    if (anchorIndex < 0) {
+      throw ArgumentError.value(anchor, 'anchor', 'Not in the multilinks');
+    }
+    final edge = edges.removeAt(valueIndex);
+    if (distinct) {
+      // Collapses duplicates, which would otherwise keep the old position.
+      _removeEdges(
+          _edges().where((e) => e != edge && _dsts[e] == _dsts[edge]));
+    }
+    final index =
+        anchorIndex - (valueIndex < anchorIndex ? 1 : 0) + (after ? 1 : 0);
+    final lower = index > 0 ? _keys[edges[index - 1]] : null;
+    final upper = index < edges.length ? _keys[edges[index]] : null;
+    final key = orderKeyBetween(lower, upper);
+    Dust.instance.setAtom(
+        edge ^ orderLabel, (src, orderLabel, key, const StringSerializer()));
+    _onSet?.call();
+    Dust.instance.barrier();
+  }
 }
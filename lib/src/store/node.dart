@@ -32,6 +32,10 @@ class NodeOption<T> extends ObservableMixin<T?> implements Observable<T?> {
     return _value == label ? _model : null;
   }
 
+  /// The label of the node currently stored at [id], which may differ from
+  /// [label] if the node is of another type, or `null` if there is none.
+  int? get storedLabel => _value;
+
   void _update(int? label) {
     _value = label;
     notifyAll();
@@ -39,7 +43,8 @@ class NodeOption<T> extends ObservableMixin<T?> implements Observable<T?> {
 }
 
 /// Just a simple wrapper around [NodeOption], calling an initialiser function
-/// to create the model when there is no such node present.
+/// to create the model when there is no such node present. Throws
+/// [IdCollisionException] instead if there is a node of a different label.
 class NodeAuto<T> implements Observable<T> {
   final NodeOption _inner;
   final void Function() _callback;
@@ -54,6 +59,10 @@ class NodeAuto<T> implements Observable<T> {
   T get(Observer? o) {
     final res = _inner.get(o);
     if (res != null) return res;
+    final existing = _inner._value;
+    if (existing != null) {
+      throw IdCollisionException(_inner.id, existing, _inner.label);
+    }
     _callback();
     final r = _inner.get(o)!;
     _onCreated?.call(r);
@@ -61,6 +70,22 @@ class NodeAuto<T> implements Observable<T> {
   }
 }
 
+/// The ID of a newly created object, which is only read back from the store
+/// (constructing the model and subscribing to its fields) when [model] is
+/// first accessed, see the `createLazy` functions of repositories.
+class Created<T> {
+  final Id id;
+  final Repository<T> _repository;
+  T? _model;
+
+  Created(this.id, this._repository);
+
+  /// Returns if [model] has been accessed yet.
+  bool get isResolved => _model != null;
+
+  T get model => _model ??= _repository.get(id).get(null)!;
+}
+
 class NodesByLabel<T>
     with ObservableMixin<List<T>>
     implements ObservableSet<T> {
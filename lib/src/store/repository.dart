@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+import 'dart:typed_data';
+
+import '../serializer.dart';
 import '../store.dart';
 
 abstract interface class Repository<T> {
@@ -24,3 +27,25 @@ abstract interface class Repository<T> {
 
   void delete(T model);
 }
+
+/// Returns if [a] and [b] serialize to the same bytes, which compares e.g.
+/// lists by their elements. Used by the generated `contentEquals` functions.
+bool serializedEquals<T>(Serializer<T> serializer, T a, T b) {
+  final lhs = BytesBuilder();
+  final rhs = BytesBuilder();
+  serializer.serialize(a, lhs);
+  serializer.serialize(b, rhs);
+  final (x, y) = (lhs.takeBytes(), rhs.takeBytes());
+  if (x.length != y.length) return false;
+  for (var i = 0; i < x.length; i++) {
+    if (x[i] != y[i]) return false;
+  }
+  return true;
+}
+
+/// Returns if [a] and [b] contain the same IDs, ignoring order and duplicates.
+bool idSetEquals(Iterable<Id> a, Iterable<Id> b) {
+  final lhs = a.toSet();
+  final rhs = b.toSet();
+  return lhs.length == rhs.length && lhs.containsAll(rhs);
+}
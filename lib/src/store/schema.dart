@@ -12,12 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+import 'manifest.dart';
+
 class Schema {
+  /// The node label of the model managed by the repository, if any, see
+  /// [Dust.resolve].
+  final int? label;
+
+  /// The shape of the model managed by the repository, if any, see
+  /// [Dust.manifest].
+  final ModelInfo? model;
   final List<int> stickyNodes;
   final List<int> stickyAtoms;
   final List<int> stickyEdges;
   final List<int> acyclicEdges;
   const Schema({
+    this.label,
+    this.model,
     required this.stickyNodes,
     required this.stickyAtoms,
     required this.stickyEdges,
@@ -0,0 +1,49 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+import 'dart:async';
+
+import '../reactive.dart';
+
+/// Returns a stream which emits [value] on listen, and again whenever the
+/// subscription made by [subscribe] calls back (used by the generated `watch`
+/// functions of models). At most one event is buffered: changes made while
+/// the listener is paused are coalesced, so slow consumers only see the
+/// latest state. The subscription is cancelled with the stream.
+Stream<T> watchStream<T>(
+    T value, Subscription Function(void Function(T value) f) subscribe) {
+  late final StreamController<T> controller;
+  Subscription? subscription;
+  var pending = true;
+
+  void flush() {
+    if (!pending || controller.isPaused) return;
+    pending = false;
+    controller.add(value);
+  }
+
+  controller = StreamController<T>(
+    onListen: () {
+      subscription = subscribe((curr) {
+        value = curr;
+        pending = true;
+        flush();
+      });
+      flush();
+    },
+    onResume: flush,
+    onCancel: () => subscription?.cancel(),
+  );
+  return controller.stream;
+}
@@ -1,3 +1,4 @@
+#include <stdbool.h>
 #include <stdint.h>
 
 #define CURRENT_VERSION 1
@@ -212,6 +213,22 @@ typedef struct CResultOptionEdge {
   };
 } CResultOptionEdge;
 
+typedef struct CResultBool {
+  uint8_t tag;
+  union {
+    bool ok;
+    CArrayUint8 err;
+  };
+} CResultBool;
+
+typedef struct CResultUint64 {
+  uint8_t tag;
+  union {
+    uint64_t ok;
+    CArrayUint8 err;
+  };
+} CResultUint64;
+
 typedef struct CResultArrayTripleIdUint64Id {
   uint8_t tag;
   union {
@@ -262,6 +279,8 @@ void add_sticky_node(uint64_t label);
 
 CResultOptionAtom atom(uint64_t idh, uint64_t idl);
 
+CResultUint64 atom_clock(uint64_t idh, uint64_t idl);
+
 CResultArrayTripleIdUint64ArrayUint8 atom_id_label_value_by_src(uint64_t srch,
                                                                 uint64_t srcl);
 
@@ -339,12 +358,18 @@ CResultArrayPairIdId edge_id_dst_by_src_label(uint64_t srch, uint64_t srcl,
 CResultArrayTripleIdUint64Id edge_id_label_dst_by_src(uint64_t srch,
                                                       uint64_t srcl);
 
+CResultBool edge_written(uint64_t idh, uint64_t idl);
+
+CResultUint64 edge_clock(uint64_t idh, uint64_t idl);
+
 CResultArrayPairIdId edge_id_src_by_dst_label(uint64_t dsth, uint64_t dstl,
                                               uint64_t label);
 
 CResultArrayTripleIdIdUint64 edge_id_src_label_by_dst(uint64_t dsth,
                                                       uint64_t dstl);
 
+CResultUnit enable_node_label_cache(void);
+
 CResultOptionNode node(uint64_t idh, uint64_t idl);
 
 CResultArrayId node_id_by_label(uint64_t label);
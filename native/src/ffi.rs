@@ -23,32 +23,38 @@ use std::cell::RefCell;
 
 use self::structs::{CArray, CAtom, CEdge, CEventData, CId, CNode, COption, CPair, CResult, CTriple, CUnit};
 use crate::{
-  store::Store,
+  store::{stats::busy_handler, Store},
   workspace::{Constraints, Workspace},
   StoreError, Transactor,
 };
 
 thread_local! {
   static CONSTRAINTS: RefCell<Constraints> = RefCell::new(Constraints::new());
-  static STORE: RefCell<Option<Store>> = RefCell::new(None);
+  static STORE: RefCell<Option<Store>> = const { RefCell::new(None) };
 }
 
 pub fn convert_result<T>(f: impl FnOnce() -> Result<T, StoreError>) -> CResult<T> {
   f().map_err(|err| err.to_string()).into()
 }
 
-pub fn access_workspace<T>(f: impl FnOnce(&mut Transactor, &mut Workspace) -> Result<T, StoreError>) -> CResult<T> {
+pub fn access_store<T>(f: impl FnOnce(&mut Store) -> Result<T, StoreError>) -> CResult<T> {
   STORE
     .with(|cell| {
       let mut borrow = cell.borrow_mut();
       let store = borrow.as_mut().ok_or(StoreError::Uninitialised)?;
-      let (txr, ws) = store.as_mut()?;
-      f(txr, ws)
+      f(store)
     })
     .map_err(|err| err.to_string())
     .into()
 }
 
+pub fn access_workspace<T>(f: impl FnOnce(&mut Transactor, &mut Workspace) -> Result<T, StoreError>) -> CResult<T> {
+  access_store(|store| {
+    let (txr, ws) = store.as_mut()?;
+    f(txr, ws)
+  })
+}
+
 #[no_mangle]
 pub extern "C" fn dust_add_sticky_node(label: u64) {
   CONSTRAINTS.with(|cell| cell.borrow_mut().add_sticky_node(label));
@@ -87,9 +93,9 @@ pub unsafe extern "C" fn dust_open(len: u64, ptr: *mut u8) -> CResult<CUnit> {
       PRAGMA synchronous = NORMAL;
       PRAGMA wal_autocheckpoint = 2000;
       PRAGMA cache_size = 2000;
-      PRAGMA busy_timeout = 1000;
       ",
     )?;
+    conn.busy_handler(Some(busy_handler))?;
     let store = Store::new(conn, CONSTRAINTS.with(|cell| cell.borrow().clone()))?;
     STORE.with(|cell| cell.replace(Some(store)));
     Ok(CUnit(0))
@@ -36,6 +36,14 @@ pub extern "C" fn dust_node_id_by_label(label: u64) -> CResult<CArray<CId>> {
   })
 }
 
+#[no_mangle]
+pub extern "C" fn dust_enable_node_label_cache() -> CResult<CUnit> {
+  access_workspace(|_, ws| {
+    ws.enable_node_label_cache();
+    Ok(CUnit(0))
+  })
+}
+
 #[no_mangle]
 pub extern "C" fn dust_atom(idh: u64, idl: u64) -> CResult<COption<CAtom>> {
   access_workspace(|txr, ws| {
@@ -44,6 +52,16 @@ pub extern "C" fn dust_atom(idh: u64, idl: u64) -> CResult<COption<CAtom>> {
   })
 }
 
+/// Returns the clock of the last modification of the atom, or zero if it has
+/// never been written.
+#[no_mangle]
+pub extern "C" fn dust_atom_clock(idh: u64, idl: u64) -> CResult<u64> {
+  access_workspace(|txr, ws| {
+    let id = CId(idh, idl).into();
+    Ok(ws.atom_clock(txr, id).unwrap_or(0))
+  })
+}
+
 #[no_mangle]
 pub extern "C" fn dust_atom_id_label_value_by_src(
   srch: u64,
@@ -118,6 +136,24 @@ pub extern "C" fn dust_edge(idh: u64, idl: u64) -> CResult<COption<CEdge>> {
   })
 }
 
+/// Returns the clock of the last modification of the edge, or zero if it has
+/// never been written.
+#[no_mangle]
+pub extern "C" fn dust_edge_clock(idh: u64, idl: u64) -> CResult<u64> {
+  access_workspace(|txr, ws| {
+    let id = CId(idh, idl).into();
+    Ok(ws.edge_clock(txr, id).unwrap_or(0))
+  })
+}
+
+#[no_mangle]
+pub extern "C" fn dust_edge_written(idh: u64, idl: u64) -> CResult<bool> {
+  access_workspace(|txr, ws| {
+    let id = CId(idh, idl).into();
+    Ok(ws.edge_written(txr, id))
+  })
+}
+
 #[no_mangle]
 pub extern "C" fn dust_edge_id_label_dst_by_src(srch: u64, srcl: u64) -> CResult<CArray<CTriple<CId, u64, CId>>> {
   access_workspace(|txr, ws| {
@@ -272,5 +308,5 @@ pub unsafe extern "C" fn dust_sync_join(len: u64, ptr: *mut u8) -> CResult<CUnit
 
 #[no_mangle]
 pub extern "C" fn dust_barrier() -> CResult<CArray<CEventData>> {
-  access_workspace(|txr, ws| Ok(ws.barrier(txr).into()))
+  access_store(|store| Ok(store.barrier()?.into()))
 }
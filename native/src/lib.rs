@@ -17,7 +17,7 @@ pub mod store;
 pub mod workspace;
 
 use bincode::{ErrorKind, Options};
-use rusqlite::Connection;
+use rusqlite::{Connection, ErrorCode};
 use serde::{Deserialize, Serialize};
 use std::{
   num::Wrapping,
@@ -36,6 +36,14 @@ pub enum StoreError {
   Uninitialised,
   #[error("data store disconnected due to previous error")]
   Disconnected,
+  #[error("io error: {0}")]
+  Io(#[from] std::io::Error),
+  #[error("invalid NDJSON on line {0}")]
+  InvalidNdjson(usize),
+  #[error("sequence number {0} is not in the event log")]
+  InvalidSequence(u64),
+  #[error("attached database has no table {0}")]
+  MissingTable(String),
 }
 
 /// A wrapper around `bincode`.
@@ -48,6 +56,9 @@ pub fn deserialize<'a, T: Deserialize<'a>>(bytes: &'a [u8]) -> Result<T, Box<Err
   bincode::options().reject_trailing_bytes().with_fixint_encoding().with_big_endian().deserialize(bytes)
 }
 
+/// Enough to hold all standard statements, see [`workspace::Workspace::warm_up`].
+const PREPARED_STATEMENT_CACHE_CAPACITY: usize = 64;
+
 /// A wrapper around `rusqlite`.
 pub struct Transactor {
   conn: Connection,
@@ -69,8 +80,66 @@ impl DerefMut for Transactor {
 impl TryFrom<Connection> for Transactor {
   type Error = rusqlite::Error;
   fn try_from(value: Connection) -> rusqlite::Result<Self> {
-    value.execute_batch("BEGIN IMMEDIATE")?;
-    Ok(Self { conn: value })
+    Self::immediate(value, &store::stats::RetryPolicy::NONE)
+  }
+}
+
+impl Transactor {
+  /// Starts an immediate transaction, which takes the write lock right away
+  /// (so that writes within it can never be busy), retrying according to
+  /// `policy` while another connection holds it. Since nothing has been done
+  /// before the lock is taken, retrying is always safe.
+  pub fn immediate(conn: Connection, policy: &store::stats::RetryPolicy) -> rusqlite::Result<Self> {
+    conn.set_prepared_statement_cache_capacity(PREPARED_STATEMENT_CACHE_CAPACITY);
+    let mut delays = policy.delays();
+    loop {
+      match conn.execute_batch("BEGIN IMMEDIATE") {
+        Err(rusqlite::Error::SqliteFailure(err, _)) if err.code == ErrorCode::DatabaseBusy => match delays.next() {
+          Some(delay) => std::thread::sleep(delay),
+          None => return Err(rusqlite::Error::SqliteFailure(err, None)),
+        },
+        res => break res?,
+      }
+    }
+    Ok(Self { conn })
+  }
+
+  /// Starts a deferred transaction instead, which does not take the write lock
+  /// until the first write. In WAL mode, any number of these may read in
+  /// parallel with a writer, each seeing the last commit before its first read.
+  pub fn deferred(conn: Connection) -> rusqlite::Result<Self> {
+    let res = Self::wrap(conn);
+    res.begin_deferred()?;
+    Ok(res)
+  }
+
+  /// Same as [`Transactor::deferred`], but reads once right away, which pins
+  /// the snapshot to the last commit before this call.
+  pub fn snapshot(conn: Connection) -> rusqlite::Result<Self> {
+    let res = Self::wrap(conn);
+    res.begin_snapshot()?;
+    Ok(res)
+  }
+
+  /// Wraps `conn` without starting a transaction, for callers which need to
+  /// keep the connection even if starting one fails.
+  pub(crate) fn wrap(conn: Connection) -> Self {
+    conn.set_prepared_statement_cache_capacity(PREPARED_STATEMENT_CACHE_CAPACITY);
+    Self { conn }
+  }
+
+  /// Unwraps the connection, without ending the current transaction.
+  pub(crate) fn into_inner(self) -> Connection {
+    self.conn
+  }
+
+  pub(crate) fn begin_deferred(&self) -> rusqlite::Result<()> {
+    self.conn.execute_batch("BEGIN DEFERRED")
+  }
+
+  pub(crate) fn begin_snapshot(&self) -> rusqlite::Result<()> {
+    self.begin_deferred()?;
+    self.conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
   }
 }
 
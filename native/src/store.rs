@@ -12,21 +12,67 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use rusqlite::Connection;
+pub mod health;
+pub mod log;
+pub mod pool;
+pub mod stats;
 
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Instant;
+
+use self::health::HealthReport;
+use self::log::{EventLog, Mutation};
+use self::stats::{RetryPolicy, TransactionStats};
+use crate::ffi::structs::CEventData;
 use crate::workspace::{Constraints, Workspace};
 use crate::{StoreError, Transactor};
 
+/// A fully-owned copy of a node with all atoms and edges starting from it,
+/// which can be passed around without access to the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeSnapshot {
+  pub id: u128,
+  pub label: u64,
+  pub atoms: BTreeMap<u128, (u64, Box<[u8]>)>,
+  pub edges: BTreeMap<u128, (u64, u128)>,
+}
+
 pub struct Store {
   txr: Option<Transactor>,
   workspace: Workspace,
+  log: Option<EventLog>,
+  began: Instant,
+  stats: TransactionStats,
+  retry: RetryPolicy,
 }
 
 impl Store {
   pub fn new(conn: Connection, constraints: Constraints) -> Result<Self, StoreError> {
-    let mut txr = conn.try_into()?;
-    let workspace = Workspace::new("", constraints, &mut txr);
-    Ok(Self { txr: Some(txr), workspace })
+    Self::with_prefix(conn, "", constraints)
+  }
+
+  /// Same as [`Store::new`], but keeps all data in tables named
+  /// `"{prefix}.{name}.*"`, so that several stores (e.g. of different app
+  /// versions during a migration) can share one database without sharing
+  /// rows.
+  pub fn with_prefix(conn: Connection, prefix: &'static str, constraints: Constraints) -> Result<Self, StoreError> {
+    let retry = RetryPolicy::default();
+    let mut txr = Transactor::immediate(conn, &retry)?;
+    let workspace = Workspace::new(prefix, constraints, &mut txr);
+    workspace.warm_up(&mut txr);
+    let (began, stats) = (Instant::now(), TransactionStats::default());
+    Ok(Self { txr: Some(txr), workspace, log: None, began, stats, retry })
+  }
+
+  /// Sets how beginning each transaction (right after the previous commit)
+  /// is retried while another connection holds the write lock. Defaults to
+  /// [`RetryPolicy::default`]. Closures run on the store (e.g. through
+  /// [`pool::StorePool::write`]) only start once the lock is held, so they are
+  /// never run twice and need not be free of side effects.
+  pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+    self.retry = policy;
   }
 
   pub fn as_mut(&mut self) -> Result<(&mut Transactor, &mut Workspace), StoreError> {
@@ -34,14 +80,185 @@ impl Store {
     Ok((txr, &mut self.workspace))
   }
 
+  /// Reads a node together with its atoms and edges, including modifications
+  /// not yet committed. Returns `None` if the node does not exist.
+  pub fn snapshot(&self, id: u128) -> Result<Option<NodeSnapshot>, StoreError> {
+    let txr = self.txr.as_ref().ok_or(StoreError::Disconnected)?;
+    Ok(self.workspace.node(txr, id).map(|label| NodeSnapshot {
+      id,
+      label,
+      atoms: self.workspace.atom_id_label_value_by_src(txr, id),
+      edges: self.workspace.edge_id_label_dst_by_src(txr, id),
+    }))
+  }
+
+  /// Same as calling [`Store::snapshot`] on each of `ids`, but reads atoms
+  /// and edges in batches of [`IN_BATCH_SIZE`](crate::workspace::metadata::IN_BATCH_SIZE) nodes per query.
+  pub fn snapshot_many(&self, ids: &[u128]) -> Result<Vec<Option<NodeSnapshot>>, StoreError> {
+    let txr = self.txr.as_ref().ok_or(StoreError::Disconnected)?;
+    let mut res: Vec<_> = ids
+      .iter()
+      .map(|&id| {
+        let label = self.workspace.node(txr, id)?;
+        Some(NodeSnapshot { id, label, atoms: BTreeMap::new(), edges: BTreeMap::new() })
+      })
+      .collect();
+    let index: BTreeMap<u128, Vec<usize>> = res.iter().enumerate().fold(BTreeMap::new(), |mut acc, (i, snapshot)| {
+      if let Some(snapshot) = snapshot {
+        acc.entry(snapshot.id).or_default().push(i);
+      }
+      acc
+    });
+    let srcs: Vec<u128> = index.keys().copied().collect();
+    for (id, (src, label, value)) in self.workspace.atom_id_src_label_value_by_srcs(txr, &srcs) {
+      for &i in &index[&src] {
+        res[i].as_mut().unwrap().atoms.insert(id, (label, value.clone()));
+      }
+    }
+    for (id, (src, label, dst)) in self.workspace.edge_id_src_label_dst_by_srcs(txr, &srcs) {
+      for &i in &index[&src] {
+        res[i].as_mut().unwrap().edges.insert(id, (label, dst));
+      }
+    }
+    Ok(res)
+  }
+
+  /// Starts recording every barrier into an [`EventLog`] of at most
+  /// `capacity` entries, replacing any existing log.
+  pub fn enable_log(&mut self, capacity: usize) {
+    self.log = Some(EventLog::new(capacity));
+  }
+
+  pub fn log(&self) -> Option<&EventLog> {
+    self.log.as_ref()
+  }
+
+  pub fn log_mut(&mut self) -> Option<&mut EventLog> {
+    self.log.as_mut()
+  }
+
+  /// Same as [`Workspace::barrier`], but also records the applied
+  /// modifications if logging is enabled.
+  pub fn barrier(&mut self) -> Result<Vec<CEventData>, StoreError> {
+    let (txr, ws) = self.as_mut()?;
+    let res = ws.barrier(txr);
+    if let Some(log) = &mut self.log {
+      log.record(res.iter().map(Mutation::from_event).collect());
+    }
+    Ok(res)
+  }
+
+  /// Undoes all applied entries from `seq` onwards (newest first) by setting
+  /// items back to their previous values, then issues a barrier which is not
+  /// recorded. The undone entries can be replayed until the next recorded
+  /// barrier. Any pending modifications are applied by the same barrier.
+  ///
+  /// Undoing is an ordinary local modification with new clock values, so it
+  /// will be synchronised to peers like any other.
+  pub fn rewind(&mut self, seq: u64) -> Result<Vec<CEventData>, StoreError> {
+    self.seek(seq, seq)
+  }
+
+  /// Rewinds to `seq`, then re-applies all logged entries from `seq` onwards
+  /// (oldest first), including previously rewound ones, in a single barrier
+  /// which is not recorded.
+  pub fn replay(&mut self, seq: u64) -> Result<Vec<CEventData>, StoreError> {
+    let end = self.log.as_ref().map_or(seq, EventLog::end_seq);
+    self.seek(seq, end)
+  }
+
+  /// Undoes applied entries from `from`, then redoes entries up to `to`.
+  fn seek(&mut self, from: u64, to: u64) -> Result<Vec<CEventData>, StoreError> {
+    let log = self.log.as_mut().ok_or(StoreError::InvalidSequence(from))?;
+    if from < log.first_seq() || from > log.cursor() {
+      return Err(StoreError::InvalidSequence(from));
+    }
+    let txr = self.txr.as_mut().ok_or(StoreError::Disconnected)?;
+    for mutation in log.entries(from, log.cursor()).rev().flat_map(|entry| entry.iter().rev()) {
+      mutation.undo(txr, &mut self.workspace);
+    }
+    for mutation in log.entries(from, to).flatten() {
+      mutation.redo(txr, &mut self.workspace);
+    }
+    log.set_cursor(to);
+    Ok(self.workspace.barrier(txr))
+  }
+
   pub fn commit(&mut self) -> Result<(), StoreError> {
+    let conn = self.finish()?;
+    self.begin(conn)
+  }
+
+  /// Returns the number and durations of transactions committed so far, e.g.
+  /// to check whether long transactions keep other connections waiting.
+  pub fn transaction_stats(&self) -> TransactionStats {
+    TransactionStats { process_busy_retries: stats::process_busy_retries(), ..self.stats }
+  }
+
+  fn finish(&mut self) -> Result<Connection, StoreError> {
     let txr = self.txr.take().ok_or(StoreError::Disconnected)?;
     let conn: Connection = txr.try_into()?;
-    let txr: Transactor = conn.try_into()?;
-    self.txr = Some(txr);
+    self.stats.transactions += 1;
+    self.stats.total_duration += self.began.elapsed();
+    Ok(conn)
+  }
+
+  fn begin(&mut self, conn: Connection) -> Result<(), StoreError> {
+    self.txr = Some(Transactor::immediate(conn, &self.retry)?);
+    self.began = Instant::now();
     Ok(())
   }
 
+  /// Attaches the database file at `path` (e.g. one received from a peer),
+  /// joins all data of the same prefix in it through
+  /// [`Workspace::merge_attached`], then detaches it again. SQLite does not
+  /// allow attaching or detaching within a transaction, so this commits
+  /// twice. Joined items are applied by the next barrier.
+  pub fn merge_attached(&mut self, path: &Path) -> Result<(), StoreError> {
+    let path = path.to_str().ok_or(StoreError::InvalidUtf8)?;
+    self.between(|conn| conn.execute("ATTACH DATABASE ? AS peer", (path,)).map(|_| ()))?;
+    let res = self.as_mut().and_then(|(txr, ws)| ws.merge_attached(txr, "peer"));
+    let detached = self.between(|conn| conn.execute_batch("DETACH DATABASE peer"));
+    res?;
+    detached
+  }
+
+  /// Commits, runs `f` outside of any transaction, then begins the next one
+  /// whether or not `f` succeeded, so that the store stays usable. Errors of
+  /// `f` take precedence over errors in beginning.
+  fn between(&mut self, f: impl FnOnce(&Connection) -> rusqlite::Result<()>) -> Result<(), StoreError> {
+    let conn = self.finish()?;
+    let res = f(&conn);
+    let begun = self.begin(conn);
+    res?;
+    begun
+  }
+
+  /// Checks the integrity of the database file and counts edges and atoms
+  /// pointing to missing nodes (which should never happen unless the file
+  /// was modified elsewhere), see [`HealthReport`]. This only reads, so it
+  /// is safe to run at any time, but takes time linear in the database size.
+  pub fn health_check(&self) -> Result<HealthReport, StoreError> {
+    let txr = self.txr.as_ref().ok_or(StoreError::Disconnected)?;
+    Ok(health::health_check(txr, self.workspace.prefix())?)
+  }
+
+  /// Commits, then rebuilds the database file with `VACUUM` to return the
+  /// space of removed data to the file system. SQLite does not allow this
+  /// within a transaction, so it runs between two. This rewrites the whole
+  /// file, so it is best done while idle.
+  pub fn vacuum(&mut self) -> Result<(), StoreError> {
+    self.between(|conn| conn.execute_batch("VACUUM"))
+  }
+
+  /// Commits, then returns up to `pages` free pages (all if `None`) to the
+  /// file system. This is much cheaper than [`Store::vacuum`], but only has
+  /// an effect if `auto_vacuum` was set to `INCREMENTAL` before the database
+  /// was created.
+  pub fn incremental_vacuum(&mut self, pages: Option<u32>) -> Result<(), StoreError> {
+    self.between(|conn| conn.execute_batch(&format!("PRAGMA incremental_vacuum({})", pages.unwrap_or(0))))
+  }
+
   pub fn close(self) -> Result<(), StoreError> {
     let txr = self.txr.ok_or(StoreError::Disconnected)?;
     let conn: Connection = txr.try_into()?;
@@ -50,6 +267,325 @@ impl Store {
   }
 }
 
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn snapshot_simple() {
+    let mut store = Store::new(Connection::open_in_memory().unwrap(), Constraints::new()).unwrap();
+    let (txr, ws) = store.as_mut().unwrap();
+    ws.set_node(txr, 1, Some(10));
+    ws.set_node(txr, 2, Some(20));
+    ws.set_atom(txr, 3, Some((1, 30, vec![3].into())));
+    ws.set_edge(txr, 4, Some((1, 40, 2)));
+    ws.barrier(txr);
+    store.commit().unwrap();
+
+    let snapshot = store.snapshot(1).unwrap().unwrap();
+    let snapshot = std::thread::spawn(move || snapshot).join().unwrap();
+    assert_eq!(snapshot.label, 10);
+    assert_eq!(snapshot.atoms, BTreeMap::from([(3, (30, vec![3].into()))]));
+    assert_eq!(snapshot.edges, BTreeMap::from([(4, (40, 2))]));
+    assert!(store.snapshot(5).unwrap().is_none());
+  }
+
+  #[test]
+  fn snapshot_many_simple() {
+    let mut store = Store::new(Connection::open_in_memory().unwrap(), Constraints::new()).unwrap();
+    let (txr, ws) = store.as_mut().unwrap();
+    for i in 0..600 {
+      ws.set_node(txr, i, Some(10));
+      ws.set_atom(txr, (1 << 64) | i, Some((i, 30, vec![i as u8].into())));
+      ws.set_edge(txr, (2 << 64) | i, Some((i, 40, (i + 1) % 600)));
+    }
+    ws.barrier(txr);
+    let (txr, ws) = store.as_mut().unwrap();
+    ws.set_atom(txr, 3 << 64, Some((7, 31, vec![].into())));
+    ws.set_edge(txr, 2 << 64, None);
+
+    let ids: Vec<u128> = (0..600).chain([7, 1000]).collect();
+    let expected: Vec<_> = ids.iter().map(|&id| store.snapshot(id).unwrap()).collect();
+    assert_eq!(store.snapshot_many(&ids).unwrap(), expected);
+    assert_eq!(expected[7].as_ref().unwrap().atoms.len(), 2);
+    assert!(expected[0].as_ref().unwrap().edges.is_empty());
+    assert!(expected[601].is_none());
+  }
+
+  #[test]
+  fn log_simple() {
+    let mut store = Store::new(Connection::open_in_memory().unwrap(), Constraints::new()).unwrap();
+    store.enable_log(3);
+    let (txr, ws) = store.as_mut().unwrap();
+    ws.set_node(txr, 1, Some(10));
+    ws.set_atom(txr, 2, Some((1, 20, vec![1].into())));
+    store.barrier().unwrap();
+    let earlier = store.snapshot(1).unwrap();
+    let (txr, ws) = store.as_mut().unwrap();
+    ws.set_atom(txr, 2, Some((1, 20, vec![2].into())));
+    ws.set_node(txr, 3, Some(30));
+    ws.set_edge(txr, 4, Some((1, 40, 3)));
+    store.barrier().unwrap();
+    let (txr, ws) = store.as_mut().unwrap();
+    ws.set_node(txr, 3, None);
+    store.barrier().unwrap();
+    let latest = store.snapshot(1).unwrap();
+    assert_eq!(latest.as_ref().unwrap().edges.len(), 0);
+    assert_eq!(store.log().unwrap().cursor(), 3);
+
+    store.rewind(1).unwrap();
+    assert_eq!(store.snapshot(1).unwrap(), earlier);
+    let (txr, ws) = store.as_mut().unwrap();
+    assert_eq!(ws.node(txr, 3), None);
+    assert_eq!(ws.edge(txr, 4), None);
+    assert_eq!(store.log().unwrap().cursor(), 1);
+
+    store.replay(1).unwrap();
+    assert_eq!(store.snapshot(1).unwrap(), latest);
+    store.rewind(2).unwrap();
+    let (txr, ws) = store.as_mut().unwrap();
+    assert_eq!(ws.node(txr, 3), Some(30));
+    assert_eq!(ws.edge(txr, 4), Some((1, 40, 3)));
+
+    // Recording a new barrier discards rewound entries; old ones fall off.
+    ws.set_node(txr, 5, Some(50));
+    store.barrier().unwrap();
+    let (txr, ws) = store.as_mut().unwrap();
+    ws.set_node(txr, 6, Some(60));
+    store.barrier().unwrap();
+    let log = store.log_mut().unwrap();
+    assert_eq!((log.first_seq(), log.cursor(), log.end_seq()), (1, 4, 4));
+    log.compact(3);
+    assert_eq!(log.first_seq(), 3);
+    assert!(matches!(store.rewind(2), Err(StoreError::InvalidSequence(2))));
+    assert!(matches!(store.rewind(5), Err(StoreError::InvalidSequence(5))));
+  }
+
+  #[test]
+  fn merge_attached_simple() {
+    let path = std::env::temp_dir().join(format!("dust-peer-{}.sqlite3", rand::random::<u64>()));
+    let mut peer = Store::with_prefix(Connection::open(&path).unwrap(), "app", Constraints::new()).unwrap();
+    let (txr, ws) = peer.as_mut().unwrap();
+    ws.set_node(txr, 1, Some(10));
+    ws.set_node(txr, 2, Some(20));
+    ws.set_atom(txr, 3, Some((1, 30, vec![3].into())));
+    ws.set_edge(txr, 4, Some((1, 40, 2)));
+    ws.barrier(txr);
+    peer.close().unwrap();
+
+    let mut store = Store::with_prefix(Connection::open_in_memory().unwrap(), "app", Constraints::new()).unwrap();
+    let (txr, ws) = store.as_mut().unwrap();
+    ws.set_node(txr, 5, Some(50));
+    ws.barrier(txr);
+    store.merge_attached(&path).unwrap();
+    assert_eq!(store.barrier().unwrap().len(), 4);
+    let (txr, ws) = store.as_mut().unwrap();
+    assert_eq!(ws.node(txr, 1), Some(10));
+    assert_eq!(ws.atom(txr, 3), Some((1, 30, vec![3].into())));
+    assert_eq!(ws.edge(txr, 4), Some((1, 40, 2)));
+    assert_eq!(ws.node(txr, 5), Some(50));
+
+    // Merging again changes nothing, and the database is detached each time.
+    store.merge_attached(&path).unwrap();
+    assert!(store.barrier().unwrap().is_empty());
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[test]
+  fn merge_attached_missing_table() {
+    let path = std::env::temp_dir().join(format!("dust-peer-{}.sqlite3", rand::random::<u64>()));
+    let peer = Store::with_prefix(Connection::open(&path).unwrap(), "app", Constraints::new()).unwrap();
+    peer.close().unwrap();
+    // As if written before atom versions were stored.
+    Connection::open(&path).unwrap().execute_batch("DROP TABLE \"app.atoms.versions\"").unwrap();
+
+    // Version vectors are only needed for causal atoms.
+    let mut store = Store::with_prefix(Connection::open_in_memory().unwrap(), "app", Constraints::new()).unwrap();
+    store.merge_attached(&path).unwrap();
+    let mut constraints = Constraints::new();
+    constraints.add_causal_atom(20);
+    let mut store = Store::with_prefix(Connection::open_in_memory().unwrap(), "app", constraints).unwrap();
+    let err = store.merge_attached(&path).unwrap_err();
+    assert!(matches!(err, StoreError::MissingTable(name) if name == "app.atoms.versions"));
+    // The store stays usable, and the database was detached again.
+    assert!(matches!(store.merge_attached(&path), Err(StoreError::MissingTable(_))));
+    let (txr, ws) = store.as_mut().unwrap();
+    ws.set_node(txr, 1, Some(10));
+    assert_eq!(store.barrier().unwrap().len(), 1);
+    store.commit().unwrap();
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[test]
+  fn health_check_simple() {
+    let mut store = Store::new(Connection::open_in_memory().unwrap(), Constraints::new()).unwrap();
+    let (txr, ws) = store.as_mut().unwrap();
+    ws.set_node(txr, 1, Some(10));
+    ws.set_atom(txr, 2, Some((1, 20, vec![2].into())));
+    ws.set_edge(txr, 3, Some((1, 30, 1)));
+    ws.barrier(txr);
+    let report = store.health_check().unwrap();
+    assert!(report.is_healthy());
+    assert_eq!(report.rows[&(String::new(), "atoms".to_owned())], 1);
+
+    // Writes rows behind the back of the workspace, as if damaged elsewhere.
+    let (txr, _) = store.as_mut().unwrap();
+    let (id, missing, zero) = (4u128.to_be_bytes(), 5u128.to_be_bytes(), 0u64.to_be_bytes());
+    txr
+      .execute(
+        "INSERT INTO \".edges.data\" VALUES (?, ?, ?, ?, ?, ?)",
+        (id, zero, zero, 1u128.to_be_bytes(), zero, missing),
+      )
+      .unwrap();
+    txr
+      .execute("INSERT INTO \".atoms.data\" VALUES (?, ?, ?, ?, ?, ?)", (id, zero, zero, missing, zero, [0u8]))
+      .unwrap();
+    let report = store.health_check().unwrap();
+    assert!(!report.is_healthy());
+    assert!(report.integrity_errors.is_empty());
+    assert_eq!((report.dangling_edges, report.orphaned_atoms), (1, 1));
+    assert_eq!(report.rows[&(String::new(), "edges".to_owned())], 2);
+  }
+
+  #[test]
+  fn vacuum_simple() {
+    fn fill_and_clear(store: &mut Store) {
+      let (txr, ws) = store.as_mut().unwrap();
+      for id in 1..=500 {
+        ws.set_node(txr, id, Some(10));
+        ws.set_atom(txr, id << 16, Some((id, 20, vec![0xab; 1024].into())));
+      }
+      ws.barrier(txr);
+      store.commit().unwrap();
+      let (txr, ws) = store.as_mut().unwrap();
+      for id in 1..=500 {
+        ws.set_node(txr, id, None);
+      }
+      ws.barrier(txr);
+      store.commit().unwrap();
+    }
+
+    let path = std::env::temp_dir().join(format!("dust-vacuum-{}.sqlite3", rand::random::<u64>()));
+    let mut store = Store::new(Connection::open(&path).unwrap(), Constraints::new()).unwrap();
+    fill_and_clear(&mut store);
+    let before = std::fs::metadata(&path).unwrap().len();
+    store.vacuum().unwrap();
+    assert!(std::fs::metadata(&path).unwrap().len() < before);
+    let (txr, ws) = store.as_mut().unwrap();
+    assert_eq!(ws.node(txr, 1), None);
+    store.close().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let conn = Connection::open(&path).unwrap();
+    conn.pragma_update(None, "auto_vacuum", "INCREMENTAL").unwrap();
+    let mut store = Store::new(conn, Constraints::new()).unwrap();
+    fill_and_clear(&mut store);
+    let before = std::fs::metadata(&path).unwrap().len();
+    store.incremental_vacuum(None).unwrap();
+    assert!(std::fs::metadata(&path).unwrap().len() < before);
+    store.close().unwrap();
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[test]
+  fn vacuum_failed() {
+    let mut store = Store::new(Connection::open_in_memory().unwrap(), Constraints::new()).unwrap();
+    let (txr, ws) = store.as_mut().unwrap();
+    ws.set_node(txr, 1, Some(10));
+    ws.barrier(txr);
+    assert!(matches!(store.between(|conn| conn.execute_batch("VACUUM missing")), Err(StoreError::Sqlite(_))));
+
+    // The next transaction was begun regardless.
+    let (txr, ws) = store.as_mut().unwrap();
+    assert_eq!(ws.node(txr, 1), Some(10));
+    ws.set_node(txr, 2, Some(20));
+    assert_eq!(store.barrier().unwrap().len(), 1);
+    store.vacuum().unwrap();
+    store.close().unwrap();
+  }
+
+  #[test]
+  fn transaction_stats_simple() {
+    let path = std::env::temp_dir().join(format!("dust-stats-{}.sqlite3", rand::random::<u64>()));
+    let other = Connection::open(&path).unwrap();
+    other.pragma_update(None, "journal_mode", "WAL").unwrap();
+    other.execute_batch("BEGIN IMMEDIATE").unwrap();
+    let holder = std::thread::spawn(move || {
+      std::thread::sleep(std::time::Duration::from_millis(50));
+      other.execute_batch("COMMIT").unwrap();
+    });
+
+    // Opening waits for the write lock held by the other connection.
+    let before = stats::process_busy_retries();
+    let conn = Connection::open(&path).unwrap();
+    conn.busy_handler(Some(stats::busy_handler)).unwrap();
+    let mut store = Store::new(conn, Constraints::new()).unwrap();
+    holder.join().unwrap();
+    assert!(store.transaction_stats().process_busy_retries > before);
+    assert_eq!(store.transaction_stats().transactions, 0);
+    assert_eq!(store.transaction_stats().average_duration(), None);
+    store.commit().unwrap();
+    store.commit().unwrap();
+    assert_eq!(store.transaction_stats().transactions, 2);
+    assert!(store.transaction_stats().average_duration().is_some());
+    store.close().unwrap();
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[test]
+  fn retry_policy_simple() {
+    let path = std::env::temp_dir().join(format!("dust-retry-{}.sqlite3", rand::random::<u64>()));
+    let other = Connection::open(&path).unwrap();
+    other.execute_batch("BEGIN IMMEDIATE").unwrap();
+    let holder = std::thread::spawn(move || {
+      std::thread::sleep(std::time::Duration::from_millis(50));
+      other.execute_batch("COMMIT").unwrap();
+    });
+
+    // Without a busy timeout, only the retry policy makes these wait.
+    let open = || {
+      let conn = Connection::open(&path).unwrap();
+      conn.busy_timeout(std::time::Duration::ZERO).unwrap();
+      conn
+    };
+    let err = Transactor::immediate(open(), &RetryPolicy::NONE).err().unwrap();
+    assert_eq!(err.sqlite_error_code(), Some(rusqlite::ErrorCode::DatabaseBusy));
+    let policy = RetryPolicy::default();
+    assert!(policy.delays().sum::<std::time::Duration>() > std::time::Duration::from_millis(50));
+    let mut store = Store::new(open(), Constraints::new()).unwrap();
+    holder.join().unwrap();
+    store.commit().unwrap();
+    store.close().unwrap();
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[test]
+  fn with_prefix_simple() {
+    let path = std::env::temp_dir().join(format!("dust-prefix-{}.sqlite3", rand::random::<u64>()));
+    let open = |prefix| Store::with_prefix(Connection::open(&path).unwrap(), prefix, Constraints::new()).unwrap();
+
+    let mut v1 = open("app.v1");
+    let (txr, ws) = v1.as_mut().unwrap();
+    ws.set_node(txr, 1, Some(10));
+    ws.barrier(txr);
+    v1.close().unwrap();
+
+    let mut v2 = open("app.v2");
+    let (txr, ws) = v2.as_mut().unwrap();
+    assert_eq!(ws.node(txr, 1), None);
+    ws.set_node(txr, 2, Some(20));
+    ws.barrier(txr);
+    v2.close().unwrap();
+
+    let mut v1 = open("app.v1");
+    let (txr, ws) = v1.as_mut().unwrap();
+    assert_eq!(ws.node(txr, 1), Some(10));
+    assert_eq!(ws.node(txr, 2), None);
+    v1.close().unwrap();
+    let _ = std::fs::remove_file(&path);
+  }
+}
+
 /*
 /// Basic interface for model types.
 pub trait Model: Sized {
@@ -0,0 +1,83 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-only consistency checks over the saved data, e.g. for a diagnostics
+//! screen.
+
+use rusqlite::Result;
+use std::collections::BTreeMap;
+
+use crate::workspace::{list_collections, ATOMS_NAME, EDGES_NAME, NODES_NAME};
+use crate::Transactor;
+
+/// See [`super::Store::health_check`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthReport {
+  /// The problems reported by `PRAGMA integrity_check`, if any.
+  pub integrity_errors: Vec<String>,
+  /// The number of edges whose source or destination is not an existing node.
+  pub dangling_edges: u64,
+  /// The number of atoms whose source is not an existing node.
+  pub orphaned_atoms: u64,
+  /// The number of rows (including those of removed items) of each structure
+  /// in the database, by `(prefix, name)` as in [`list_collections`].
+  pub rows: BTreeMap<(String, String), u64>,
+}
+
+impl HealthReport {
+  /// Returns if none of the checks found a problem.
+  pub fn is_healthy(&self) -> bool {
+    self.integrity_errors.is_empty() && self.dangling_edges == 0 && self.orphaned_atoms == 0
+  }
+}
+
+/// Runs all checks, counting dangling edges and orphaned atoms of the
+/// structures with given prefix. Pending modifications are not seen, since
+/// these never violate the invariants after a barrier.
+pub(super) fn health_check(txr: &Transactor, prefix: &str) -> Result<HealthReport> {
+  let mut integrity_errors = Vec::new();
+  let mut stmt = txr.prepare("PRAGMA integrity_check")?;
+  let mut rows = stmt.query(())?;
+  while let Some(row) = rows.next()? {
+    let message: String = row.get(0)?;
+    if message != "ok" {
+      integrity_errors.push(message);
+    }
+  }
+
+  let nodes = format!("\"{prefix}.{NODES_NAME}.data\"");
+  let exists = |column: &str| format!("EXISTS (SELECT 1 FROM {nodes} WHERE id = {column} AND label IS NOT NULL)");
+  let dangling_edges = txr.query_row(
+    &format!(
+      "SELECT count(*) FROM \"{prefix}.{EDGES_NAME}.data\" WHERE src IS NOT NULL AND NOT ({} AND {})",
+      exists("src"),
+      exists("dst")
+    ),
+    (),
+    |row| row.get(0),
+  )?;
+  let orphaned_atoms = txr.query_row(
+    &format!("SELECT count(*) FROM \"{prefix}.{ATOMS_NAME}.data\" WHERE src IS NOT NULL AND NOT {}", exists("src")),
+    (),
+    |row| row.get(0),
+  )?;
+
+  let mut rows = BTreeMap::new();
+  for (prefix, name) in list_collections(txr) {
+    let count = txr.query_row(&format!("SELECT count(*) FROM \"{prefix}.{name}.data\""), (), |row| row.get(0))?;
+    rows.insert((prefix, name), count);
+  }
+
+  Ok(HealthReport { integrity_errors, dangling_edges, orphaned_atoms, rows })
+}
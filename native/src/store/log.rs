@@ -0,0 +1,149 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An in-memory log of applied modifications, used by [`super::Store`] to
+//! rewind and replay them (e.g. for undo/redo).
+
+use std::collections::VecDeque;
+
+use crate::ffi::structs::{CAtom, CEdge, CEventData, CNode, COption};
+use crate::workspace::Workspace;
+use crate::Transactor;
+
+/// A single applied modification, holding both the previous and the current
+/// value so that it can be undone or redone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mutation {
+  Node { id: u128, prev: Option<u64>, curr: Option<u64> },
+  Atom { id: u128, prev: Option<(u128, u64, Box<[u8]>)>, curr: Option<(u128, u64, Box<[u8]>)> },
+  Edge { id: u128, prev: Option<(u128, u64, u128)>, curr: Option<(u128, u64, u128)> },
+}
+
+impl Mutation {
+  /// Copies an event returned by [`Workspace::barrier`].
+  pub fn from_event(event: &CEventData) -> Self {
+    fn option<T, U>(value: &COption<T>, f: impl FnOnce(&T) -> U) -> Option<U> {
+      match value {
+        COption::None => None,
+        COption::Some(value) => Some(f(value)),
+      }
+    }
+    match event {
+      CEventData::Node { id, prev, curr } => {
+        let node = |node: &CNode| node.label;
+        Self::Node { id: (*id).into(), prev: option(prev, node), curr: option(curr, node) }
+      }
+      CEventData::Atom { id, prev, curr } => {
+        // SAFETY: events own their values until they are passed through FFI.
+        let atom = |atom: &CAtom| (atom.src.into(), atom.label, unsafe { atom.value.as_ref() }.into());
+        Self::Atom { id: (*id).into(), prev: option(prev, atom), curr: option(curr, atom) }
+      }
+      CEventData::Edge { id, prev, curr } => {
+        let edge = |edge: &CEdge| (edge.src.into(), edge.label, edge.dst.into());
+        Self::Edge { id: (*id).into(), prev: option(prev, edge), curr: option(curr, edge) }
+      }
+    }
+  }
+
+  /// Sets the item back to its previous value.
+  pub fn undo(&self, txr: &Transactor, ws: &mut Workspace) {
+    match self {
+      Self::Node { id, prev, .. } => ws.set_node(txr, *id, *prev),
+      Self::Atom { id, prev, .. } => ws.set_atom(txr, *id, prev.clone()),
+      Self::Edge { id, prev, .. } => ws.set_edge(txr, *id, *prev),
+    }
+  }
+
+  /// Sets the item to its current value again.
+  pub fn redo(&self, txr: &Transactor, ws: &mut Workspace) {
+    match self {
+      Self::Node { id, curr, .. } => ws.set_node(txr, *id, *curr),
+      Self::Atom { id, curr, .. } => ws.set_atom(txr, *id, curr.clone()),
+      Self::Edge { id, curr, .. } => ws.set_edge(txr, *id, *curr),
+    }
+  }
+}
+
+/// A bounded sequence of barriers, each numbered by a sequence number and
+/// holding all modifications it applied. Entries before [`EventLog::cursor`]
+/// are currently applied; entries after it have been rewound and can still be
+/// replayed, until a new barrier is recorded.
+#[derive(Debug, Clone)]
+pub struct EventLog {
+  capacity: usize,
+  first: u64,
+  cursor: u64,
+  entries: VecDeque<Vec<Mutation>>,
+}
+
+impl EventLog {
+  /// Creates an empty log which keeps at most `capacity` entries, dropping the
+  /// oldest ones when full.
+  pub fn new(capacity: usize) -> Self {
+    Self { capacity, first: 0, cursor: 0, entries: VecDeque::new() }
+  }
+
+  /// The sequence number of the oldest entry still kept.
+  pub fn first_seq(&self) -> u64 {
+    self.first
+  }
+
+  /// The sequence number one past the newest entry.
+  pub fn end_seq(&self) -> u64 {
+    self.first + self.entries.len() as u64
+  }
+
+  /// The sequence number one past the newest applied entry.
+  pub fn cursor(&self) -> u64 {
+    self.cursor
+  }
+
+  /// Returns the modifications applied by the entry numbered `seq`.
+  pub fn get(&self, seq: u64) -> Option<&[Mutation]> {
+    let index = seq.checked_sub(self.first)?;
+    self.entries.get(index as usize).map(Vec::as_slice)
+  }
+
+  /// Appends a new entry, discarding all rewound ones. Returns its sequence
+  /// number, or `None` if there was nothing to record.
+  pub fn record(&mut self, mutations: Vec<Mutation>) -> Option<u64> {
+    if mutations.is_empty() {
+      return None;
+    }
+    self.entries.truncate((self.cursor - self.first) as usize);
+    self.entries.push_back(mutations);
+    self.cursor += 1;
+    while self.entries.len() > self.capacity {
+      self.entries.pop_front();
+      self.first += 1;
+    }
+    Some(self.cursor - 1)
+  }
+
+  /// Drops all entries before `seq` (clamped to the cursor), so that they can
+  /// no longer be rewound.
+  pub fn compact(&mut self, seq: u64) {
+    let seq = seq.clamp(self.first, self.cursor);
+    self.entries.drain(..(seq - self.first) as usize);
+    self.first = seq;
+  }
+
+  pub(super) fn entries(&self, from: u64, to: u64) -> impl DoubleEndedIterator<Item = &Vec<Mutation>> {
+    self.entries.range((from - self.first) as usize..(to - self.first) as usize)
+  }
+
+  pub(super) fn set_cursor(&mut self, seq: u64) {
+    self.cursor = seq;
+  }
+}
@@ -0,0 +1,218 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rusqlite::Connection;
+use std::{
+  path::Path,
+  sync::{Condvar, Mutex, PoisonError},
+};
+
+use crate::store::{stats::busy_handler, Store};
+use crate::workspace::{Constraints, Workspace};
+use crate::{StoreError, Transactor};
+
+/// A [`Store`] together with a number of read connections to the same
+/// database file, which can be shared between threads.
+///
+/// All writes go through the single store, and are committed at the end of
+/// each [`StorePool::write`]. Reads run in parallel with each other and with
+/// the writer (the database is put in WAL mode), and each [`StorePool::read`]
+/// sees a consistent snapshot of the last commit. Workspaces of readers never
+/// have pending modifications, so they should only be used for queries.
+pub struct StorePool {
+  writer: Mutex<Store>,
+  readers: Mutex<Vec<(Connection, Workspace)>>,
+  available: Condvar,
+}
+
+impl StorePool {
+  /// Opens the database at `path` with one writer and `readers` (at least one)
+  /// read connections.
+  pub fn open(path: impl AsRef<Path>, readers: usize, constraints: Constraints) -> Result<Self, StoreError> {
+    assert!(readers > 0, "There must be at least one reader.");
+    let conn = Connection::open(&path)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.busy_handler(Some(busy_handler))?;
+    let mut store = Store::new(conn, constraints.clone())?;
+    store.commit()?; // Makes the schema visible to readers.
+    let mut pool = Vec::new();
+    for _ in 0..readers {
+      let mut txr = Transactor::deferred(Connection::open(&path)?)?;
+      let workspace = Workspace::new("", constraints.clone(), &mut txr);
+      pool.push((txr.try_into()?, workspace));
+    }
+    Ok(Self { writer: Mutex::new(store), readers: Mutex::new(pool), available: Condvar::new() })
+  }
+
+  /// Runs `f` on the writer and commits. Waits for any other write to finish.
+  pub fn write<T>(&self, f: impl FnOnce(&mut Transactor, &mut Workspace) -> T) -> Result<T, StoreError> {
+    let mut store = self.writer.lock().map_err(|_| StoreError::Disconnected)?;
+    let (txr, workspace) = store.as_mut()?;
+    let res = f(txr, workspace);
+    store.commit()?;
+    Ok(res)
+  }
+
+  /// Runs `f` on any free reader. Waits if all readers are in use.
+  pub fn read<T>(&self, f: impl FnOnce(&Transactor, &Workspace) -> T) -> Result<T, StoreError> {
+    self.read_with(Transactor::begin_deferred, f)
+  }
+
+  /// Same as [`StorePool::read`], but the snapshot is pinned before `f` runs
+  /// (instead of on its first query), so nothing committed after this call is
+  /// seen by any query within `f`. This keeps many queries consistent with
+  /// each other, e.g. when rendering a screen while the writer commits.
+  pub fn snapshot<T>(&self, f: impl FnOnce(&Transactor, &Workspace) -> T) -> Result<T, StoreError> {
+    self.read_with(Transactor::begin_snapshot, f)
+  }
+
+  fn read_with<T>(
+    &self,
+    begin: fn(&Transactor) -> rusqlite::Result<()>,
+    f: impl FnOnce(&Transactor, &Workspace) -> T,
+  ) -> Result<T, StoreError> {
+    let (conn, workspace) = {
+      let mut readers = self.readers.lock().map_err(|_| StoreError::Disconnected)?;
+      loop {
+        if let Some(reader) = readers.pop() {
+          break reader;
+        }
+        readers = self.available.wait(readers).map_err(|_| StoreError::Disconnected)?;
+      }
+    };
+    let reader = Reader { pool: self, inner: Some((Transactor::wrap(conn), workspace)) };
+    let (txr, workspace) = reader.inner.as_ref().unwrap();
+    begin(txr)?;
+    let res = f(txr, workspace);
+    txr.execute_batch("COMMIT")?;
+    Ok(res)
+  }
+}
+
+/// A reader taken from a [`StorePool`], which is put back when dropped, even
+/// if beginning, running or ending the read failed or panicked. Otherwise the
+/// pool would shrink, and eventually block all reads forever.
+struct Reader<'a> {
+  pool: &'a StorePool,
+  inner: Option<(Transactor, Workspace)>,
+}
+
+impl Drop for Reader<'_> {
+  fn drop(&mut self) {
+    let Some((txr, workspace)) = self.inner.take() else { return };
+    let conn = txr.into_inner();
+    if !conn.is_autocommit() {
+      let _ = conn.execute_batch("ROLLBACK");
+    }
+    self.pool.readers.lock().unwrap_or_else(PoisonError::into_inner).push((conn, workspace));
+    self.pool.available.notify_one();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::{AtomicBool, Ordering};
+
+  #[test]
+  fn pool_concurrent_readers() {
+    let path = std::env::temp_dir().join(format!("dust-pool-{}.sqlite3", rand::random::<u64>()));
+    let pool = StorePool::open(&path, 4, Constraints::new()).unwrap();
+    let done = AtomicBool::new(false);
+
+    std::thread::scope(|s| {
+      for _ in 0..8 {
+        s.spawn(|| {
+          let mut last = 0;
+          while !done.load(Ordering::Acquire) {
+            let count = pool
+              .read(|txr, ws| {
+                let nodes = ws.node_id_by_label(txr, 1);
+                // Each node and its atom are written in the same commit.
+                for &id in nodes.keys() {
+                  assert_eq!(ws.atom(txr, id ^ 2), Some((id, 2, id.to_be_bytes().into())));
+                }
+                nodes.len()
+              })
+              .unwrap();
+            assert!(count >= last);
+            last = count;
+          }
+        });
+      }
+      for id in 1..=200u128 {
+        pool
+          .write(|txr, ws| {
+            ws.set_node(txr, id << 8, Some(1));
+            ws.set_atom(txr, id << 8 ^ 2, Some((id << 8, 2, (id << 8).to_be_bytes().into())));
+            ws.barrier(txr);
+          })
+          .unwrap();
+      }
+      done.store(true, Ordering::Release);
+    });
+
+    assert_eq!(pool.read(|txr, ws| ws.node_id_by_label(txr, 1).len()).unwrap(), 200);
+    drop(pool);
+    for suffix in ["", "-wal", "-shm"] {
+      let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+  }
+
+  #[test]
+  fn pool_reader_returned() {
+    let path = std::env::temp_dir().join(format!("dust-pool-{}.sqlite3", rand::random::<u64>()));
+    let pool = StorePool::open(&path, 1, Constraints::new()).unwrap();
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+      pool.snapshot(|_, _| panic!("read failed")).unwrap();
+    }));
+    assert!(res.is_err());
+    // The only reader is back, and no longer within the failed transaction.
+    assert_eq!(pool.read(|txr, ws| ws.node(txr, 1)).unwrap(), None);
+    assert!(!pool.snapshot(|txr, _| txr.is_autocommit()).unwrap());
+    drop(pool);
+    for suffix in ["", "-wal", "-shm"] {
+      let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+  }
+
+  #[test]
+  fn pool_snapshot_simple() {
+    let path = std::env::temp_dir().join(format!("dust-pool-{}.sqlite3", rand::random::<u64>()));
+    let pool = StorePool::open(&path, 1, Constraints::new()).unwrap();
+    let write = |id| {
+      pool
+        .write(|txr, ws| {
+          ws.set_node(txr, id, Some(1));
+          ws.barrier(txr);
+        })
+        .unwrap()
+    };
+    write(1);
+
+    let (before, after) = pool
+      .snapshot(|txr, ws| {
+        // Commits before the first query, which `read` would see.
+        std::thread::scope(|s| s.spawn(|| write(2)).join().unwrap());
+        (ws.node(txr, 1), ws.node(txr, 2))
+      })
+      .unwrap();
+    assert_eq!((before, after), (Some(1), None));
+    assert_eq!(pool.read(|txr, ws| ws.node(txr, 2)).unwrap(), Some(1));
+    drop(pool);
+    for suffix in ["", "-wal", "-shm"] {
+      let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+  }
+}
@@ -0,0 +1,95 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Counters for diagnosing lock contention, e.g. between a sync thread and
+//! the UI thread writing to the same database, and the policy for retrying
+//! when the lock cannot be taken.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// How long [`busy_handler`] keeps retrying before giving up with
+/// `SQLITE_BUSY`.
+pub const BUSY_TIMEOUT: Duration = Duration::from_millis(1000);
+
+static BUSY_RETRIES: AtomicU64 = AtomicU64::new(0);
+
+/// A replacement for `PRAGMA busy_timeout` (install it through
+/// [`rusqlite::Connection::busy_handler`]) which retries every millisecond
+/// for up to [`BUSY_TIMEOUT`], counting each retry in
+/// [`process_busy_retries`].
+pub fn busy_handler(count: i32) -> bool {
+  if count as u128 >= BUSY_TIMEOUT.as_millis() {
+    return false;
+  }
+  BUSY_RETRIES.fetch_add(1, Ordering::Relaxed);
+  std::thread::sleep(Duration::from_millis(1));
+  true
+}
+
+/// Returns the number of retries by [`busy_handler`] so far. SQLite only
+/// accepts plain function pointers as handlers, so this is counted over all
+/// connections of the process, and only those which installed the handler:
+/// connections waiting through `PRAGMA busy_timeout` are not counted.
+pub fn process_busy_retries() -> u64 {
+  BUSY_RETRIES.load(Ordering::Relaxed)
+}
+
+/// How often and how long [`crate::Transactor::immediate`] retries taking
+/// the write lock when the database is busy (i.e. SQLite gave up waiting,
+/// e.g. after [`BUSY_TIMEOUT`] with [`busy_handler`], or at once without a
+/// handler). The delay starts at `initial_delay` and doubles after each retry,
+/// up to `max_delay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+  pub max_retries: u32,
+  pub initial_delay: Duration,
+  pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+  /// Fails on the first busy error.
+  pub const NONE: Self = Self { max_retries: 0, initial_delay: Duration::ZERO, max_delay: Duration::ZERO };
+
+  /// Returns the delay before each retry.
+  pub fn delays(&self) -> impl Iterator<Item = Duration> + '_ {
+    (0..self.max_retries).map(|i| self.initial_delay.saturating_mul(1 << i.min(31)).min(self.max_delay))
+  }
+}
+
+impl Default for RetryPolicy {
+  /// Retries for about a second in total.
+  fn default() -> Self {
+    Self { max_retries: 12, initial_delay: Duration::from_millis(1), max_delay: Duration::from_millis(200) }
+  }
+}
+
+/// See [`super::Store::transaction_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransactionStats {
+  /// The number of transactions committed.
+  pub transactions: u64,
+  /// The total time from beginning to committing all these transactions.
+  pub total_duration: Duration,
+  /// The value of [`process_busy_retries`]. This counts the retries of all
+  /// connections of the process, not only those of this store.
+  pub process_busy_retries: u64,
+}
+
+impl TransactionStats {
+  pub fn average_duration(&self) -> Option<Duration> {
+    let transactions = u32::try_from(self.transactions).ok().filter(|&n| n > 0)?;
+    Some(self.total_duration / transactions)
+  }
+}
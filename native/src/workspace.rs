@@ -15,18 +15,38 @@
 #![allow(clippy::type_complexity)]
 
 pub mod atom_set;
+pub mod attach;
+pub mod delta;
 pub mod edge_set;
 pub mod metadata;
+pub mod ndjson;
 pub mod node_set;
+pub mod numeric_index;
+pub mod raw;
+pub mod version_set;
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  ops::{ControlFlow, RangeBounds},
+};
 
-use self::{atom_set::AtomSet, edge_set::EdgeSet, metadata::WorkspaceMetadata, node_set::NodeSet};
+use self::{
+  atom_set::AtomSet,
+  edge_set::{EdgeSet, EdgeSetTransactor},
+  metadata::WorkspaceMetadata,
+  node_set::{NodeSet, NodeSetTransactor},
+  numeric_index::{NumericIndex, NumericKind},
+  version_set::{dominates, pointwise_max, VersionSet},
+};
 use crate::{deserialize, ffi::structs::CEventData, serialize, Transactor};
 
 pub const NODES_NAME: &str = "nodes";
 pub const ATOMS_NAME: &str = "atoms";
 pub const EDGES_NAME: &str = "edges";
+pub const ATOMS_VERSIONS_NAME: &str = "atoms.versions";
+
+/// Number of items read at once by [`Workspace::merge_from`].
+pub const MERGE_BATCH_SIZE: u64 = 256;
 
 #[derive(Debug, Clone, Default)]
 pub struct Constraints {
@@ -34,6 +54,9 @@ pub struct Constraints {
   sticky_atoms: BTreeSet<u64>,
   sticky_edges: BTreeSet<u64>,
   acyclic_edges: BTreeSet<u64>,
+  causal_atoms: BTreeSet<u64>,
+  atom_mergers: BTreeMap<u64, fn(&[u8], &[u8]) -> Box<[u8]>>,
+  numeric_atoms: BTreeMap<u64, NumericKind>,
 }
 
 impl Constraints {
@@ -52,6 +75,64 @@ impl Constraints {
   pub fn add_acyclic_edge(&mut self, label: u64) {
     self.acyclic_edges.insert(label);
   }
+  /// Atoms with the given label will carry version vectors, allowing
+  /// concurrent modifications to be detected in [`Workspace::sync_join`].
+  pub fn add_causal_atom(&mut self, label: u64) {
+    self.causal_atoms.insert(label);
+  }
+  /// Same as [`Constraints::add_causal_atom`], but concurrent modifications
+  /// are resolved by `merge(local, remote)` instead of last-writer-wins. The
+  /// merged value is written as a new local modification.
+  ///
+  /// For replicas to converge, `merge` must be commutative, associative and
+  /// idempotent (e.g. union of sets, or pointwise maximum of per-replica
+  /// counters).
+  pub fn add_causal_atom_merger(&mut self, label: u64, merge: fn(&[u8], &[u8]) -> Box<[u8]>) {
+    self.causal_atoms.insert(label);
+    self.atom_mergers.insert(label, merge);
+  }
+  /// Values of atoms with the given label will also be stored decoded in a
+  /// typed column, to be queried by [`Workspace::atom_id_src_by_label_range`].
+  pub fn add_numeric_atom(&mut self, label: u64, kind: NumericKind) {
+    self.numeric_atoms.insert(label, kind);
+  }
+}
+
+/// A remote modification of an atom which was concurrent with its local value.
+/// The conflict is resolved by the usual last-writer-wins rule (which keeps
+/// replicas convergent) or a merge function added through
+/// [`Constraints::add_causal_atom_merger`]; it is only reported here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtomConflict {
+  pub id: u128,
+  pub local: Option<(u128, u64, Box<[u8]>)>,
+  pub remote: Option<(u128, u64, Box<[u8]>)>,
+}
+
+/// A local atom value which was replaced by a newer remote one during
+/// [`Workspace::sync_join_overwrites`], so that apps can show what the local
+/// value was before.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtomOverwrite {
+  pub id: u128,
+  pub local_before: Option<(u128, u64, Box<[u8]>)>,
+  pub remote_after: Option<(u128, u64, Box<[u8]>)>,
+}
+
+/// Returns the `(prefix, name)` of every structure stored in the database,
+/// such as `("", "nodes")` for the nodes of the default workspace.
+pub fn list_collections(txr: &Transactor) -> Vec<(String, String)> {
+  txr
+    .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name LIKE '%.data' ORDER BY name")
+    .unwrap()
+    .query_map((), |row| row.get::<_, String>(0))
+    .unwrap()
+    .filter_map(|name| {
+      let name = name.unwrap();
+      let (prefix, name) = name.strip_suffix(".data")?.rsplit_once('.')?;
+      Some((prefix.to_owned(), name.to_owned()))
+    })
+    .collect()
 }
 
 #[derive(Debug)]
@@ -61,6 +142,10 @@ pub struct Workspace {
   nodes: NodeSet,
   atoms: AtomSet,
   edges: EdgeSet,
+  atoms_versions: VersionSet,
+  atoms_numeric: NumericIndex,
+  conflicts: Vec<AtomConflict>,
+  conflict_counts: BTreeMap<u64, u64>,
 }
 
 impl Workspace {
@@ -69,21 +154,98 @@ impl Workspace {
     let nodes = NodeSet::new(prefix, NODES_NAME, txr);
     let atoms = AtomSet::new(prefix, ATOMS_NAME, txr);
     let edges = EdgeSet::new(prefix, EDGES_NAME, txr);
-    Self { metadata, constraints, nodes, atoms, edges }
+    let atoms_versions = VersionSet::new(prefix, ATOMS_NAME, txr);
+    let atoms_numeric = NumericIndex::new(prefix, ATOMS_NAME, txr);
+    // Index existing atoms of newly added numeric labels.
+    for (&label, &kind) in &constraints.numeric_atoms {
+      if atoms_numeric.is_empty(txr, label) {
+        for (id, (src, value)) in atoms.id_src_value_by_label(txr, label) {
+          atoms_numeric.set_or_skip(txr, id, kind.decode(&value).map(|value| (src, label, value)));
+        }
+      }
+    }
+    Self {
+      metadata,
+      constraints,
+      nodes,
+      atoms,
+      edges,
+      atoms_versions,
+      atoms_numeric,
+      conflicts: Vec::new(),
+      conflict_counts: BTreeMap::new(),
+    }
   }
 
+  pub fn prefix(&self) -> &'static str {
+    self.nodes.prefix()
+  }
   pub fn node(&self, txr: &Transactor, id: u128) -> Option<u64> {
     self.nodes.get(txr, id).and_then(|(_, _, label)| label)
   }
   pub fn node_id_by_label(&self, txr: &Transactor, label: u64) -> BTreeMap<u128, ()> {
     self.nodes.id_by_label(txr, label)
   }
+  /// Same as [`Workspace::node_id_by_label`], but calls `f` on each ID as the
+  /// rows are read instead of collecting them, stopping once `f` breaks.
+  pub fn node_id_by_label_for_each(
+    &self,
+    txr: &Transactor,
+    label: u64,
+    f: impl FnMut(u128) -> ControlFlow<()>,
+  ) -> ControlFlow<()> {
+    self.nodes.id_by_label_for_each(txr, label, f)
+  }
+  /// Returns the number of present nodes with each label.
+  pub fn node_count_by_label(&self, txr: &Transactor) -> BTreeMap<u64, u64> {
+    self.nodes.count_by_label(txr)
+  }
+  /// Same as [`Workspace::node_id_by_label`], but sorted by the value of the
+  /// atom with `atom_label` from each node (the smallest, if there are
+  /// several), then by ID. Nodes without that atom come first, or last if
+  /// `desc`. Values are compared as bytes, so this only gives a meaningful
+  /// order for encodings which preserve it (e.g. raw UTF-8 or big-endian
+  /// unsigned integers, but not length-prefixed strings). Runs as a single
+  /// query when there are no pending modifications.
+  pub fn node_id_by_label_ordered_by(&self, txr: &Transactor, label: u64, atom_label: u64, desc: bool) -> Vec<u128> {
+    if self.nodes.is_saved() && self.atoms.is_saved() {
+      return txr.id_by_label_ordered_by_atom(self.nodes.prefix(), NODES_NAME, ATOMS_NAME, label, atom_label, desc);
+    }
+    let mut res: Vec<_> = self
+      .node_id_by_label(txr, label)
+      .into_keys()
+      .map(|id| (self.atom_id_value_by_src_label(txr, id, atom_label).into_values().min(), id))
+      .collect();
+    if desc {
+      res.sort_by(|(lhs, lid), (rhs, rid)| rhs.cmp(lhs).then(lid.cmp(rid)));
+    } else {
+      res.sort();
+    }
+    res.into_iter().map(|(_, id)| id).collect()
+  }
+  /// Keeps results of [`Workspace::node_id_by_label`] in memory until a node
+  /// of that label is modified (locally or by a join).
+  pub fn enable_node_label_cache(&mut self) {
+    self.nodes.enable_label_cache();
+  }
   pub fn atom(&self, txr: &Transactor, id: u128) -> Option<(u128, u64, Box<[u8]>)> {
     self.atoms.get(txr, id).and_then(|(_, _, slv)| slv)
   }
+  /// Returns the clock of the last modification of the atom (even if it was a
+  /// removal), or `None` if it has never been written.
+  pub fn atom_clock(&self, txr: &Transactor, id: u128) -> Option<u64> {
+    self.atoms.get(txr, id).map(|(_, clock, _)| clock)
+  }
   pub fn atom_id_label_value_by_src(&self, txr: &Transactor, src: u128) -> BTreeMap<u128, (u64, Box<[u8]>)> {
     self.atoms.id_label_value_by_src(txr, src)
   }
+  pub fn atom_id_src_label_value_by_srcs(
+    &self,
+    txr: &Transactor,
+    srcs: &[u128],
+  ) -> BTreeMap<u128, (u128, u64, Box<[u8]>)> {
+    self.atoms.id_src_label_value_by_srcs(txr, srcs)
+  }
   pub fn atom_id_value_by_src_label(&self, txr: &Transactor, src: u128, label: u64) -> BTreeMap<u128, Box<[u8]>> {
     self.atoms.id_value_by_src_label(txr, src, label)
   }
@@ -93,12 +255,41 @@ impl Workspace {
   pub fn atom_id_src_by_label_value(&self, txr: &Transactor, label: u64, value: &[u8]) -> BTreeMap<u128, u128> {
     self.atoms.id_src_by_label_value(txr, label, value)
   }
+  /// Returns the atoms with given label whose decoded values lie within
+  /// `range`, as of the last barrier. The label must have been added through
+  /// [`Constraints::add_numeric_atom`].
+  pub fn atom_id_src_by_label_range(
+    &self,
+    txr: &Transactor,
+    label: u64,
+    range: impl RangeBounds<f64>,
+  ) -> BTreeMap<u128, u128> {
+    self.atoms_numeric.id_src_by_label_range(txr, label, range)
+  }
   pub fn edge(&self, txr: &Transactor, id: u128) -> Option<(u128, u64, u128)> {
     self.edges.get(txr, id).and_then(|(_, _, sld)| sld)
   }
+  /// Same as calling `edge` on each of `ids`, but in grouped queries. Removed
+  /// and never-written edges are left out of the result.
+  pub fn edges(&self, txr: &Transactor, ids: &[u128]) -> BTreeMap<u128, (u128, u64, u128)> {
+    self.edges.get_many(txr, ids).into_iter().filter_map(|(id, (_, _, sld))| Some((id, sld?))).collect()
+  }
+  /// Returns if the edge has ever been written, even if it has since been
+  /// removed. This distinguishes removed edges from never-existing ones.
+  pub fn edge_written(&self, txr: &Transactor, id: u128) -> bool {
+    self.edges.get(txr, id).is_some()
+  }
+  /// Returns the clock of the last modification of the edge (even if it was a
+  /// removal), or `None` if it has never been written.
+  pub fn edge_clock(&self, txr: &Transactor, id: u128) -> Option<u64> {
+    self.edges.get(txr, id).map(|(_, clock, _)| clock)
+  }
   pub fn edge_id_label_dst_by_src(&self, txr: &Transactor, src: u128) -> BTreeMap<u128, (u64, u128)> {
     self.edges.id_label_dst_by_src(txr, src)
   }
+  pub fn edge_id_src_label_dst_by_srcs(&self, txr: &Transactor, srcs: &[u128]) -> BTreeMap<u128, (u128, u64, u128)> {
+    self.edges.id_src_label_dst_by_srcs(txr, srcs)
+  }
   pub fn edge_id_dst_by_src_label(&self, txr: &Transactor, src: u128, label: u64) -> BTreeMap<u128, u128> {
     self.edges.id_dst_by_src_label(txr, src, label)
   }
@@ -109,6 +300,33 @@ impl Workspace {
     self.edges.id_src_by_dst_label(txr, dst, label)
   }
 
+  /// Returns edges from `src` with `label` whose destination has an atom with
+  /// `atom_label` and `value`. Runs as a single join query when there are no
+  /// pending modifications.
+  pub fn edge_id_dst_by_src_label_atom(
+    &self,
+    txr: &Transactor,
+    src: u128,
+    label: u64,
+    atom_label: u64,
+    value: &[u8],
+  ) -> BTreeMap<u128, u128> {
+    if self.atoms.is_saved() && self.edges.is_saved() {
+      return txr.id_dst_by_src_label_atom(self.edges.prefix(), EDGES_NAME, ATOMS_NAME, src, label, atom_label, value);
+    }
+    let mut res = self.edge_id_dst_by_src_label(txr, src, label);
+    res.retain(|_, dst| self.atom_id_value_by_src_label(txr, *dst, atom_label).values().any(|v| **v == *value));
+    res
+  }
+
+  /// Returns the label of the node together with all its outgoing edges and
+  /// their clocks, for diagnosing models which fail to load.
+  pub fn dump_node(&self, txr: &Transactor, id: u128) -> (Option<u64>, Vec<(u128, (u128, u64, u128), u64)>) {
+    let edges = self.edge_id_label_dst_by_src(txr, id).into_iter();
+    let edges = edges.filter_map(|(edge, (label, dst))| Some((edge, (id, label, dst), self.edge_clock(txr, edge)?)));
+    (self.node(txr, id), edges.collect())
+  }
+
   pub fn set_node(&mut self, txr: &Transactor, id: u128, label: Option<u64>) {
     let this = self.metadata.this();
     let next = self.nodes.next();
@@ -118,6 +336,15 @@ impl Workspace {
   pub fn set_atom(&mut self, txr: &Transactor, id: u128, slv: Option<(u128, u64, Box<[u8]>)>) {
     let this = self.metadata.this();
     let next = self.atoms.next();
+    let label = match &slv {
+      Some((_, label, _)) => Some(*label),
+      None => self.atom(txr, id).map(|(_, label, _)| label),
+    };
+    if label.is_some_and(|label| self.constraints.causal_atoms.contains(&label)) {
+      let mut version = self.atoms_versions.get(txr, id);
+      version.insert(this, next);
+      self.atoms_versions.set(id, version);
+    }
     assert!(self.atoms.set(txr, id, this, next, slv));
   }
 
@@ -127,16 +354,80 @@ impl Workspace {
     assert!(self.edges.set(txr, id, this, next, sld));
   }
 
+  /// Same as [`Workspace::set_node`], but only if the current value (including
+  /// pending modifications) equals `expected`. Returns if the value was set.
+  pub fn set_node_if(&mut self, txr: &Transactor, id: u128, expected: Option<u64>, label: Option<u64>) -> bool {
+    let matches = self.node(txr, id) == expected;
+    if matches {
+      self.set_node(txr, id, label);
+    }
+    matches
+  }
+
+  /// Same as [`Workspace::set_atom`], but only if the current value (including
+  /// pending modifications) equals `expected`. Returns if the value was set.
+  pub fn set_atom_if(
+    &mut self,
+    txr: &Transactor,
+    id: u128,
+    expected: Option<(u128, u64, &[u8])>,
+    slv: Option<(u128, u64, Box<[u8]>)>,
+  ) -> bool {
+    let matches = self.atom(txr, id).as_ref().map(|(src, label, value)| (*src, *label, &value[..])) == expected;
+    if matches {
+      self.set_atom(txr, id, slv);
+    }
+    matches
+  }
+
+  /// Same as [`Workspace::set_edge`], but only if the current value (including
+  /// pending modifications) equals `expected`. Returns if the value was set.
+  pub fn set_edge_if(
+    &mut self,
+    txr: &Transactor,
+    id: u128,
+    expected: Option<(u128, u64, u128)>,
+    sld: Option<(u128, u64, u128)>,
+  ) -> bool {
+    let matches = self.edge(txr, id) == expected;
+    if matches {
+      self.set_edge(txr, id, sld);
+    }
+    matches
+  }
+
+  /// For each of the given single-valued `labels`, removes all but the newest
+  /// (by `(clock, bucket)`) edge with that label starting from node `id`, so
+  /// that reading the field is deterministic again. Returns the number of
+  /// removed edges. Requires a [`Workspace::barrier`] call to come into effect.
+  pub fn repair_node(&mut self, txr: &Transactor, id: u128, labels: &[u64]) -> usize {
+    let mut removed = 0;
+    for &label in labels {
+      let mut edges: Vec<_> = self
+        .edge_id_dst_by_src_label(txr, id, label)
+        .into_keys()
+        .filter_map(|edge| self.edges.get(txr, edge).map(|(bucket, clock, _)| ((clock, bucket), edge)))
+        .collect();
+      edges.sort();
+      edges.pop();
+      for (_, edge) in edges {
+        self.set_edge(txr, edge, None);
+        removed += 1;
+      }
+    }
+    removed
+  }
+
   /// Issues write-read barrier: goes through all recent modifications,
   /// performing any additional action required to maintain invariants:
   ///
   /// 1. `atom_implies_node`: all atoms must start from a node.
   /// 2. `edge_implies_node`: all edges must start from and ends at nodes.
   /// 3. `sticky_or_none`: for each node, if it has "sticky" atoms or edges
-  ///     attached to it at the previous barrier, those must be preserved,
-  ///     otherwise the node must be removed.
+  ///    attached to it at the previous barrier, those must be preserved,
+  ///    otherwise the node must be removed.
   /// 4. `acyclic_or_none`: edges marked as "acyclic" cannot form cycles,
-  ///     otherwise some edges must be removed to break the cycle.
+  ///    otherwise some edges must be removed to break the cycle.
   pub fn barrier(&mut self, txr: &mut Transactor) -> Vec<CEventData> {
     // Assuming all conditions were true before any of the modifications,
     // we only need to focus on changes which cause violations.
@@ -231,14 +522,210 @@ impl Workspace {
       res.push(CEventData::Edge { id: id.into(), prev: prev.map(Into::into).into(), curr: curr.map(Into::into).into() })
     }
 
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+      prefix = self.nodes.prefix(),
+      nodes = res.iter().filter(|e| matches!(e, CEventData::Node { .. })).count(),
+      atoms = res.iter().filter(|e| matches!(e, CEventData::Atom { .. })).count(),
+      edges = res.iter().filter(|e| matches!(e, CEventData::Edge { .. })).count(),
+      "apply"
+    );
+
+    // Update the numeric index.
+    for (id, prev, curr) in self.atoms.mods() {
+      let numeric = |slv: &Option<(u128, u64, Box<[u8]>)>| {
+        slv.as_ref().and_then(|(_, label, _)| self.constraints.numeric_atoms.get(label)).is_some()
+      };
+      if numeric(&prev) || numeric(&curr) {
+        let curr = curr.and_then(|(src, label, value)| {
+          let kind = self.constraints.numeric_atoms.get(&label)?;
+          kind.decode(&value).map(|value| (src, label, value))
+        });
+        self.atoms_numeric.set_or_skip(txr, id, curr);
+      }
+    }
+
     // Apply and save all modifications.
     self.nodes.save(txr);
     self.atoms.save(txr);
     self.edges.save(txr);
+    self.atoms_versions.save(txr);
 
     res
   }
 
+  /// Removes all data in this workspace, discarding pending modifications.
+  ///
+  /// This is a local-only operation: no actions are generated, so peers will
+  /// not learn about it, and cleared data is not considered deleted. Since the
+  /// clock values are reset as well, the next synchronisation with any peer
+  /// will be a full resync, bringing back everything that peer still has.
+  pub fn clear(&mut self, txr: &mut Transactor) {
+    self.nodes.clear(txr);
+    self.atoms.clear(txr);
+    self.edges.clear(txr);
+    self.atoms_versions.clear(txr);
+    self.atoms_numeric.clear(txr);
+    self.conflicts.clear();
+    self.conflict_counts.clear();
+  }
+
+  /// Ensures that each given label has at most one atom and one edge starting
+  /// from `src`, stored at the canonical ID `src ^ label` used by single-valued
+  /// fields. If the canonical item is absent, the most recent extra item is
+  /// moved there; all other extras are removed. Returns the number of removed
+  /// items. Must be followed by a [`Workspace::barrier`].
+  pub fn compact(&mut self, txr: &Transactor, src: u128, labels: &[u64]) -> usize {
+    let mut count = 0;
+    for &label in labels {
+      let canonical = src ^ u128::from(label);
+      let extras = self.atom_id_value_by_src_label(txr, src, label);
+      let latest = extras
+        .keys()
+        .filter(|&&id| id != canonical)
+        .filter_map(|&id| self.atoms.get(txr, id).map(|(bucket, clock, _)| ((clock, bucket), id)))
+        .max()
+        .map(|(_, id)| id);
+      if !extras.contains_key(&canonical) {
+        if let Some(id) = latest {
+          self.set_atom(txr, canonical, Some((src, label, extras[&id].clone())));
+        }
+      }
+      for &id in extras.keys().filter(|&&id| id != canonical) {
+        self.set_atom(txr, id, None);
+        count += 1;
+      }
+      let extras = self.edge_id_dst_by_src_label(txr, src, label);
+      let latest = extras
+        .keys()
+        .filter(|&&id| id != canonical)
+        .filter_map(|&id| self.edges.get(txr, id).map(|(bucket, clock, _)| ((clock, bucket), id)))
+        .max()
+        .map(|(_, id)| id);
+      if !extras.contains_key(&canonical) {
+        if let Some(id) = latest {
+          self.set_edge(txr, canonical, Some((src, label, extras[&id])));
+        }
+      }
+      for &id in extras.keys().filter(|&&id| id != canonical) {
+        self.set_edge(txr, id, None);
+        count += 1;
+      }
+    }
+    count
+  }
+
+  /// Rewrites the values of all atoms with `label` through `f`, e.g. when the
+  /// type of a field changes. Values for which `f` returns `None` (such as
+  /// ones already in the new encoding, so that running this again is safe)
+  /// are left unchanged. Rewrites are ordinary modifications with new clocks,
+  /// so they are synchronised to peers. Returns the number of rewritten
+  /// atoms. Must be followed by a [`Workspace::barrier`].
+  pub fn migrate_atoms(&mut self, txr: &Transactor, label: u64, f: impl Fn(&[u8]) -> Option<Box<[u8]>>) -> usize {
+    let mut count = 0;
+    for (id, (src, value)) in self.atom_id_src_value_by_label(txr, label) {
+      if let Some(value) = f(&value) {
+        self.set_atom(txr, id, Some((src, label, value)));
+        count += 1;
+      }
+    }
+    count
+  }
+
+  /// Rewrites all nodes with `label`, together with their atoms and outgoing
+  /// edges, with unchanged values but new clocks, so that they win over
+  /// concurrent values and are all sent by the next [`Workspace::sync_actions`]
+  /// (e.g. to force a full push after repairing data). Issues a
+  /// [`Workspace::barrier`] after every [`MERGE_BATCH_SIZE`] nodes. Returns the
+  /// number of touched nodes, together with the events of all these barriers.
+  pub fn touch_by_label(&mut self, txr: &mut Transactor, label: u64) -> (usize, Vec<CEventData>) {
+    let ids: Vec<u128> = self.node_id_by_label(txr, label).into_keys().collect();
+    let mut events = Vec::new();
+    for chunk in ids.chunks(MERGE_BATCH_SIZE as usize) {
+      let atoms = self.atom_id_src_label_value_by_srcs(txr, chunk);
+      let edges = self.edge_id_src_label_dst_by_srcs(txr, chunk);
+      for &id in chunk {
+        self.set_node(txr, id, Some(label));
+      }
+      for (id, (src, label, value)) in atoms {
+        self.set_atom(txr, id, Some((src, label, value)));
+      }
+      for (id, sld) in edges {
+        self.set_edge(txr, id, Some(sld));
+      }
+      events.extend(self.barrier(txr));
+    }
+    (ids.len(), events)
+  }
+
+  /// Exchanges the destinations of the edges `a` and `b`. Both are modified
+  /// before the same barrier, so observers and peers never see both pointing at
+  /// the same node. Returns `false` (modifying nothing) if either edge does not
+  /// exist. Must be followed by a [`Workspace::barrier`].
+  pub fn swap_edge_dst(&mut self, txr: &Transactor, a: u128, b: u128) -> bool {
+    let (Some((src_a, label_a, dst_a)), Some((src_b, label_b, dst_b))) = (self.edge(txr, a), self.edge(txr, b)) else {
+      return false;
+    };
+    self.set_edge(txr, a, Some((src_a, label_a, dst_b)));
+    self.set_edge(txr, b, Some((src_b, label_b, dst_a)));
+    true
+  }
+
+  /// Moves the node `old` to the ID `new`, e.g. when merging records created
+  /// separately. Its atoms and outgoing edges are moved to `id ^ old ^ new`, so
+  /// that IDs derived from the node ID (as used by single-valued fields) stay
+  /// derived, and incoming edges are redirected to `new`. All of these are
+  /// ordinary modifications, so they are synchronised to peers. Returns if
+  /// `old` existed. Must be followed by a [`Workspace::barrier`].
+  pub fn rekey_node(&mut self, txr: &Transactor, old: u128, new: u128) -> bool {
+    let Some(label) = self.node(txr, old) else { return false };
+    let rekey = |id: u128| id ^ old ^ new;
+    let redirect = |id: u128| if id == old { new } else { id };
+    self.set_node(txr, new, Some(label));
+    for (id, (label, value)) in self.atom_id_label_value_by_src(txr, old) {
+      self.set_atom(txr, id, None);
+      self.set_atom(txr, rekey(id), Some((new, label, value)));
+    }
+    for (id, (label, dst)) in self.edge_id_label_dst_by_src(txr, old) {
+      self.set_edge(txr, id, None);
+      self.set_edge(txr, rekey(id), Some((new, label, redirect(dst))));
+    }
+    for (id, (src, label)) in self.edge_id_src_label_by_dst(txr, old) {
+      if src != old {
+        self.set_edge(txr, id, Some((src, label, new)));
+      }
+    }
+    self.set_node(txr, old, None);
+    true
+  }
+
+  /// Returns and removes all conflicts detected by [`Workspace::sync_join`]
+  /// since the last call. Only atoms with labels added through
+  /// [`Constraints::add_causal_atom`] are checked.
+  pub fn take_conflicts(&mut self) -> Vec<AtomConflict> {
+    std::mem::take(&mut self.conflicts)
+  }
+
+  /// Returns the number of conflicts detected by [`Workspace::sync_join`] for
+  /// each atom label since this workspace was opened, including ones already
+  /// removed by [`Workspace::take_conflicts`].
+  pub fn conflict_metrics(&self) -> BTreeMap<u64, u64> {
+    self.conflict_counts.clone()
+  }
+
+  /// Prepares and caches all standard statements, so that the first queries
+  /// do not pay for compiling them. Leaves all data unchanged.
+  pub fn warm_up(&self, txr: &mut Transactor) {
+    txr.execute_batch("SAVEPOINT warm_up").unwrap();
+    self.nodes.warm_up(txr);
+    self.atoms.warm_up(txr);
+    self.edges.warm_up(txr);
+    self.atoms_versions.warm_up(txr);
+    self.atoms_numeric.warm_up(txr);
+    txr.id_dst_by_src_label_atom(self.edges.prefix(), EDGES_NAME, ATOMS_NAME, 0, 0, 0, &[]);
+    txr.execute_batch("ROLLBACK TO warm_up; RELEASE warm_up").unwrap();
+  }
+
   /// Used in checking acyclicity constraints.
   fn reachable(&self, txr: &Transactor, label: u64, src: u128, dst: u128, v: &mut BTreeSet<u128>) -> bool {
     if src == dst {
@@ -269,24 +756,43 @@ impl Workspace {
     serialize(&all).unwrap().into()
   }
 
+  pub fn sync_actions(&self, txr: &Transactor, version: &[u8]) -> Box<[u8]> {
+    self.sync_actions_pruned(txr, version, 0)
+  }
+
+  /// Same as [`Workspace::sync_actions`], but leaves out removed items whose
+  /// last modification has a clock before `cutoff`, which makes the payload
+  /// for a new peer smaller. This must only be used for peers which have
+  /// never seen these items, since others would not learn of the removals.
+  ///
   /// To keep backward compatibility, do not change existing strings and type
   /// annotations below. Additional entries may be added.
-  pub fn sync_actions(&self, txr: &Transactor, version: &[u8]) -> Box<[u8]> {
+  pub fn sync_actions_pruned(&self, txr: &Transactor, version: &[u8], cutoff: u64) -> Box<[u8]> {
     let all: BTreeMap<String, &[u8]> = deserialize(version).unwrap();
 
     let nodes_version: BTreeMap<u64, u64> = all.get(NODES_NAME).map_or_else(BTreeMap::new, |m| deserialize(m).unwrap());
     let atoms_version: BTreeMap<u64, u64> = all.get(ATOMS_NAME).map_or_else(BTreeMap::new, |m| deserialize(m).unwrap());
     let edges_version: BTreeMap<u64, u64> = all.get(EDGES_NAME).map_or_else(BTreeMap::new, |m| deserialize(m).unwrap());
 
-    let nodes_actions: BTreeMap<u128, (u64, u64, Option<u64>)> = self.nodes.actions(txr, nodes_version);
-    let atoms_actions: BTreeMap<u128, (u64, u64, Option<(u128, u64, Box<[u8]>)>)> =
+    let mut nodes_actions: BTreeMap<u128, (u64, u64, Option<u64>)> = self.nodes.actions(txr, nodes_version);
+    let mut atoms_actions: BTreeMap<u128, (u64, u64, Option<(u128, u64, Box<[u8]>)>)> =
       self.atoms.actions(txr, atoms_version);
-    let edges_actions: BTreeMap<u128, (u64, u64, Option<(u128, u64, u128)>)> = self.edges.actions(txr, edges_version);
+    let mut edges_actions: BTreeMap<u128, (u64, u64, Option<(u128, u64, u128)>)> =
+      self.edges.actions(txr, edges_version);
+    nodes_actions.retain(|_, (_, clock, l)| l.is_some() || *clock >= cutoff);
+    atoms_actions.retain(|_, (_, clock, slv)| slv.is_some() || *clock >= cutoff);
+    edges_actions.retain(|_, (_, clock, sld)| sld.is_some() || *clock >= cutoff);
+    let atoms_versions: BTreeMap<u128, BTreeMap<u64, u64>> = atoms_actions
+      .iter()
+      .map(|(&id, (_, _, slv))| (id, self.atom_version(txr, id, slv.as_ref().map(|(_, label, _)| *label))))
+      .filter(|(_, version)| !version.is_empty())
+      .collect();
 
     let all: BTreeMap<&str, Vec<u8>> = BTreeMap::from([
       (NODES_NAME, serialize(&nodes_actions).unwrap()),
       (ATOMS_NAME, serialize(&atoms_actions).unwrap()),
       (EDGES_NAME, serialize(&edges_actions).unwrap()),
+      (ATOMS_VERSIONS_NAME, serialize(&atoms_versions).unwrap()),
     ]);
 
     serialize(&all).unwrap().into()
@@ -295,6 +801,30 @@ impl Workspace {
   /// To keep backward compatibility, do not change existing strings and type
   /// annotations below. Additional entries may be added.
   pub fn sync_join(&mut self, txr: &Transactor, actions: &[u8]) {
+    self.sync_join_tracked(txr, actions);
+  }
+
+  /// Same as [`Workspace::sync_join`], but returns the IDs of the nodes, atoms
+  /// and edges whose values were actually changed, leaving out actions which
+  /// lost to newer local ones or wrote the values already present.
+  pub fn sync_join_tracked(&mut self, txr: &Transactor, actions: &[u8]) -> (Vec<u128>, Vec<u128>, Vec<u128>) {
+    self.join_actions(txr, actions, None)
+  }
+
+  /// Same as [`Workspace::sync_join`], but returns every existing local atom
+  /// value which was replaced by a remote one, together with its replacement.
+  pub fn sync_join_overwrites(&mut self, txr: &Transactor, actions: &[u8]) -> Vec<AtomOverwrite> {
+    let mut overwrites = Vec::new();
+    self.join_actions(txr, actions, Some(&mut overwrites));
+    overwrites
+  }
+
+  fn join_actions(
+    &mut self,
+    txr: &Transactor,
+    actions: &[u8],
+    mut overwrites: Option<&mut Vec<AtomOverwrite>>,
+  ) -> (Vec<u128>, Vec<u128>, Vec<u128>) {
     let all: BTreeMap<String, &[u8]> = deserialize(actions).unwrap();
 
     let nodes_actions: BTreeMap<u128, (u64, u64, Option<u64>)> =
@@ -303,6 +833,17 @@ impl Workspace {
       all.get(ATOMS_NAME).map_or_else(BTreeMap::new, |m| deserialize(m).unwrap());
     let edges_actions: BTreeMap<u128, (u64, u64, Option<(u128, u64, u128)>)> =
       all.get(EDGES_NAME).map_or_else(BTreeMap::new, |m| deserialize(m).unwrap());
+    let atoms_versions: BTreeMap<u128, BTreeMap<u64, u64>> =
+      all.get(ATOMS_VERSIONS_NAME).map_or_else(BTreeMap::new, |m| deserialize(m).unwrap());
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+      prefix = self.nodes.prefix(),
+      nodes = nodes_actions.len(),
+      atoms = atoms_actions.len(),
+      edges = edges_actions.len(),
+      "join"
+    );
 
     let mut nodes_actions = nodes_actions.into_iter().collect::<Vec<_>>();
     nodes_actions.sort_by_key(|(_, (bucket, clock, _))| (*bucket, *clock));
@@ -311,14 +852,150 @@ impl Workspace {
     let mut edges_actions = edges_actions.into_iter().collect::<Vec<_>>();
     edges_actions.sort_by_key(|(_, (bucket, clock, _))| (*bucket, *clock));
 
+    #[cfg(debug_assertions)]
+    let versions = (
+      version_set::bucket_max(nodes_actions.iter().map(|(_, (b, c, _))| (*b, *c))),
+      version_set::bucket_max(atoms_actions.iter().map(|(_, (b, c, _))| (*b, *c))),
+      version_set::bucket_max(edges_actions.iter().map(|(_, (b, c, _))| (*b, *c))),
+    );
+
+    let (mut nodes, mut atoms, mut edges) = (Vec::new(), Vec::new(), Vec::new());
     for (id, (bucket, clock, l)) in nodes_actions {
-      self.nodes.set(txr, id, bucket, clock, l);
+      let prev = self.node(txr, id);
+      if self.nodes.set(txr, id, bucket, clock, l) && prev != l {
+        nodes.push(id);
+      }
     }
     for (id, (bucket, clock, slv)) in atoms_actions {
-      self.atoms.set(txr, id, bucket, clock, slv);
+      let remote = atoms_versions.get(&id).cloned().unwrap_or_default();
+      let prev = self.atom(txr, id);
+      self.join_atom(txr, id, bucket, clock, slv, remote);
+      let curr = self.atom(txr, id);
+      if curr != prev {
+        if let (Some(overwrites), Some(_)) = (overwrites.as_deref_mut(), &prev) {
+          overwrites.push(AtomOverwrite { id, local_before: prev, remote_after: curr });
+        }
+        atoms.push(id);
+      }
     }
     for (id, (bucket, clock, sld)) in edges_actions {
-      self.edges.set(txr, id, bucket, clock, sld);
+      let prev = self.edge(txr, id);
+      if self.edges.set(txr, id, bucket, clock, sld) && prev != sld {
+        edges.push(id);
+      }
+    }
+    #[cfg(debug_assertions)]
+    self.verify_convergence(&versions.0, &versions.1, &versions.2);
+    (nodes, atoms, edges)
+  }
+
+  /// Asserts that the current versions have observed everything in the given
+  /// ones, which must hold right after joining actions of these versions.
+  /// Only checked in debug builds, to catch regressions in the merge logic.
+  #[cfg(debug_assertions)]
+  fn verify_convergence(&self, nodes: &BTreeMap<u64, u64>, atoms: &BTreeMap<u64, u64>, edges: &BTreeMap<u64, u64>) {
+    assert!(dominates(&self.nodes.buckets(), nodes), "joined nodes do not dominate their input");
+    assert!(dominates(&self.atoms.buckets(), atoms), "joined atoms do not dominate their input");
+    assert!(dominates(&self.edges.buckets(), edges), "joined edges do not dominate their input");
+  }
+
+  /// Joins all data from `other` into this workspace. This is equivalent to
+  /// [`Workspace::sync_join`] on the actions of `other`, but reads `other` in
+  /// batches of [`MERGE_BATCH_SIZE`] instead of materialising all actions at
+  /// once. `other` must not have pending modifications.
+  pub fn merge_from(&mut self, txr: &Transactor, other: &Workspace, other_txr: &Transactor) {
+    assert!(other.nodes.is_saved() && other.atoms.is_saved() && other.edges.is_saved());
+
+    for &bucket in other.nodes.buckets().keys() {
+      let mut lower = self.nodes.buckets().get(&bucket).copied();
+      loop {
+        let batch = other.nodes.actions_batch(other_txr, bucket, lower, MERGE_BATCH_SIZE);
+        let Some(&(_, (_, clock, _))) = batch.last() else { break };
+        lower = Some(clock);
+        for (id, (bucket, clock, l)) in batch {
+          self.nodes.set(txr, id, bucket, clock, l);
+        }
+      }
+    }
+    for &bucket in other.atoms.buckets().keys() {
+      let mut lower = self.atoms.buckets().get(&bucket).copied();
+      loop {
+        let batch = other.atoms.actions_batch(other_txr, bucket, lower, MERGE_BATCH_SIZE);
+        let Some(&(_, (_, clock, _))) = batch.last() else { break };
+        lower = Some(clock);
+        for (id, (bucket, clock, slv)) in batch {
+          let remote = other.atom_version(other_txr, id, slv.as_ref().map(|(_, label, _)| *label));
+          self.join_atom(txr, id, bucket, clock, slv, remote);
+        }
+      }
+    }
+    for &bucket in other.edges.buckets().keys() {
+      let mut lower = self.edges.buckets().get(&bucket).copied();
+      loop {
+        let batch = other.edges.actions_batch(other_txr, bucket, lower, MERGE_BATCH_SIZE);
+        let Some(&(_, (_, clock, _))) = batch.last() else { break };
+        lower = Some(clock);
+        for (id, (bucket, clock, sld)) in batch {
+          self.edges.set(txr, id, bucket, clock, sld);
+        }
+      }
+    }
+  }
+
+  /// Returns the version vector of the atom `id` with `label`. Only atoms
+  /// with causal labels have one, so this skips the lookup unless `label` is
+  /// causal, or unknown (e.g. for removals) while any label is.
+  fn atom_version(&self, txr: &Transactor, id: u128, label: Option<u64>) -> BTreeMap<u64, u64> {
+    let causal = &self.constraints.causal_atoms;
+    if causal.is_empty() || label.is_some_and(|label| !causal.contains(&label)) {
+      return BTreeMap::new();
+    }
+    self.atoms_versions.get(txr, id)
+  }
+
+  /// Joins one remote atom action, detecting and merging concurrent values.
+  fn join_atom(
+    &mut self,
+    txr: &Transactor,
+    id: u128,
+    bucket: u64,
+    clock: u64,
+    slv: Option<(u128, u64, Box<[u8]>)>,
+    remote: BTreeMap<u64, u64>,
+  ) {
+    let local = self.atom_version(txr, id, slv.as_ref().map(|(_, label, _)| *label));
+    let concurrent =
+      !local.is_empty() && !remote.is_empty() && !dominates(&remote, &local) && !dominates(&local, &remote);
+    let prev = if concurrent { self.atom(txr, id) } else { None };
+    if concurrent {
+      if let Some((_, label, _)) = prev.as_ref().or(slv.as_ref()) {
+        *self.conflict_counts.entry(*label).or_default() += 1;
+      }
+      self.conflicts.push(AtomConflict { id, local: prev.clone(), remote: slv.clone() });
+    }
+    // The winning value carries every version seen so far.
+    let won = self.atoms.set(txr, id, bucket, clock, slv.clone());
+    if won && !dominates(&local, &remote) {
+      self.atoms_versions.set(id, pointwise_max(&local, &remote));
+    }
+    if !concurrent {
+      return;
+    }
+    // Merge concurrent values, if requested. Otherwise rewrite the winning
+    // value, so that peers still holding the losing one receive it with the
+    // joined version instead of seeing it as concurrent again.
+    if let (Some((src, label, lvalue)), Some((src_, label_, rvalue))) = (prev, slv.clone()) {
+      if let Some(merge) = self.constraints.atom_mergers.get(&label).filter(|_| src == src_ && label == label_) {
+        let merged = merge(&lvalue, &rvalue);
+        self.atoms_versions.set(id, pointwise_max(&local, &remote));
+        if self.atom(txr, id).map(|(_, _, value)| value) != Some(merged.clone()) {
+          self.set_atom(txr, id, Some((src, label, merged)));
+        }
+        return;
+      }
+    }
+    if won {
+      self.set_atom(txr, id, slv);
     }
   }
 }
@@ -609,6 +1286,1015 @@ mod tests {
     }
   }
 
+  #[test]
+  fn sync_unseen_bucket() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut rng = rand::thread_rng();
+    let mut ws0 = Workspace::new("ws0", Constraints::new(), &mut txr);
+    let mut ws1 = Workspace::new("ws1", Constraints::new(), &mut txr);
+
+    let node = rng.gen();
+    let atom = rng.gen();
+    let edge = rng.gen();
+    ws0.set_node(&txr, node, Some(0));
+    ws0.set_atom(&txr, atom, Some((node, 1, [42].into())));
+    ws0.set_edge(&txr, edge, Some((node, 2, node)));
+    ws0.barrier(&mut txr);
+
+    // `ws1` has never seen any bucket of `ws0`, so all actions must be sent.
+    let actions = ws0.sync_actions(&txr, &ws1.sync_version(&txr));
+    ws1.sync_join(&txr, &actions);
+    ws1.barrier(&mut txr);
+    assert_eq!(ws1.node(&txr, node), Some(0));
+    assert_eq!(ws1.atom(&txr, atom), Some((node, 1, [42].into())));
+    assert_eq!(ws1.edge(&txr, edge), Some((node, 2, node)));
+  }
+
+  #[test]
+  fn clear_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut rng = rand::thread_rng();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+
+    let node0 = rng.gen();
+    let node1 = rng.gen();
+    let atom0 = rng.gen();
+    let edge0 = rng.gen();
+    ws.set_node(&txr, node0, Some(0));
+    ws.set_node(&txr, node1, Some(0));
+    ws.set_atom(&txr, atom0, Some((node0, 1, vec![1, 2, 3].into())));
+    ws.set_edge(&txr, edge0, Some((node0, 2, node1)));
+    ws.barrier(&mut txr);
+    ws.set_node(&txr, rng.gen(), Some(0)); // Pending
+    assert_eq!(ws.node_id_by_label(&txr, 0).len(), 3);
+    let version = ws.sync_version(&txr);
+
+    ws.clear(&mut txr);
+    assert!(ws.node(&txr, node0).is_none());
+    assert!(ws.atom(&txr, atom0).is_none());
+    assert!(ws.edge(&txr, edge0).is_none());
+    assert!(ws.node_id_by_label(&txr, 0).is_empty());
+    assert!(ws.atom_id_label_value_by_src(&txr, node0).is_empty());
+    assert!(ws.edge_id_src_label_by_dst(&txr, node1).is_empty());
+    assert!(ws.barrier(&mut txr).is_empty());
+
+    // Cleared data is not deleted: a resync brings it back.
+    let mut other_txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut other = Workspace::new("", Constraints::new(), &mut other_txr);
+    other.set_node(&other_txr, node0, Some(0));
+    other.set_node(&other_txr, node1, Some(0));
+    other.set_edge(&other_txr, edge0, Some((node0, 2, node1)));
+    other.barrier(&mut other_txr);
+    assert_ne!(ws.sync_version(&txr), version);
+    let actions = other.sync_actions(&other_txr, &ws.sync_version(&txr));
+    ws.sync_join(&txr, &actions);
+    ws.barrier(&mut txr);
+    assert_eq!(ws.node(&txr, node0), Some(0));
+    assert_eq!(ws.edge(&txr, edge0), Some((node0, 2, node1)));
+  }
+
+  #[test]
+  fn warm_up_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    ws.set_node(&txr, 1, Some(0));
+    ws.set_atom(&txr, 2, Some((1, 0, vec![0].into())));
+    ws.barrier(&mut txr);
+    let version = ws.sync_version(&txr);
+    let actions = ws.sync_actions(&txr, &serialize(&BTreeMap::<String, Vec<u8>>::new()).unwrap());
+
+    ws.warm_up(&mut txr);
+    assert_eq!(ws.node(&txr, 0), None);
+    assert_eq!(ws.atom(&txr, 0), None);
+    assert_eq!(ws.edge(&txr, 0), None);
+    assert_eq!(ws.node(&txr, 1), Some(0));
+    assert_eq!(ws.atom(&txr, 2), Some((1, 0, vec![0].into())));
+    assert_eq!(ws.sync_version(&txr), version);
+    assert_eq!(ws.sync_actions(&txr, &serialize(&BTreeMap::<String, Vec<u8>>::new()).unwrap()), actions);
+    ws.set_edge(&txr, 3, Some((1, 0, 1)));
+    ws.barrier(&mut txr);
+    assert_eq!(ws.edge(&txr, 3), Some((1, 0, 1)));
+  }
+
+  #[test]
+  fn actions_batch_keyset() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("ws0", Constraints::new(), &mut txr);
+    let count = 3 * MERGE_BATCH_SIZE as u128 + 10;
+    for id in 1..=count {
+      ws0.set_node(&txr, id, Some(0));
+      ws0.set_atom(&txr, id << 64, Some((id, 1, [1].into())));
+      ws0.set_edge(&txr, id << 32, Some((id, 2, id)));
+    }
+    ws0.barrier(&mut txr);
+
+    // Each batch starts right after the previous one.
+    let bucket = *ws0.nodes.buckets().keys().next().unwrap();
+    let (mut lower, mut clocks) = (None, Vec::new());
+    loop {
+      let batch = ws0.nodes.actions_batch(&txr, bucket, lower, MERGE_BATCH_SIZE);
+      let Some(&(_, (_, clock, _))) = batch.last() else { break };
+      assert!(batch.len() <= MERGE_BATCH_SIZE as usize);
+      clocks.extend(batch.iter().map(|(_, (_, clock, _))| *clock));
+      lower = Some(clock);
+    }
+    assert_eq!(clocks.len() as u128, count);
+    assert!(clocks.windows(2).all(|w| w[0] < w[1]));
+
+    let mut ws1 = Workspace::new("ws1", Constraints::new(), &mut txr);
+    ws1.merge_from(&txr, &ws0, &txr);
+    ws1.barrier(&mut txr);
+    assert_eq!(ws1.node_id_by_label(&txr, 0).len() as u128, count);
+    assert_eq!(ws1.atom_id_src_value_by_label(&txr, 1).len() as u128, count);
+    assert_eq!(ws1.edge(&txr, count << 32), Some((count, 2, count)));
+  }
+
+  #[test]
+  fn merge_from_simple() {
+    fn random_ops(ws: &mut Workspace, txr: &mut Transactor, nodes: &[u128], count: usize) {
+      let mut rng = rand::thread_rng();
+      for _ in 0..count {
+        let src = *nodes.choose(&mut rng).unwrap();
+        let dst = *nodes.choose(&mut rng).unwrap();
+        match rng.gen_range(0..4) {
+          0 => ws.set_node(txr, if rng.gen_bool(0.9) { src } else { rng.gen() }, Some(rng.gen_range(0..3))),
+          1 => ws.set_atom(txr, src ^ rng.gen_range(0..3), Some((src, rng.gen_range(0..3), [rng.gen()].into()))),
+          2 => ws.set_edge(txr, src ^ dst, Some((src, rng.gen_range(0..3), dst))),
+          _ => ws.set_node(txr, src, None),
+        }
+      }
+      ws.barrier(txr);
+    }
+    fn dump(ws: &Workspace, txr: &Transactor) -> Vec<String> {
+      let mut res = Vec::new();
+      for label in 0..3 {
+        for id in ws.node_id_by_label(txr, label).into_keys() {
+          res.push(format!("{id} {label} {:?}", ws.edge_id_label_dst_by_src(txr, id)));
+        }
+        res.push(format!("{:?}", ws.atom_id_src_value_by_label(txr, label)));
+      }
+      res
+    }
+
+    let mut rng = rand::thread_rng();
+    let nodes = (0..600).map(|_| rng.gen()).collect::<Vec<u128>>();
+    let mut txr0: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("", Constraints::new(), &mut txr0);
+    let mut txr1: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws1 = Workspace::new("", Constraints::new(), &mut txr1);
+    let mut txr2: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws2 = Workspace::new("", Constraints::new(), &mut txr2);
+
+    for &id in &nodes {
+      ws0.set_node(&txr0, id, Some(0));
+    }
+    ws0.barrier(&mut txr0);
+    ws1.merge_from(&txr1, &ws0, &txr0);
+    ws1.barrier(&mut txr1);
+    assert_eq!(dump(&ws0, &txr0), dump(&ws1, &txr1));
+
+    // Diverge, and keep `ws2` as a copy of `ws0`.
+    random_ops(&mut ws0, &mut txr0, &nodes, 1000);
+    random_ops(&mut ws1, &mut txr1, &nodes, 1000);
+    ws2.sync_join(&txr2, &ws0.sync_actions(&txr0, &ws2.sync_version(&txr2)));
+    ws2.barrier(&mut txr2);
+    assert_eq!(dump(&ws0, &txr0), dump(&ws2, &txr2));
+
+    ws0.merge_from(&txr0, &ws1, &txr1);
+    ws0.barrier(&mut txr0);
+    ws2.sync_join(&txr2, &ws1.sync_actions(&txr1, &ws2.sync_version(&txr2)));
+    ws2.barrier(&mut txr2);
+    assert_eq!(dump(&ws0, &txr0), dump(&ws2, &txr2));
+
+    // Merging again changes nothing.
+    ws0.merge_from(&txr0, &ws1, &txr1);
+    assert!(ws0.barrier(&mut txr0).is_empty());
+  }
+
+  #[test]
+  fn numeric_range_simple() {
+    use std::ops::Bound;
+
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut constraints = Constraints::new();
+    constraints.add_numeric_atom(1, NumericKind::Integer);
+    constraints.add_numeric_atom(2, NumericKind::Real);
+    let mut ws = Workspace::new("", constraints, &mut txr);
+
+    for i in -5..5i64 {
+      let node = ((i + 100) as u128) << 8;
+      ws.set_node(&txr, node, Some(0));
+      ws.set_atom(&txr, node ^ 1, Some((node, 1, serialize(&i).unwrap().into())));
+      ws.set_atom(&txr, node ^ 2, Some((node, 2, serialize(&(i as f64 / 2.0)).unwrap().into())));
+      ws.set_atom(&txr, node ^ 3, Some((node, 3, serialize(&i).unwrap().into())));
+    }
+    ws.barrier(&mut txr);
+    let srcs = |res: BTreeMap<u128, u128>| {
+      let mut res = res.into_values().map(|src| (src >> 8) as i64 - 100).collect::<Vec<_>>();
+      res.sort();
+      res
+    };
+    assert_eq!(srcs(ws.atom_id_src_by_label_range(&txr, 1, (Bound::Excluded(2.0), Bound::Unbounded))), [3, 4]);
+    assert_eq!(srcs(ws.atom_id_src_by_label_range(&txr, 1, -1.0..1.0)), [-1, 0]);
+    assert_eq!(srcs(ws.atom_id_src_by_label_range(&txr, 2, ..=-2.0)), [-5, -4]);
+    assert!(ws.atom_id_src_by_label_range(&txr, 3, ..).is_empty());
+
+    // Modifications, removals and removed nodes are reflected after barrier.
+    let node = |i: u128| (i + 100) << 8;
+    ws.set_atom(&txr, node(4) ^ 1, Some((node(4), 1, serialize(&-10i64).unwrap().into())));
+    ws.set_atom(&txr, node(3) ^ 1, None);
+    ws.set_node(&txr, node(2), None);
+    assert_eq!(srcs(ws.atom_id_src_by_label_range(&txr, 1, 2.0..)), [2, 3, 4]);
+    ws.barrier(&mut txr);
+    assert!(ws.atom_id_src_by_label_range(&txr, 1, 2.0..).is_empty());
+    assert_eq!(srcs(ws.atom_id_src_by_label_range(&txr, 1, ..-4.0)), [-5, 4]);
+
+    // Newly added labels are indexed on load.
+    let mut constraints = Constraints::new();
+    constraints.add_numeric_atom(3, NumericKind::Integer);
+    let ws = Workspace::new("", constraints, &mut txr);
+    assert_eq!(srcs(ws.atom_id_src_by_label_range(&txr, 3, 3.0..)), [3, 4]);
+  }
+
+  #[test]
+  fn numeric_nan() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut constraints = Constraints::new();
+    constraints.add_numeric_atom(2, NumericKind::Real);
+    let mut ws = Workspace::new("", constraints, &mut txr);
+    ws.set_node(&txr, 1, Some(0));
+    ws.set_atom(&txr, 3, Some((1, 2, serialize(&1.0f64).unwrap().into())));
+    ws.barrier(&mut txr);
+    assert_eq!(ws.atom_id_src_by_label_range(&txr, 2, ..).len(), 1);
+
+    // NaN is saved, but not indexed (and replaces the previous value there).
+    let nan: Box<[u8]> = serialize(&f64::NAN).unwrap().into();
+    ws.set_atom(&txr, 3, Some((1, 2, nan.clone())));
+    ws.set_atom(&txr, 4, Some((1, 2, nan.clone())));
+    ws.barrier(&mut txr);
+    assert_eq!(ws.atom(&txr, 4), Some((1, 2, nan)));
+    assert!(ws.atom_id_src_by_label_range(&txr, 2, ..).is_empty());
+  }
+
+  #[test]
+  fn list_collections_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let _ = Workspace::new("first", Constraints::new(), &mut txr);
+    let _ = Workspace::new("second.graph", Constraints::new(), &mut txr);
+    let collections = list_collections(&txr);
+    let expected = [
+      ("first", "atoms"),
+      ("first", "edges"),
+      ("first", "nodes"),
+      ("second.graph", "atoms"),
+      ("second.graph", "edges"),
+      ("second.graph", "nodes"),
+    ];
+    assert_eq!(collections, expected.map(|(prefix, name)| (prefix.to_owned(), name.to_owned())));
+  }
+
+  #[test]
+  fn set_if_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    assert!(ws.set_node_if(&txr, 1, None, Some(10)));
+    assert!(!ws.set_node_if(&txr, 1, None, Some(11)));
+    ws.barrier(&mut txr);
+    assert!(!ws.set_node_if(&txr, 1, Some(11), Some(12)));
+    assert_eq!(ws.node(&txr, 1), Some(10));
+    assert!(ws.set_node_if(&txr, 1, Some(10), Some(12)));
+    assert_eq!(ws.node(&txr, 1), Some(12));
+
+    assert!(ws.set_atom_if(&txr, 2, None, Some((1, 20, vec![0].into()))));
+    assert!(!ws.set_atom_if(&txr, 2, Some((1, 20, &[1])), Some((1, 20, vec![2].into()))));
+    assert!(ws.set_atom_if(&txr, 2, Some((1, 20, &[0])), Some((1, 20, vec![1].into()))));
+    assert!(ws.set_edge_if(&txr, 3, None, Some((1, 30, 1))));
+    assert!(!ws.set_edge_if(&txr, 3, Some((1, 30, 2)), None));
+    ws.barrier(&mut txr);
+    assert_eq!(ws.atom(&txr, 2), Some((1, 20, vec![1].into())));
+    assert_eq!(ws.edge(&txr, 3), Some((1, 30, 1)));
+  }
+
+  #[test]
+  fn repair_node_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    for id in 1..=3 {
+      ws.set_node(&txr, id, Some(0));
+    }
+    ws.set_edge(&txr, 11, Some((1, 5, 3)));
+    ws.set_edge(&txr, 10, Some((1, 5, 2)));
+    ws.set_edge(&txr, 12, Some((1, 6, 2)));
+    ws.barrier(&mut txr);
+
+    assert_eq!(ws.repair_node(&txr, 1, &[5, 6]), 1);
+    ws.barrier(&mut txr);
+    assert_eq!(ws.edge_id_dst_by_src_label(&txr, 1, 5), BTreeMap::from([(10, 2)]));
+    assert_eq!(ws.edge_id_dst_by_src_label(&txr, 1, 6), BTreeMap::from([(12, 2)]));
+    assert_eq!(ws.repair_node(&txr, 1, &[5, 6]), 0);
+  }
+
+  #[test]
+  fn clock_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    assert_eq!(ws.atom_clock(&txr, 2), None);
+    assert_eq!(ws.edge_clock(&txr, 3), None);
+    ws.set_node(&txr, 1, Some(10));
+    ws.set_atom(&txr, 2, Some((1, 20, vec![0].into())));
+    ws.set_edge(&txr, 3, Some((1, 30, 1)));
+    ws.barrier(&mut txr);
+    let atom = ws.atom_clock(&txr, 2).unwrap();
+    let edge = ws.edge_clock(&txr, 3).unwrap();
+    ws.set_atom(&txr, 2, Some((1, 20, vec![1].into())));
+    ws.set_edge(&txr, 3, None);
+    ws.barrier(&mut txr);
+    assert!(ws.atom_clock(&txr, 2).unwrap() > atom);
+    assert!(ws.edge_clock(&txr, 3).unwrap() > edge);
+  }
+
+  #[test]
+  fn edge_written_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+
+    ws.set_node(&txr, 1, Some(0));
+    ws.set_node(&txr, 2, Some(0));
+    ws.barrier(&mut txr);
+    assert!(!ws.edge_written(&txr, 3));
+    ws.set_edge(&txr, 3, Some((1, 0, 2)));
+    ws.barrier(&mut txr);
+    assert!(ws.edge_written(&txr, 3));
+    ws.set_edge(&txr, 3, None);
+    ws.barrier(&mut txr);
+    assert!(ws.edge(&txr, 3).is_none());
+    assert!(ws.edge_written(&txr, 3));
+
+    // Removing a never-existing edge also counts as writing it.
+    ws.set_edge(&txr, 4, None);
+    assert!(ws.edge_written(&txr, 4));
+  }
+
+  #[test]
+  fn node_label_cache_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    ws.enable_node_label_cache();
+    ws.set_node(&txr, 1, Some(10));
+    ws.set_node(&txr, 2, Some(20));
+    ws.barrier(&mut txr);
+    assert_eq!(ws.node_id_by_label(&txr, 10), BTreeMap::from([(1, ())]));
+
+    // Rows written behind the workspace's back are not seen while cached.
+    let sql = node_set::NodeSetTransactor::item_statements(&txr, "", NODES_NAME);
+    node_set::NodeSetTransactor::set(&mut txr, &sql, 3, (0, 0, Some(10)));
+    assert_eq!(ws.node_id_by_label(&txr, 10), BTreeMap::from([(1, ())]));
+
+    // Pending and saved modifications are.
+    ws.set_node(&txr, 2, Some(10));
+    assert_eq!(ws.node_id_by_label(&txr, 10), BTreeMap::from([(1, ()), (2, ())]));
+    ws.barrier(&mut txr);
+    assert_eq!(ws.node_id_by_label(&txr, 10), BTreeMap::from([(1, ()), (2, ()), (3, ())]));
+    assert_eq!(ws.node_id_by_label(&txr, 20), BTreeMap::new());
+
+    // So are joins.
+    let mut txr1: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws1 = Workspace::new("", Constraints::new(), &mut txr1);
+    ws1.set_node(&txr1, 4, Some(20));
+    ws1.barrier(&mut txr1);
+    let actions = ws1.sync_actions(&txr1, &ws.sync_version(&txr));
+    ws.sync_join(&txr, &actions);
+    ws.barrier(&mut txr);
+    assert_eq!(ws.node_id_by_label(&txr, 20), BTreeMap::from([(4, ())]));
+  }
+
+  #[test]
+  fn sync_join_tracked_simple() {
+    let mut txr0: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("", Constraints::new(), &mut txr0);
+    let mut txr1: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws1 = Workspace::new("", Constraints::new(), &mut txr1);
+    ws1.set_node(&txr1, 2, Some(20));
+    ws1.barrier(&mut txr1);
+    ws0.set_node(&txr0, 1, Some(10));
+    ws0.set_node(&txr0, 2, Some(20));
+    ws0.set_atom(&txr0, 3, Some((1, 30, vec![3].into())));
+    ws0.set_edge(&txr0, 4, Some((1, 40, 2)));
+    ws0.barrier(&mut txr0);
+
+    // Node 2 already has the same label, so only its clock changes.
+    let actions = ws0.sync_actions(&txr0, &ws1.sync_version(&txr1));
+    assert_eq!(ws1.sync_join_tracked(&txr1, &actions), (vec![1], vec![3], vec![4]));
+    ws1.barrier(&mut txr1);
+    assert_eq!(ws1.sync_join_tracked(&txr1, &actions), (vec![], vec![], vec![]));
+  }
+
+  #[test]
+  fn sync_join_converges() {
+    let mut txr0: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("", Constraints::new(), &mut txr0);
+    let mut txr1: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws1 = Workspace::new("", Constraints::new(), &mut txr1);
+    ws0.set_node(&txr0, 1, Some(10));
+    ws0.set_atom(&txr0, 2, Some((1, 20, vec![2].into())));
+    ws0.barrier(&mut txr0);
+    ws1.set_node(&txr1, 1, Some(11));
+    ws1.set_edge(&txr1, 3, Some((1, 30, 1)));
+    ws1.barrier(&mut txr1);
+
+    // Joining in both directions (which also runs the debug assertion) leaves
+    // both sides dominating each other.
+    let actions = ws0.sync_actions(&txr0, &ws1.sync_version(&txr1));
+    ws1.sync_join(&txr1, &actions);
+    ws1.barrier(&mut txr1);
+    let actions = ws1.sync_actions(&txr1, &ws0.sync_version(&txr0));
+    ws0.sync_join(&txr0, &actions);
+    ws0.barrier(&mut txr0);
+    for (lhs, rhs) in [(&ws0, &ws1), (&ws1, &ws0)] {
+      assert!(dominates(&lhs.nodes.buckets(), &rhs.nodes.buckets()));
+      assert!(dominates(&lhs.atoms.buckets(), &rhs.atoms.buckets()));
+      assert!(dominates(&lhs.edges.buckets(), &rhs.edges.buckets()));
+    }
+    assert_eq!(ws0.node(&txr0, 1), ws1.node(&txr1, 1));
+  }
+
+  #[test]
+  fn dump_node_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    assert_eq!(ws.dump_node(&txr, 1), (None, vec![]));
+    for id in [1, 5, 6, 7] {
+      ws.set_node(&txr, id, Some(10));
+    }
+    ws.set_edge(&txr, 2, Some((1, 20, 5)));
+    ws.set_edge(&txr, 3, Some((1, 30, 6)));
+    ws.set_edge(&txr, 4, Some((5, 20, 1)));
+    ws.barrier(&mut txr);
+    ws.set_edge(&txr, 3, Some((1, 30, 7)));
+    let clock2 = ws.edge_clock(&txr, 2).unwrap();
+    let clock3 = ws.edge_clock(&txr, 3).unwrap();
+    assert!(clock3 > clock2);
+    assert_eq!(ws.dump_node(&txr, 1), (Some(10), vec![(2, (1, 20, 5), clock2), (3, (1, 30, 7), clock3)]));
+  }
+
+  #[test]
+  fn sync_join_overwrites_simple() {
+    let mut txr0: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("", Constraints::new(), &mut txr0);
+    let mut txr1: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws1 = Workspace::new("", Constraints::new(), &mut txr1);
+    ws1.set_node(&txr1, 5, Some(1));
+    ws1.set_atom(&txr1, 1, Some((5, 10, vec![1].into())));
+    ws1.set_atom(&txr1, 2, Some((5, 10, vec![2].into())));
+    ws1.barrier(&mut txr1);
+    ws0.sync_join(&txr0, &ws1.sync_actions(&txr1, &ws0.sync_version(&txr0)));
+    ws0.barrier(&mut txr0);
+    ws0.set_atom(&txr0, 1, Some((5, 10, vec![3].into())));
+    ws0.set_atom(&txr0, 2, None);
+    ws0.set_atom(&txr0, 3, Some((5, 10, vec![4].into())));
+    ws0.barrier(&mut txr0);
+
+    // Atom 3 did not exist locally, so it is not reported.
+    let actions = ws0.sync_actions(&txr0, &ws1.sync_version(&txr1));
+    assert_eq!(
+      ws1.sync_join_overwrites(&txr1, &actions),
+      vec![
+        AtomOverwrite {
+          id: 1,
+          local_before: Some((5, 10, vec![1].into())),
+          remote_after: Some((5, 10, vec![3].into()))
+        },
+        AtomOverwrite { id: 2, local_before: Some((5, 10, vec![2].into())), remote_after: None },
+      ]
+    );
+    ws1.barrier(&mut txr1);
+    assert_eq!(ws1.sync_join_overwrites(&txr1, &actions), vec![]);
+  }
+
+  #[test]
+  fn sync_actions_pruned_simple() {
+    let mut txr0: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("", Constraints::new(), &mut txr0);
+    ws0.set_node(&txr0, 1, Some(10));
+    ws0.set_edge(&txr0, 2, Some((1, 20, 1)));
+    ws0.set_edge(&txr0, 3, Some((1, 20, 1)));
+    ws0.barrier(&mut txr0);
+    ws0.set_edge(&txr0, 2, None);
+    ws0.barrier(&mut txr0);
+    let cutoff = ws0.edge_clock(&txr0, 2).unwrap() + 1;
+    ws0.set_edge(&txr0, 4, Some((1, 20, 1)));
+    ws0.barrier(&mut txr0);
+    ws0.set_edge(&txr0, 4, None);
+    ws0.barrier(&mut txr0);
+
+    // The old tombstone is left out, while the recent one and the old live
+    // edge are kept.
+    let mut txr1: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws1 = Workspace::new("", Constraints::new(), &mut txr1);
+    let version = ws1.sync_version(&txr1);
+    let actions = ws0.sync_actions_pruned(&txr0, &version, cutoff);
+    assert!(actions.len() < ws0.sync_actions(&txr0, &version).len());
+    ws1.sync_join(&txr1, &actions);
+    ws1.barrier(&mut txr1);
+    assert!(!ws1.edge_written(&txr1, 2));
+    assert_eq!(ws1.edge(&txr1, 3), Some((1, 20, 1)));
+    assert!(ws1.edge_written(&txr1, 4) && ws1.edge(&txr1, 4).is_none());
+  }
+
+  #[test]
+  fn node_id_by_label_ordered_by_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    for (id, name) in [(1, "carol"), (2, "alice"), (3, "bob"), (4, "alice")] {
+      ws.set_node(&txr, id, Some(10));
+      ws.set_atom(&txr, id + 100, Some((id, 20, name.as_bytes().into())));
+    }
+    ws.set_node(&txr, 5, Some(10));
+    ws.set_node(&txr, 6, Some(11));
+
+    // Pending modifications are sorted in memory, saved ones by the query.
+    for _ in 0..2 {
+      assert_eq!(ws.node_id_by_label_ordered_by(&txr, 10, 20, false), vec![5, 2, 4, 3, 1]);
+      assert_eq!(ws.node_id_by_label_ordered_by(&txr, 10, 20, true), vec![1, 3, 2, 4, 5]);
+      ws.barrier(&mut txr);
+    }
+  }
+
+  #[test]
+  fn migrate_atoms_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    for id in 1..=3u32 {
+      ws.set_node(&txr, id.into(), Some(10));
+      ws.set_atom(&txr, (id + 100).into(), Some((id.into(), 20, (id * 1000).to_be_bytes().into())));
+    }
+    ws.set_atom(&txr, 200, Some((1, 30, 7u32.to_be_bytes().into())));
+    ws.barrier(&mut txr);
+
+    let widen = |value: &[u8]| {
+      let value: [u8; 4] = value.try_into().ok()?;
+      Some(u64::from(u32::from_be_bytes(value)).to_be_bytes().into())
+    };
+    assert_eq!(ws.migrate_atoms(&txr, 20, widen), 3);
+    ws.barrier(&mut txr);
+    assert_eq!(ws.migrate_atoms(&txr, 20, widen), 0);
+    for id in 1..=3u32 {
+      let (_, _, value) = ws.atom(&txr, (id + 100).into()).unwrap();
+      assert_eq!(u64::from_be_bytes((*value).try_into().unwrap()), u64::from(id * 1000));
+    }
+    assert_eq!(ws.atom(&txr, 200), Some((1, 30, 7u32.to_be_bytes().into())));
+  }
+
+  #[test]
+  fn by_srcs_batched() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    for id in 0..600 {
+      ws.set_node(&txr, id, Some(0));
+      ws.set_atom(&txr, id + 1000, Some((id, 1, vec![id as u8].into())));
+      ws.set_edge(&txr, id + 2000, Some((id, 2, (id + 1) % 600)));
+    }
+    ws.barrier(&mut txr);
+    ws.set_atom(&txr, 1000, None);
+    ws.set_edge(&txr, 2001, Some((599, 3, 0)));
+
+    let srcs: Vec<u128> = (0..600).rev().collect();
+    let atoms = ws.atom_id_src_label_value_by_srcs(&txr, &srcs);
+    let edges = ws.edge_id_src_label_dst_by_srcs(&txr, &srcs);
+    assert_eq!((atoms.len(), edges.len()), (599, 600));
+    for src in srcs {
+      for (id, (label, value)) in ws.atom_id_label_value_by_src(&txr, src) {
+        assert_eq!(atoms[&id], (src, label, value));
+      }
+      for (id, (label, dst)) in ws.edge_id_label_dst_by_src(&txr, src) {
+        assert_eq!(edges[&id], (src, label, dst));
+      }
+    }
+  }
+
+  #[test]
+  fn edges_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    for id in 0..300 {
+      ws.set_node(&txr, id, Some(0));
+    }
+    for id in 0..300 {
+      ws.set_edge(&txr, id + 1000, Some((id, 1, (id + 1) % 300)));
+    }
+    ws.barrier(&mut txr);
+    ws.set_edge(&txr, 1000, None);
+    ws.set_edge(&txr, 1001, Some((5, 2, 6)));
+
+    let ids: Vec<u128> = (990..1300).chain([1001, 1002]).collect();
+    let edges = ws.edges(&txr, &ids);
+    assert_eq!(edges.len(), 299);
+    for id in ids {
+      assert_eq!(edges.get(&id).copied(), ws.edge(&txr, id));
+    }
+    assert_eq!(edges[&1001], (5, 2, 6));
+  }
+
+  #[test]
+  fn touch_by_label_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    for id in 0..300 {
+      ws.set_node(&txr, id, Some(10));
+      ws.set_atom(&txr, id + 1000, Some((id, 20, vec![id as u8].into())));
+      ws.set_edge(&txr, id + 2000, Some((id, 30, (id + 1) % 300)));
+    }
+    ws.set_node(&txr, 300, Some(11));
+    ws.barrier(&mut txr);
+    let clock = |ws: &Workspace, txr: &Transactor, id| ws.nodes.get(txr, id).unwrap().1;
+    let before = ws.atoms.next().max(ws.edges.next()).max(ws.nodes.next());
+    let other = clock(&ws, &txr, 300);
+
+    let (count, events) = ws.touch_by_label(&mut txr, 10);
+    assert_eq!(count, 300);
+    assert_eq!(events.len(), 900);
+    let nodes = |id: u128| {
+      events.iter().filter(|event| matches!(event, CEventData::Node { id: id_, .. } if u128::from(*id_) == id)).count()
+    };
+    assert_eq!((nodes(0), nodes(299), nodes(300)), (1, 1, 0));
+    for id in 0..300 {
+      assert!(clock(&ws, &txr, id) >= before);
+      assert!(ws.atom_clock(&txr, id + 1000).unwrap() >= before);
+      assert!(ws.edge_clock(&txr, id + 2000).unwrap() >= before);
+      assert_eq!(ws.atom(&txr, id + 1000), Some((id, 20, vec![id as u8].into())));
+      assert_eq!(ws.edge(&txr, id + 2000), Some((id, 30, (id + 1) % 300)));
+    }
+    assert_eq!(clock(&ws, &txr, 300), other);
+  }
+
+  #[test]
+  fn swap_edge_dst_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    for id in 1..=3 {
+      ws.set_node(&txr, id, Some(10));
+    }
+    ws.set_edge(&txr, 4, Some((1, 20, 2)));
+    ws.set_edge(&txr, 5, Some((1, 20, 3)));
+    ws.barrier(&mut txr);
+    let (clock_a, clock_b) = (ws.edge_clock(&txr, 4).unwrap(), ws.edge_clock(&txr, 5).unwrap());
+
+    assert!(ws.swap_edge_dst(&txr, 4, 5));
+    assert!(!ws.swap_edge_dst(&txr, 4, 6));
+    let events = ws.barrier(&mut txr);
+    assert_eq!(events.len(), 2);
+    assert_eq!(ws.edge(&txr, 4), Some((1, 20, 3)));
+    assert_eq!(ws.edge(&txr, 5), Some((1, 20, 2)));
+    assert!(ws.edge_clock(&txr, 4).unwrap() > clock_a);
+    assert!(ws.edge_clock(&txr, 5).unwrap() > clock_b);
+  }
+
+  #[test]
+  fn rekey_node_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    ws.set_node(&txr, 1, Some(10));
+    ws.set_node(&txr, 2, Some(10));
+    ws.set_atom(&txr, 1 ^ 20, Some((1, 20, vec![7].into())));
+    ws.set_edge(&txr, 1 ^ 30, Some((1, 30, 2)));
+    ws.set_edge(&txr, 2 ^ 30, Some((2, 30, 1)));
+    ws.set_edge(&txr, 40, Some((1, 40, 1)));
+    ws.barrier(&mut txr);
+
+    assert!(ws.rekey_node(&txr, 1, 5));
+    ws.barrier(&mut txr);
+    assert!(!ws.rekey_node(&txr, 1, 6));
+    assert_eq!(ws.node(&txr, 1), None);
+    assert_eq!(ws.node(&txr, 5), Some(10));
+    assert_eq!(ws.atom(&txr, 5 ^ 20), Some((5, 20, vec![7].into())));
+    assert_eq!(ws.edge(&txr, 5 ^ 30), Some((5, 30, 2)));
+    assert_eq!(ws.edge(&txr, 2 ^ 30), Some((2, 30, 5)));
+    assert_eq!(ws.edge(&txr, 40 ^ 1 ^ 5), Some((5, 40, 5)));
+    assert!(ws.atom_id_label_value_by_src(&txr, 1).is_empty());
+    assert!(ws.edge_id_label_dst_by_src(&txr, 1).is_empty());
+    assert!(ws.edge_id_src_label_by_dst(&txr, 1).is_empty());
+  }
+
+  #[test]
+  fn edge_atom_join_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut rng = rand::thread_rng();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+
+    let manual = |ws: &Workspace, txr: &Transactor, src: u128, value: &[u8]| {
+      let mut res = BTreeMap::new();
+      for (id, dst) in ws.edge_id_dst_by_src_label(txr, src, 1) {
+        if ws.atom_id_value_by_src_label(txr, dst, 2).values().any(|v| **v == *value) {
+          res.insert(id, dst);
+        }
+      }
+      res
+    };
+
+    let src = rng.gen();
+    let mut dsts = Vec::new();
+    for i in 0..20u8 {
+      let dst = rng.gen();
+      ws.set_node(&txr, dst, Some(0));
+      ws.set_atom(&txr, dst ^ 2, Some((dst, 2, vec![i % 3].into())));
+      ws.set_edge(&txr, rng.gen(), Some((if i % 4 == 0 { rng.gen() } else { src }, 1, dst)));
+      dsts.push(dst);
+    }
+    ws.set_node(&txr, src, Some(0));
+    ws.barrier(&mut txr);
+    let res = ws.edge_id_dst_by_src_label_atom(&txr, src, 1, 2, &[0]);
+    assert!(!res.is_empty());
+    assert_eq!(res, manual(&ws, &txr, src, &[0]));
+
+    // Pending modifications are taken into account.
+    ws.set_atom(&txr, dsts[1] ^ 2, Some((dsts[1], 2, vec![0].into())));
+    ws.set_atom(&txr, dsts[3] ^ 2, None);
+    let res = ws.edge_id_dst_by_src_label_atom(&txr, src, 1, 2, &[0]);
+    assert!(res.values().any(|dst| *dst == dsts[1]));
+    assert_eq!(res, manual(&ws, &txr, src, &[0]));
+    ws.barrier(&mut txr);
+    assert_eq!(ws.edge_id_dst_by_src_label_atom(&txr, src, 1, 2, &[0]), res);
+  }
+
+  #[test]
+  fn compact_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut rng = rand::thread_rng();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+
+    let node0 = rng.gen();
+    let node1 = rng.gen();
+    ws.set_node(&txr, node0, Some(0));
+    ws.set_node(&txr, node1, Some(0));
+    // Field with label 1 is set at the canonical ID, then under other IDs.
+    ws.set_atom(&txr, node0 ^ 1, Some((node0, 1, vec![0].into())));
+    for i in 1..5 {
+      ws.set_atom(&txr, rng.gen(), Some((node0, 1, vec![i].into())));
+    }
+    // Field with label 2 is only set under other IDs.
+    for i in 0..5 {
+      ws.set_atom(&txr, rng.gen(), Some((node0, 2, vec![i].into())));
+      ws.set_edge(&txr, rng.gen(), Some((node0, 2, if i % 2 == 0 { node0 } else { node1 })));
+    }
+    // Label 3 is not compacted.
+    ws.set_edge(&txr, rng.gen(), Some((node0, 3, node1)));
+    ws.set_edge(&txr, rng.gen(), Some((node0, 3, node1)));
+    ws.barrier(&mut txr);
+
+    assert_eq!(ws.compact(&txr, node0, &[1, 2]), 4 + 5 + 5);
+    ws.barrier(&mut txr);
+    let atoms = ws.atom_id_label_value_by_src(&txr, node0);
+    assert_eq!(atoms.len(), 2);
+    assert_eq!(atoms[&(node0 ^ 1)], (1, vec![0].into()));
+    assert_eq!(atoms[&(node0 ^ 2)], (2, vec![4].into()));
+    assert_eq!(ws.edge_id_dst_by_src_label(&txr, node0, 2), BTreeMap::from([(node0 ^ 2, node0)]));
+    assert_eq!(ws.edge_id_dst_by_src_label(&txr, node0, 3).len(), 2);
+    assert_eq!(ws.compact(&txr, node0, &[1, 2]), 0);
+  }
+
+  #[test]
+  fn causal_conflict_simple() {
+    let mut rng = rand::thread_rng();
+    let mut constraints = Constraints::new();
+    constraints.add_causal_atom(1);
+    let mut txr0: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("", constraints.clone(), &mut txr0);
+    let mut txr1: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws1 = Workspace::new("", constraints, &mut txr1);
+
+    let node = rng.gen();
+    let atom = rng.gen();
+    ws0.set_node(&txr0, node, Some(0));
+    ws0.set_atom(&txr0, atom, Some((node, 1, vec![0].into())));
+    ws0.barrier(&mut txr0);
+    let actions = ws0.sync_actions(&txr0, &ws1.sync_version(&txr1));
+    ws1.sync_join(&txr1, &actions);
+    ws1.barrier(&mut txr1);
+    assert!(ws1.take_conflicts().is_empty());
+
+    // Concurrent modifications.
+    ws0.set_atom(&txr0, atom, Some((node, 1, vec![1].into())));
+    ws0.barrier(&mut txr0);
+    ws1.set_atom(&txr1, atom, Some((node, 1, vec![2].into())));
+    ws1.barrier(&mut txr1);
+    let actions = ws1.sync_actions(&txr1, &ws0.sync_version(&txr0));
+    ws0.sync_join(&txr0, &actions);
+    ws0.barrier(&mut txr0);
+    let conflicts = ws0.take_conflicts();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].id, atom);
+    assert_eq!(conflicts[0].local, Some((node, 1, vec![1].into())));
+    assert_eq!(conflicts[0].remote, Some((node, 1, vec![2].into())));
+    assert!(ws0.take_conflicts().is_empty());
+    assert_eq!(ws0.conflict_metrics(), BTreeMap::from([(1, 1)]));
+    assert!(ws1.conflict_metrics().is_empty());
+
+    // Both replicas converge, and later modifications are causally ordered.
+    let actions = ws0.sync_actions(&txr0, &ws1.sync_version(&txr1));
+    ws1.sync_join(&txr1, &actions);
+    ws1.barrier(&mut txr1);
+    assert!(ws1.take_conflicts().is_empty());
+    assert_eq!(ws0.atom(&txr0, atom), ws1.atom(&txr1, atom));
+    ws1.set_atom(&txr1, atom, Some((node, 1, vec![3].into())));
+    ws1.barrier(&mut txr1);
+    let actions = ws1.sync_actions(&txr1, &ws0.sync_version(&txr0));
+    ws0.sync_join(&txr0, &actions);
+    ws0.barrier(&mut txr0);
+    assert!(ws0.take_conflicts().is_empty());
+    assert_eq!(ws0.atom(&txr0, atom), Some((node, 1, vec![3].into())));
+  }
+
+  #[test]
+  fn causal_conflict_three_replicas() {
+    fn sync(src: &Workspace, src_txr: &Transactor, dst: &mut Workspace, dst_txr: &mut Transactor) {
+      let actions = src.sync_actions(src_txr, &dst.sync_version(dst_txr));
+      dst.sync_join(dst_txr, &actions);
+      dst.barrier(dst_txr);
+    }
+
+    let mut constraints = Constraints::new();
+    constraints.add_causal_atom(1);
+    let mut txr0: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("", constraints.clone(), &mut txr0);
+    let mut txr1: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws1 = Workspace::new("", constraints.clone(), &mut txr1);
+    let mut txr2: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws2 = Workspace::new("", constraints, &mut txr2);
+
+    let node = rand::thread_rng().gen();
+    ws0.set_node(&txr0, node, Some(0));
+    ws0.set_atom(&txr0, node ^ 1, Some((node, 1, vec![0].into())));
+    ws0.barrier(&mut txr0);
+    sync(&ws0, &txr0, &mut ws1, &mut txr1);
+
+    // Replica 2 sees the local value of replica 0 before the later (and
+    // hence winning) concurrent value of replica 1 arrives there.
+    ws0.set_atom(&txr0, node ^ 1, Some((node, 1, vec![1].into())));
+    ws0.barrier(&mut txr0);
+    sync(&ws0, &txr0, &mut ws2, &mut txr2);
+    ws1.set_atom(&txr1, node ^ 1, Some((node, 1, vec![2].into())));
+    ws1.barrier(&mut txr1);
+    let local = ws0.atom_version(&txr0, node ^ 1, Some(1));
+    let remote = ws1.atom_version(&txr1, node ^ 1, Some(1));
+    sync(&ws1, &txr1, &mut ws0, &mut txr0);
+    assert_eq!(ws0.take_conflicts().len(), 1);
+    assert_eq!(ws0.atom(&txr0, node ^ 1), Some((node, 1, vec![2].into())));
+    assert!(dominates(&ws0.atom_version(&txr0, node ^ 1, Some(1)), &pointwise_max(&local, &remote)));
+
+    // The conflict has been resolved by replica 0, so replica 2 takes the
+    // result without reporting it again.
+    sync(&ws0, &txr0, &mut ws2, &mut txr2);
+    assert!(ws2.take_conflicts().is_empty());
+    assert_eq!(ws2.atom(&txr2, node ^ 1), Some((node, 1, vec![2].into())));
+
+    // Later modifications on replica 2 are causally ordered everywhere.
+    ws2.set_atom(&txr2, node ^ 1, Some((node, 1, vec![3].into())));
+    ws2.barrier(&mut txr2);
+    sync(&ws2, &txr2, &mut ws0, &mut txr0);
+    sync(&ws2, &txr2, &mut ws1, &mut txr1);
+    assert!(ws0.take_conflicts().is_empty());
+    assert!(ws1.take_conflicts().is_empty());
+    assert_eq!(ws0.atom(&txr0, node ^ 1), Some((node, 1, vec![3].into())));
+    assert_eq!(ws1.atom(&txr1, node ^ 1), Some((node, 1, vec![3].into())));
+  }
+
+  #[test]
+  fn causal_merge_counter() {
+    // A grow-only counter, storing the number of increments from each replica.
+    fn merge(lhs: &[u8], rhs: &[u8]) -> Box<[u8]> {
+      let lhs: BTreeMap<u64, u64> = deserialize(lhs).unwrap();
+      let rhs: BTreeMap<u64, u64> = deserialize(rhs).unwrap();
+      serialize(&pointwise_max(&lhs, &rhs)).unwrap().into()
+    }
+    fn increment(ws: &mut Workspace, txr: &mut Transactor, node: u128, replica: u64) {
+      let (_, _, value) = ws.atom(txr, node ^ 1).unwrap();
+      let mut counter: BTreeMap<u64, u64> = deserialize(&value).unwrap();
+      *counter.entry(replica).or_default() += 1;
+      ws.set_atom(txr, node ^ 1, Some((node, 1, serialize(&counter).unwrap().into())));
+      ws.barrier(txr);
+    }
+    fn total(ws: &Workspace, txr: &Transactor, node: u128) -> u64 {
+      let (_, _, value) = ws.atom(txr, node ^ 1).unwrap();
+      deserialize::<BTreeMap<u64, u64>>(&value).unwrap().values().sum()
+    }
+    fn sync(src: &Workspace, src_txr: &Transactor, dst: &mut Workspace, dst_txr: &mut Transactor) {
+      let actions = src.sync_actions(src_txr, &dst.sync_version(dst_txr));
+      dst.sync_join(dst_txr, &actions);
+      dst.barrier(dst_txr);
+    }
+
+    let mut constraints = Constraints::new();
+    constraints.add_causal_atom_merger(1, merge);
+    let mut txr0: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("", constraints.clone(), &mut txr0);
+    let mut txr1: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws1 = Workspace::new("", constraints, &mut txr1);
+
+    let node = rand::thread_rng().gen();
+    ws0.set_node(&txr0, node, Some(0));
+    ws0.set_atom(&txr0, node ^ 1, Some((node, 1, serialize(&BTreeMap::<u64, u64>::new()).unwrap().into())));
+    ws0.barrier(&mut txr0);
+    sync(&ws0, &txr0, &mut ws1, &mut txr1);
+
+    // Concurrent increments.
+    increment(&mut ws0, &mut txr0, node, 0);
+    increment(&mut ws1, &mut txr1, node, 1);
+    sync(&ws1, &txr1, &mut ws0, &mut txr0);
+    sync(&ws0, &txr0, &mut ws1, &mut txr1);
+    assert_eq!(total(&ws0, &txr0, node), 2);
+    assert_eq!(total(&ws1, &txr1, node), 2);
+    assert_eq!(ws0.take_conflicts().len(), 1);
+    assert!(ws1.take_conflicts().is_empty());
+
+    // Merging again (in both directions at once) is stable.
+    increment(&mut ws0, &mut txr0, node, 0);
+    increment(&mut ws1, &mut txr1, node, 1);
+    let actions0 = ws0.sync_actions(&txr0, &ws1.sync_version(&txr1));
+    let actions1 = ws1.sync_actions(&txr1, &ws0.sync_version(&txr0));
+    ws0.sync_join(&txr0, &actions1);
+    ws0.barrier(&mut txr0);
+    ws1.sync_join(&txr1, &actions0);
+    ws1.barrier(&mut txr1);
+    for _ in 0..2 {
+      sync(&ws1, &txr1, &mut ws0, &mut txr0);
+      sync(&ws0, &txr0, &mut ws1, &mut txr1);
+    }
+    assert_eq!(total(&ws0, &txr0, node), 4);
+    assert_eq!(total(&ws1, &txr1, node), 4);
+    assert_eq!(ws0.atom(&txr0, node ^ 1), ws1.atom(&txr1, node ^ 1));
+  }
+
+  #[cfg(feature = "tracing")]
+  #[test]
+  fn tracing_simple() {
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+
+    // Records the fields of all events.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<BTreeMap<String, String>>>>);
+    struct Fields<'a>(&'a mut BTreeMap<String, String>);
+
+    impl Visit for Fields<'_> {
+      fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_owned(), format!("{value:?}"));
+      }
+      fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.to_owned());
+      }
+    }
+
+    impl tracing::Subscriber for Recorder {
+      fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+        true
+      }
+      fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        tracing::span::Id::from_u64(1)
+      }
+      fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+      fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+      fn event(&self, event: &tracing::Event<'_>) {
+        let mut fields = BTreeMap::new();
+        event.record(&mut Fields(&mut fields));
+        self.0.lock().unwrap().push(fields);
+      }
+      fn enter(&self, _: &tracing::span::Id) {}
+      fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut txr0: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("test", Constraints::new(), &mut txr0);
+    let mut txr1: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws1 = Workspace::new("test", Constraints::new(), &mut txr1);
+
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+      let node0 = rng.gen();
+      let node1 = rng.gen();
+      ws0.set_node(&txr0, node0, Some(0));
+      ws0.set_node(&txr0, node1, Some(0));
+      ws0.set_atom(&txr0, rng.gen(), Some((node0, 0, [1].into())));
+      ws0.set_edge(&txr0, rng.gen(), Some((node0, 0, node1)));
+      ws0.barrier(&mut txr0);
+      let actions = ws0.sync_actions(&txr0, &ws1.sync_version(&txr1));
+      ws1.sync_join(&txr1, &actions);
+      ws1.barrier(&mut txr1);
+    });
+
+    let events = recorder.0.lock().unwrap().clone();
+    let fields = |message: &str, nodes: &str, atoms: &str, edges: &str| {
+      BTreeMap::from([
+        ("message".to_owned(), message.to_owned()),
+        ("prefix".to_owned(), "test".to_owned()),
+        ("nodes".to_owned(), nodes.to_owned()),
+        ("atoms".to_owned(), atoms.to_owned()),
+        ("edges".to_owned(), edges.to_owned()),
+      ])
+    };
+    assert_eq!(events, [fields("apply", "2", "1", "1"), fields("join", "2", "1", "1"), fields("apply", "2", "1", "1")]);
+  }
+
   #[test]
   fn acyclic_simple() {
     let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
@@ -652,4 +2338,61 @@ mod tests {
     assert!(ws.edge(&txr, edge1).is_none());
     assert!(ws.edge(&txr, edge2).is_none());
   }
+
+  #[test]
+  fn node_id_by_label_for_each_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    for id in 1..=100 {
+      ws.set_node(&txr, id, Some(1));
+    }
+    ws.barrier(&mut txr);
+    ws.set_node(&txr, 101, Some(1));
+    ws.set_node(&txr, 1, Some(2));
+
+    let mut all = BTreeSet::new();
+    let res = ws.node_id_by_label_for_each(&txr, 1, |id| {
+      all.insert(id);
+      ControlFlow::Continue(())
+    });
+    assert_eq!(res, ControlFlow::Continue(()));
+    assert_eq!(all, (2..=101).collect());
+
+    let mut visited = 0;
+    let res = ws.node_id_by_label_for_each(&txr, 1, |_| {
+      visited += 1;
+      if visited == 10 {
+        ControlFlow::Break(())
+      } else {
+        ControlFlow::Continue(())
+      }
+    });
+    assert_eq!((res, visited), (ControlFlow::Break(()), 10));
+  }
+
+  #[test]
+  fn node_count_by_label_simple() {
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    for id in 1..=30 {
+      ws.set_node(&txr, id, Some(id as u64 % 3 + 1));
+    }
+    ws.set_node(&txr, 31, None);
+    ws.barrier(&mut txr);
+    assert_eq!(ws.node_count_by_label(&txr), BTreeMap::from([(1, 10), (2, 10), (3, 10)]));
+
+    // Pending modifications are counted too.
+    for id in 1..=30 {
+      if id % 3 == 0 {
+        ws.set_node(&txr, id, None);
+      }
+    }
+    ws.set_node(&txr, 1, Some(4));
+    assert_eq!(ws.node_count_by_label(&txr), BTreeMap::from([(2, 9), (3, 10), (4, 1)]));
+    ws.barrier(&mut txr);
+    for label in 1..=4 {
+      let count = ws.node_id_by_label(&txr, label).len() as u64;
+      assert_eq!(ws.node_count_by_label(&txr).get(&label).copied().unwrap_or(0), count);
+    }
+  }
 }
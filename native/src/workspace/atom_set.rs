@@ -12,16 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use rusqlite::{OptionalExtension, Result, Row};
-use std::collections::{btree_map::Entry, BTreeMap};
+use rusqlite::{params_from_iter, OptionalExtension, Result, Row};
+use std::collections::{btree_map::Entry, BTreeMap, HashSet};
 
-use super::metadata::{StructureMetadata, StructureMetadataTransactor};
+use super::metadata::{ItemStatements, StructureMetadata, StructureMetadataTransactor, IN_BATCH_SIZE};
 use crate::Transactor;
 
 /// A last-writer-wins element set for storing atomic data.
 #[derive(Debug)]
 pub struct AtomSet {
   metadata: StructureMetadata,
+  sql: ItemStatements,
   mods: BTreeMap<u128, (Option<Item>, Item)>,
 }
 
@@ -35,13 +36,29 @@ fn item_lt(lhs: &Item, rhs: &Item) -> bool {
 /// Database interface for [`AtomSet`].
 pub trait AtomSetTransactor: StructureMetadataTransactor {
   fn init(&mut self, prefix: &str, name: &str);
-  fn get(&self, prefix: &str, name: &str, id: u128) -> Option<Item>;
-  fn set(&mut self, prefix: &str, name: &str, id: u128, item: Item);
+  fn item_statements(&self, prefix: &str, name: &str) -> ItemStatements;
+  fn get(&self, sql: &ItemStatements, id: u128) -> Option<Item>;
+  fn set(&mut self, sql: &ItemStatements, id: u128, item: Item);
+  fn clear(&mut self, prefix: &str, name: &str);
   fn id_label_value_by_src(&self, prefix: &str, name: &str, src: u128) -> BTreeMap<u128, (u64, Box<[u8]>)>;
   fn id_value_by_src_label(&self, prefix: &str, name: &str, src: u128, label: u64) -> BTreeMap<u128, Box<[u8]>>;
+  fn id_src_label_value_by_srcs(
+    &self,
+    prefix: &str,
+    name: &str,
+    srcs: &[u128],
+  ) -> BTreeMap<u128, (u128, u64, Box<[u8]>)>;
   fn id_src_value_by_label(&self, prefix: &str, name: &str, label: u64) -> BTreeMap<u128, (u128, Box<[u8]>)>;
   fn id_src_by_label_value(&self, prefix: &str, name: &str, label: u64, value: &[u8]) -> BTreeMap<u128, u128>;
   fn by_bucket_clock_range(&self, prefix: &str, name: &str, bucket: u64, lower: Option<u64>) -> BTreeMap<u128, Item>;
+  fn by_bucket_clock_batch(
+    &self,
+    prefix: &str,
+    name: &str,
+    bucket: u64,
+    lower: Option<u64>,
+    limit: u64,
+  ) -> Vec<(u128, Item)>;
 }
 
 impl AtomSet {
@@ -50,7 +67,8 @@ impl AtomSet {
     let metadata = StructureMetadata::new(prefix, name, txr);
     let mods = BTreeMap::new();
     txr.init(prefix, name);
-    Self { metadata, mods }
+    let sql = txr.item_statements(prefix, name);
+    Self { metadata, sql, mods }
   }
 
   /// Returns the name of the workspace.
@@ -73,6 +91,11 @@ impl AtomSet {
     self.metadata.next()
   }
 
+  /// Returns if there are no pending modifications.
+  pub fn is_saved(&self) -> bool {
+    self.mods.is_empty()
+  }
+
   /// Returns pending modifications.
   pub fn mods(&self) -> Vec<(u128, Option<(u128, u64, Box<[u8]>)>, Option<(u128, u64, Box<[u8]>)>)> {
     let mut res = Vec::new();
@@ -83,7 +106,7 @@ impl AtomSet {
   }
 
   pub fn get(&self, txr: &impl AtomSetTransactor, id: u128) -> Option<Item> {
-    self.mods.get(&id).map_or_else(|| txr.get(self.prefix(), self.name(), id), |(_, curr)| Some(curr.clone()))
+    self.mods.get(&id).map_or_else(|| txr.get(&self.sql, id), |(_, curr)| Some(curr.clone()))
   }
 
   pub fn id_label_value_by_src(&self, txr: &impl AtomSetTransactor, src: u128) -> BTreeMap<u128, (u64, Box<[u8]>)> {
@@ -97,6 +120,27 @@ impl AtomSet {
     res
   }
 
+  /// Same as calling `id_label_value_by_src` on each of `srcs`, but querying in
+  /// batches of [`IN_BATCH_SIZE`].
+  pub fn id_src_label_value_by_srcs(
+    &self,
+    txr: &impl AtomSetTransactor,
+    srcs: &[u128],
+  ) -> BTreeMap<u128, (u128, u64, Box<[u8]>)> {
+    let mut res = BTreeMap::new();
+    for chunk in srcs.chunks(IN_BATCH_SIZE) {
+      res.extend(txr.id_src_label_value_by_srcs(self.prefix(), self.name(), chunk));
+    }
+    let srcs: HashSet<u128> = srcs.iter().copied().collect();
+    for (id, (_, (_, _, slv))) in &self.mods {
+      match slv {
+        Some((src, label, value)) if srcs.contains(src) => res.insert(*id, (*src, *label, value.clone())),
+        _ => res.remove(id),
+      };
+    }
+    res
+  }
+
   pub fn id_value_by_src_label(
     &self,
     txr: &impl AtomSetTransactor,
@@ -156,6 +200,18 @@ impl AtomSet {
     res
   }
 
+  /// Returns up to `limit` saved actions of one bucket strictly later than
+  /// given clock value, in increasing order of clock values.
+  pub fn actions_batch(
+    &self,
+    txr: &impl AtomSetTransactor,
+    bucket: u64,
+    lower: Option<u64>,
+    limit: u64,
+  ) -> Vec<(u128, Item)> {
+    txr.by_bucket_clock_batch(self.prefix(), self.name(), bucket, lower, limit)
+  }
+
   /// Modifies item.
   pub fn set(
     &mut self,
@@ -169,7 +225,7 @@ impl AtomSet {
       let item = (bucket, clock, slv);
       match self.mods.entry(id) {
         Entry::Vacant(entry) => {
-          let prev = txr.get(self.metadata.prefix(), self.metadata.name(), id);
+          let prev = txr.get(&self.sql, id);
           if prev.is_none() || item_lt(prev.as_ref().unwrap(), &item) {
             entry.insert((prev, item));
             return true;
@@ -190,12 +246,36 @@ impl AtomSet {
   pub fn save(&mut self, txr: &mut impl AtomSetTransactor) {
     self.metadata.save(txr);
     for (id, (_, curr)) in std::mem::take(&mut self.mods) {
-      txr.set(self.prefix(), self.name(), id, curr);
+      txr.set(&self.sql, id, curr);
     }
   }
+
+  /// Removes all items and clock values, discarding pending modifications.
+  /// No actions are generated.
+  pub fn clear(&mut self, txr: &mut impl AtomSetTransactor) {
+    self.metadata.clear(txr);
+    self.mods.clear();
+    txr.clear(self.prefix(), self.name());
+  }
+
+  /// Prepares all statements by running them on dummy arguments. Writes some
+  /// dummy data, which should be rolled back by the caller.
+  pub fn warm_up(&self, txr: &mut impl AtomSetTransactor) {
+    let (prefix, name) = (self.prefix(), self.name());
+    txr.get(&self.sql, 0);
+    txr.id_label_value_by_src(prefix, name, 0);
+    txr.id_value_by_src_label(prefix, name, 0, 0);
+    txr.id_src_value_by_label(prefix, name, 0);
+    txr.id_src_by_label_value(prefix, name, 0, &[]);
+    txr.by_bucket_clock_range(prefix, name, 0, None);
+    txr.by_bucket_clock_batch(prefix, name, 0, None, 0);
+    txr.get_buckets(prefix, name);
+    txr.set(&self.sql, 0, (0, 0, None));
+    txr.set_bucket(prefix, name, 0, 0);
+  }
 }
 
-fn read_row(row: &Row<'_>) -> (u128, Item) {
+pub(super) fn read_row(row: &Row<'_>) -> (u128, Item) {
   let id = row.get(0).unwrap();
   let bucket = row.get(1).unwrap();
   let clock = row.get(2).unwrap();
@@ -219,6 +299,14 @@ fn read_row_id_label_value(row: &Row<'_>) -> (u128, (u64, Box<[u8]>)) {
   (u128::from_be_bytes(id), (u64::from_be_bytes(label), value.into()))
 }
 
+fn read_row_id_src_label_value(row: &Row<'_>) -> (u128, (u128, u64, Box<[u8]>)) {
+  let id = row.get(0).unwrap();
+  let src = row.get(1).unwrap();
+  let label = row.get(2).unwrap();
+  let value: Vec<u8> = row.get(3).unwrap();
+  (u128::from_be_bytes(id), (u128::from_be_bytes(src), u64::from_be_bytes(label), value.into()))
+}
+
 fn read_row_id_value(row: &Row<'_>) -> (u128, Box<[u8]>) {
   let id = row.get(0).unwrap();
   let value: Vec<u8> = row.get(1).unwrap();
@@ -273,12 +361,16 @@ impl AtomSetTransactor for Transactor {
       .unwrap();
   }
 
-  fn get(&self, prefix: &str, name: &str, id: u128) -> Option<Item> {
+  fn item_statements(&self, prefix: &str, name: &str) -> ItemStatements {
+    ItemStatements {
+      get: format!("SELECT id, bucket, clock, src, label, value FROM \"{prefix}.{name}.data\" WHERE id = ?"),
+      set: format!("REPLACE INTO \"{prefix}.{name}.data\" VALUES (?, ?, ?, ?, ?, ?)"),
+    }
+  }
+
+  fn get(&self, sql: &ItemStatements, id: u128) -> Option<Item> {
     self
-      .prepare_cached(&format!(
-        "SELECT id, bucket, clock, src, label, value FROM \"{prefix}.{name}.data\"
-        WHERE id = ?"
-      ))
+      .prepare_cached(&sql.get)
       .unwrap()
       .query_row((id.to_be_bytes(),), |row| Ok(read_row(row)))
       .optional()
@@ -286,12 +378,12 @@ impl AtomSetTransactor for Transactor {
       .map(|(_, item)| item)
   }
 
-  fn set(&mut self, prefix: &str, name: &str, id: u128, item: Item) {
-    self
-      .prepare_cached(&format!("REPLACE INTO \"{prefix}.{name}.data\" VALUES (?, ?, ?, ?, ?, ?)"))
-      .unwrap()
-      .execute(make_row(id, item))
-      .unwrap();
+  fn set(&mut self, sql: &ItemStatements, id: u128, item: Item) {
+    self.prepare_cached(&sql.set).unwrap().execute(make_row(id, item)).unwrap();
+  }
+
+  fn clear(&mut self, prefix: &str, name: &str) {
+    self.execute_batch(&format!("DELETE FROM \"{prefix}.{name}.data\"")).unwrap();
   }
 
   fn id_label_value_by_src(&self, prefix: &str, name: &str, src: u128) -> BTreeMap<u128, (u64, Box<[u8]>)> {
@@ -307,6 +399,25 @@ impl AtomSetTransactor for Transactor {
       .collect()
   }
 
+  fn id_src_label_value_by_srcs(
+    &self,
+    prefix: &str,
+    name: &str,
+    srcs: &[u128],
+  ) -> BTreeMap<u128, (u128, u64, Box<[u8]>)> {
+    let params = vec!["?"; srcs.len()].join(", ");
+    self
+      .prepare(&format!(
+        "SELECT id, src, label, value FROM \"{prefix}.{name}.data\" INDEXED BY \"{prefix}.{name}.data.idx_src_label\"
+        WHERE src IN ({params})"
+      ))
+      .unwrap()
+      .query_map(params_from_iter(srcs.iter().map(|src| src.to_be_bytes())), |row| Ok(read_row_id_src_label_value(row)))
+      .unwrap()
+      .map(Result::unwrap)
+      .collect()
+  }
+
   fn id_value_by_src_label(&self, prefix: &str, name: &str, src: u128, label: u64) -> BTreeMap<u128, Box<[u8]>> {
     self
       .prepare_cached(&format!(
@@ -350,7 +461,7 @@ impl AtomSetTransactor for Transactor {
     self
       .prepare_cached(&format!(
         "SELECT id, bucket, clock, src, label, value FROM \"{prefix}.{name}.data\" INDEXED BY \"{prefix}.{name}.data.idx_bucket_clock\"
-        WHERE bucket = ? AND clock > ?"
+        WHERE bucket = ?1 AND clock > COALESCE(?2, x'')"
       ))
       .unwrap()
       .query_map((bucket.to_be_bytes(), lower.map(u64::to_be_bytes)), |row| Ok(read_row(row)))
@@ -358,4 +469,24 @@ impl AtomSetTransactor for Transactor {
       .map(Result::unwrap)
       .collect()
   }
+
+  fn by_bucket_clock_batch(
+    &self,
+    prefix: &str,
+    name: &str,
+    bucket: u64,
+    lower: Option<u64>,
+    limit: u64,
+  ) -> Vec<(u128, Item)> {
+    self
+      .prepare_cached(&format!(
+        "SELECT id, bucket, clock, src, label, value FROM \"{prefix}.{name}.data\" INDEXED BY \"{prefix}.{name}.data.idx_bucket_clock\"
+        WHERE bucket = ?1 AND clock > COALESCE(?2, x'') ORDER BY clock ASC LIMIT ?3"
+      ))
+      .unwrap()
+      .query_map((bucket.to_be_bytes(), lower.map(u64::to_be_bytes), limit), |row| Ok(read_row(row)))
+      .unwrap()
+      .map(Result::unwrap)
+      .collect()
+  }
 }
@@ -0,0 +1,151 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Joining data directly from another database file attached to the same
+//! connection (through `ATTACH DATABASE`), e.g. one received from a peer.
+
+use std::collections::BTreeMap;
+
+use rusqlite::Row;
+
+use super::{atom_set, edge_set, node_set, Workspace, ATOMS_NAME, EDGES_NAME, MERGE_BATCH_SIZE, NODES_NAME};
+use crate::{deserialize, StoreError, Transactor};
+
+/// Quotes an SQL identifier, so that names containing `"` cannot escape it.
+pub fn quote(name: &str) -> String {
+  format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+impl Workspace {
+  /// Joins all items of the workspace with the same prefix in the database
+  /// attached as `schema`. This is equivalent to [`Workspace::merge_from`] on
+  /// a workspace opened on that database, but reads the attached tables in
+  /// batches of [`MERGE_BATCH_SIZE`] through this connection instead.
+  ///
+  /// Fails without joining anything if any of the tables is missing, e.g.
+  /// if the attached database was written by an older version.
+  pub fn merge_attached(&mut self, txr: &Transactor, schema: &str) -> Result<(), StoreError> {
+    let prefix = self.nodes.prefix();
+    let table = |name: &str, suffix: &str| format!("{}.{}", quote(schema), quote(&format!("{prefix}.{name}.{suffix}")));
+
+    // Version vectors are only kept for causal atoms.
+    let causal = !self.constraints.causal_atoms.is_empty();
+    let master = format!("{}.sqlite_master", quote(schema));
+    let versions = causal.then_some((ATOMS_NAME, "versions"));
+    for (name, suffix) in [
+      (NODES_NAME, "data"),
+      (NODES_NAME, "buckets"),
+      (ATOMS_NAME, "data"),
+      (ATOMS_NAME, "buckets"),
+      (EDGES_NAME, "data"),
+      (EDGES_NAME, "buckets"),
+    ]
+    .into_iter()
+    .chain(versions)
+    {
+      let name = format!("{prefix}.{name}.{suffix}");
+      let count: i64 = txr.query_row(
+        &format!("SELECT count(*) FROM {master} WHERE type = 'table' AND name = ?"),
+        (&name,),
+        |row| row.get(0),
+      )?;
+      if count == 0 {
+        return Err(StoreError::MissingTable(name));
+      }
+    }
+
+    let nodes = table(NODES_NAME, "data");
+    for bucket in buckets(txr, &table(NODES_NAME, "buckets")) {
+      let mut lower = self.nodes.buckets().get(&bucket).copied();
+      loop {
+        let columns = "id, bucket, clock, label";
+        let batch = batch(txr, columns, &nodes, "", bucket, lower, node_set::read_row);
+        let Some(&(_, (_, clock, _))) = batch.last() else { break };
+        lower = Some(clock);
+        for (id, (bucket, clock, l)) in batch {
+          self.nodes.set(txr, id, bucket, clock, l);
+        }
+      }
+    }
+
+    let atoms = table(ATOMS_NAME, "data");
+    let (version, join) = match causal {
+      true => ("v.version", format!("LEFT JOIN {} AS v ON v.id = d.id", table(ATOMS_NAME, "versions"))),
+      false => ("NULL", String::new()),
+    };
+    for bucket in buckets(txr, &table(ATOMS_NAME, "buckets")) {
+      let mut lower = self.atoms.buckets().get(&bucket).copied();
+      loop {
+        let columns = format!("d.id, d.bucket, d.clock, d.src, d.label, d.value, {version}");
+        let batch = batch(txr, &columns, &atoms, &join, bucket, lower, |row| {
+          let version: Option<Vec<u8>> = row.get(6).unwrap();
+          (atom_set::read_row(row), version.map_or_else(BTreeMap::new, |version| deserialize(&version).unwrap()))
+        });
+        let Some(&((_, (_, clock, _)), _)) = batch.last() else { break };
+        lower = Some(clock);
+        for ((id, (bucket, clock, slv)), remote) in batch {
+          self.join_atom(txr, id, bucket, clock, slv, remote);
+        }
+      }
+    }
+
+    let edges = table(EDGES_NAME, "data");
+    for bucket in buckets(txr, &table(EDGES_NAME, "buckets")) {
+      let mut lower = self.edges.buckets().get(&bucket).copied();
+      loop {
+        let columns = "id, bucket, clock, src, label, dst";
+        let batch = batch(txr, columns, &edges, "", bucket, lower, edge_set::read_row);
+        let Some(&(_, (_, clock, _))) = batch.last() else { break };
+        lower = Some(clock);
+        for (id, (bucket, clock, sld)) in batch {
+          self.edges.set(txr, id, bucket, clock, sld);
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+fn buckets(txr: &Transactor, table: &str) -> Vec<u64> {
+  txr
+    .prepare(&format!("SELECT bucket FROM {table}"))
+    .unwrap()
+    .query_map((), |row| Ok(u64::from_be_bytes(row.get(0).unwrap())))
+    .unwrap()
+    .map(Result::unwrap)
+    .collect()
+}
+
+/// Reads the next [`MERGE_BATCH_SIZE`] rows of `bucket` after clock `lower`
+/// from `table` (aliased as `d`).
+fn batch<T>(
+  txr: &Transactor,
+  columns: &str,
+  table: &str,
+  join: &str,
+  bucket: u64,
+  lower: Option<u64>,
+  read: impl Fn(&Row<'_>) -> T,
+) -> Vec<T> {
+  txr
+    .prepare(&format!(
+      "SELECT {columns} FROM {table} AS d {join}
+      WHERE d.bucket = ?1 AND d.clock > COALESCE(?2, x'') ORDER BY d.clock ASC LIMIT ?3"
+    ))
+    .unwrap()
+    .query_map((bucket.to_be_bytes(), lower.map(u64::to_be_bytes), MERGE_BATCH_SIZE), |row| Ok(read(row)))
+    .unwrap()
+    .map(Result::unwrap)
+    .collect()
+}
@@ -0,0 +1,218 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Large values stored as a base plus a chain of binary deltas, so that small
+//! edits (e.g. keystrokes in a document) write and synchronise only what
+//! changed instead of the whole value.
+//!
+//! A delta-encoded value of `src` uses two labels: the base is the atom at the
+//! canonical ID `src ^ label`, holding `(base, parent, value)` where `base` is
+//! a random ID of this base; each delta is a separate atom from `src` with
+//! `delta_label` and a random ID, holding `(base, seq, delta)` where `delta`
+//! turns version `seq - 1` of the chain on `base` into version `seq`. Since
+//! each delta is its own atom, deltas written on different replicas are all
+//! kept by sync. The value is read by applying the deltas of the current base
+//! in order of `(seq, id)`; deltas with the same `seq` written concurrently on
+//! different replicas are thus both applied, each clamped to the value it
+//! finds (see [`patch`]), so that all replicas still converge.
+//!
+//! Once the chain holds `max_chain` deltas, the next write is a compaction: a
+//! new base holding the current value, with all deltas it folds in removed,
+//! followed by the change as the first delta on it. The new base records its
+//! `parent` base and how many deltas of it were folded, so that deltas
+//! appended to the parent concurrently (which the compaction did not see)
+//! continue the chain on the new base instead of being lost. Deltas on any
+//! other base (e.g. appended concurrently with two compactions in a row, or
+//! with another compaction that lost) are ignored, and removed by the next
+//! compaction.
+
+use super::Workspace;
+use crate::{deserialize, serialize, Transactor};
+
+/// Returns a delta turning `old` into `new`, which replaces everything between
+/// their common prefix and common suffix.
+pub fn diff(old: &[u8], new: &[u8]) -> Box<[u8]> {
+  let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+  let (old_rest, new_rest) = (&old[prefix..], &new[prefix..]);
+  let suffix = old_rest.iter().rev().zip(new_rest.iter().rev()).take_while(|(a, b)| a == b).count();
+  let removed = old_rest.len() - suffix;
+  let inserted = &new_rest[..new_rest.len() - suffix];
+  serialize(&(prefix as u64, removed as u64, inserted)).unwrap().into()
+}
+
+/// Applies a delta returned by [`diff`]. If `old` is not the value the delta
+/// was computed against, the replaced range is clamped to `old`, so that this
+/// always succeeds. Returns `None` only for malformed deltas.
+pub fn patch(old: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+  let (prefix, removed, inserted): (u64, u64, Vec<u8>) = deserialize(delta).ok()?;
+  let start = (prefix as usize).min(old.len());
+  let end = start.saturating_add(removed as usize).min(old.len());
+  let mut res = Vec::with_capacity(old.len() - (end - start) + inserted.len());
+  res.extend_from_slice(&old[..start]);
+  res.extend_from_slice(&inserted);
+  res.extend_from_slice(&old[end..]);
+  Some(res)
+}
+
+/// The current state of a delta-encoded value.
+struct Chain {
+  base: u128,
+  seq: u64,
+  value: Vec<u8>,
+  deltas: Vec<u128>,
+}
+
+impl Workspace {
+  /// Returns the delta-encoded value of `src` (see [`crate::workspace::delta`]).
+  pub fn delta_value(&self, txr: &Transactor, src: u128, label: u64, delta_label: u64) -> Option<Vec<u8>> {
+    self.delta_chain(txr, src, label, delta_label).map(|chain| chain.value)
+  }
+
+  /// Sets the delta-encoded value of `src`, writing a delta against the
+  /// current value, or a new base if there is none yet or the chain already
+  /// holds `max_chain` deltas. Must be followed by a [`Workspace::barrier`].
+  pub fn set_delta_value(
+    &mut self,
+    txr: &Transactor,
+    src: u128,
+    label: u64,
+    delta_label: u64,
+    value: &[u8],
+    max_chain: usize,
+  ) {
+    let canonical = src ^ u128::from(label);
+    let set_base = |ws: &mut Self, parent: Option<(u128, u64)>, value: &[u8]| {
+      let base: u128 = rand::random();
+      ws.set_atom(txr, canonical, Some((src, label, serialize(&(base, parent, value)).unwrap().into())));
+      base
+    };
+    let (base, seq, delta) = match self.delta_chain(txr, src, label, delta_label) {
+      None => {
+        set_base(self, None, value);
+        return;
+      }
+      Some(chain) if chain.value == value => return,
+      Some(chain) if chain.deltas.len() >= max_chain => {
+        let base = set_base(self, Some((chain.base, chain.seq)), &chain.value);
+        for id in chain.deltas {
+          self.set_atom(txr, id, None);
+        }
+        (base, 1, diff(&chain.value, value))
+      }
+      Some(chain) => (chain.base, chain.seq + 1, diff(&chain.value, value)),
+    };
+    let delta = serialize(&(base, seq, delta)).unwrap();
+    self.set_atom(txr, rand::random(), Some((src, delta_label, delta.into())));
+  }
+
+  fn delta_chain(&self, txr: &Transactor, src: u128, label: u64, delta_label: u64) -> Option<Chain> {
+    let base = self.atom(txr, src ^ u128::from(label))?.2;
+    let (base, parent, mut value): (u128, Option<(u128, u64)>, Vec<u8>) = deserialize(&base).ok()?;
+    let mut deltas: Vec<(u64, u128, Vec<u8>)> = Vec::new();
+    let mut ids = Vec::new();
+    for (id, delta) in self.atom_id_value_by_src_label(txr, src, delta_label) {
+      ids.push(id);
+      let Ok((delta_base, seq, delta)) = deserialize::<(u128, u64, Vec<u8>)>(&delta) else { continue };
+      let seq = match parent {
+        _ if delta_base == base => seq,
+        // Appended to the parent after the deltas folded into this base.
+        Some((parent, folded)) if delta_base == parent && seq > folded => seq - folded,
+        _ => continue,
+      };
+      deltas.push((seq, id, delta));
+    }
+    deltas.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
+    let seq = deltas.last().map_or(0, |(seq, _, _)| *seq);
+    for (_, _, delta) in deltas {
+      value = patch(&value, &delta).unwrap_or(value);
+    }
+    Some(Chain { base, seq, value, deltas: ids })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rusqlite::Connection;
+
+  use super::*;
+  use crate::workspace::Constraints;
+
+  #[test]
+  fn delta_value_simple() {
+    assert_eq!(patch(b"hello world", &diff(b"hello world", b"hello, brave world")).unwrap(), b"hello, brave world");
+    assert_eq!(patch(b"abc", &diff(b"abcdef", b"abc")).unwrap(), b"abc");
+
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws = Workspace::new("", Constraints::new(), &mut txr);
+    ws.set_node(&txr, 1, Some(4));
+    let mut text: Vec<u8> = (0..4096).map(|i| b'a' + (i % 26) as u8).collect();
+    ws.set_delta_value(&txr, 1, 2, 3, &text, 16);
+    ws.barrier(&mut txr);
+    for i in 0..100 {
+      text.insert(i * 37 % text.len(), b'!');
+      ws.set_delta_value(&txr, 1, 2, 3, &text, 16);
+      ws.barrier(&mut txr);
+      assert_eq!(ws.delta_value(&txr, 1, 2, 3).unwrap(), text);
+    }
+
+    // At most one base and `max_chain` small deltas are stored.
+    let base = ws.atom(&txr, 1 ^ 2).unwrap().2.len();
+    let deltas = ws.atom_id_value_by_src_label(&txr, 1, 3);
+    assert!(deltas.len() <= 16 && deltas.values().all(|delta| delta.len() < 64));
+    let stored = base + deltas.values().map(|delta| delta.len()).sum::<usize>();
+    assert!(stored < 2 * text.len());
+  }
+
+  #[test]
+  fn delta_value_concurrent_compaction() {
+    fn sync(txr: &mut Transactor, from: &Workspace, to: &mut Workspace) {
+      let actions = from.sync_actions(txr, &to.sync_version(txr));
+      to.sync_join(txr, &actions);
+      to.barrier(txr);
+    }
+
+    let mut txr: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("ws0", Constraints::new(), &mut txr);
+    let mut ws1 = Workspace::new("ws1", Constraints::new(), &mut txr);
+    ws0.set_node(&txr, 1, Some(4));
+    let mut text = b"hello".to_vec();
+    ws0.set_delta_value(&txr, 1, 2, 3, &text, 2);
+    ws0.barrier(&mut txr);
+    for c in *b"12" {
+      text.push(c);
+      ws0.set_delta_value(&txr, 1, 2, 3, &text, 2);
+      ws0.barrier(&mut txr);
+    }
+    sync(&mut txr, &ws0, &mut ws1);
+
+    // `ws0` compacts while `ws1` appends a third delta to the old base.
+    ws0.set_delta_value(&txr, 1, 2, 3, &[&text[..], b"a"].concat(), 2);
+    ws0.barrier(&mut txr);
+    ws1.set_delta_value(&txr, 1, 2, 3, &[&text[..], b"b"].concat(), 16);
+    ws1.barrier(&mut txr);
+    sync(&mut txr, &ws0, &mut ws1);
+    sync(&mut txr, &ws1, &mut ws0);
+    let value = ws0.delta_value(&txr, 1, 2, 3).unwrap();
+    assert_eq!(ws1.delta_value(&txr, 1, 2, 3).unwrap(), value);
+    assert!(value == [&text[..], b"ab"].concat() || value == [&text[..], b"ba"].concat());
+
+    // Compacting again on `ws1` folds in both.
+    text = [&value[..], b"c"].concat();
+    ws1.set_delta_value(&txr, 1, 2, 3, &text, 2);
+    ws1.barrier(&mut txr);
+    sync(&mut txr, &ws1, &mut ws0);
+    assert_eq!(ws0.delta_value(&txr, 1, 2, 3).unwrap(), text);
+    assert_eq!(ws0.atom_id_value_by_src_label(&txr, 1, 3).len(), 1);
+  }
+}
@@ -12,16 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use rusqlite::{OptionalExtension, Result, Row};
-use std::collections::{btree_map::Entry, BTreeMap};
+use rusqlite::{params_from_iter, OptionalExtension, Result, Row};
+use std::collections::{btree_map::Entry, BTreeMap, HashSet};
 
-use super::metadata::{StructureMetadata, StructureMetadataTransactor};
+use super::metadata::{ItemStatements, StructureMetadata, StructureMetadataTransactor, IN_BATCH_SIZE};
 use crate::Transactor;
 
 /// A last-writer-wins element set for storing edges.
 #[derive(Debug)]
 pub struct EdgeSet {
   metadata: StructureMetadata,
+  sql: ItemStatements,
   mods: BTreeMap<u128, (Option<Item>, Item)>,
 }
 
@@ -35,13 +36,36 @@ fn item_lt(lhs: &Item, rhs: &Item) -> bool {
 /// Database interface for [`EdgeSet`].
 pub trait EdgeSetTransactor: StructureMetadataTransactor {
   fn init(&mut self, prefix: &str, name: &str);
-  fn get(&self, prefix: &str, name: &str, id: u128) -> Option<Item>;
-  fn set(&mut self, prefix: &str, name: &str, id: u128, item: Item);
+  fn item_statements(&self, prefix: &str, name: &str) -> ItemStatements;
+  fn get(&self, sql: &ItemStatements, id: u128) -> Option<Item>;
+  fn get_many(&self, prefix: &str, name: &str, ids: &[u128]) -> BTreeMap<u128, Item>;
+  fn set(&mut self, sql: &ItemStatements, id: u128, item: Item);
+  fn clear(&mut self, prefix: &str, name: &str);
   fn id_label_dst_by_src(&self, prefix: &str, name: &str, src: u128) -> BTreeMap<u128, (u64, u128)>;
   fn id_dst_by_src_label(&self, prefix: &str, name: &str, src: u128, label: u64) -> BTreeMap<u128, u128>;
+  fn id_src_label_dst_by_srcs(&self, prefix: &str, name: &str, srcs: &[u128]) -> BTreeMap<u128, (u128, u64, u128)>;
   fn id_src_label_by_dst(&self, prefix: &str, name: &str, dst: u128) -> BTreeMap<u128, (u128, u64)>;
   fn id_src_by_dst_label(&self, prefix: &str, name: &str, dst: u128, label: u64) -> BTreeMap<u128, u128>;
+  #[allow(clippy::too_many_arguments)]
+  fn id_dst_by_src_label_atom(
+    &self,
+    prefix: &str,
+    name: &str,
+    atoms_name: &str,
+    src: u128,
+    label: u64,
+    atom_label: u64,
+    value: &[u8],
+  ) -> BTreeMap<u128, u128>;
   fn by_bucket_clock_range(&self, prefix: &str, name: &str, bucket: u64, lower: Option<u64>) -> Vec<(u128, Item)>;
+  fn by_bucket_clock_batch(
+    &self,
+    prefix: &str,
+    name: &str,
+    bucket: u64,
+    lower: Option<u64>,
+    limit: u64,
+  ) -> Vec<(u128, Item)>;
 }
 
 impl EdgeSet {
@@ -50,7 +74,8 @@ impl EdgeSet {
     let metadata = StructureMetadata::new(prefix, name, txr);
     let mods = BTreeMap::new();
     txr.init(prefix, name);
-    Self { metadata, mods }
+    let sql = txr.item_statements(prefix, name);
+    Self { metadata, sql, mods }
   }
 
   /// Returns the name of the workspace.
@@ -73,6 +98,11 @@ impl EdgeSet {
     self.metadata.next()
   }
 
+  /// Returns if there are no pending modifications.
+  pub fn is_saved(&self) -> bool {
+    self.mods.is_empty()
+  }
+
   /// Returns pending modifications.
   pub fn mods(&self) -> Vec<(u128, Option<(u128, u64, u128)>, Option<(u128, u64, u128)>)> {
     let mut res = Vec::new();
@@ -83,7 +113,27 @@ impl EdgeSet {
   }
 
   pub fn get(&self, txr: &impl EdgeSetTransactor, id: u128) -> Option<Item> {
-    self.mods.get(&id).map_or_else(|| txr.get(self.prefix(), self.name(), id), |(_, curr)| Some(*curr))
+    self.mods.get(&id).map_or_else(|| txr.get(&self.sql, id), |(_, curr)| Some(*curr))
+  }
+
+  /// Same as calling `get` on each of `ids`, but querying the ones without
+  /// pending modifications in batches of [`IN_BATCH_SIZE`].
+  pub fn get_many(&self, txr: &impl EdgeSetTransactor, ids: &[u128]) -> BTreeMap<u128, Item> {
+    let mut res = BTreeMap::new();
+    let mut missing = Vec::new();
+    for &id in ids {
+      if let Some((_, curr)) = self.mods.get(&id) {
+        res.insert(id, *curr);
+      } else {
+        missing.push(id);
+      }
+    }
+    missing.sort_unstable();
+    missing.dedup();
+    for chunk in missing.chunks(IN_BATCH_SIZE) {
+      res.extend(txr.get_many(self.prefix(), self.name(), chunk));
+    }
+    res
   }
 
   pub fn id_label_dst_by_src(&self, txr: &impl EdgeSetTransactor, src: u128) -> BTreeMap<u128, (u64, u128)> {
@@ -97,6 +147,27 @@ impl EdgeSet {
     res
   }
 
+  /// Same as calling `id_label_dst_by_src` on each of `srcs`, but querying in
+  /// batches of [`IN_BATCH_SIZE`].
+  pub fn id_src_label_dst_by_srcs(
+    &self,
+    txr: &impl EdgeSetTransactor,
+    srcs: &[u128],
+  ) -> BTreeMap<u128, (u128, u64, u128)> {
+    let mut res = BTreeMap::new();
+    for chunk in srcs.chunks(IN_BATCH_SIZE) {
+      res.extend(txr.id_src_label_dst_by_srcs(self.prefix(), self.name(), chunk));
+    }
+    let srcs: HashSet<u128> = srcs.iter().copied().collect();
+    for (id, (_, (_, _, sld))) in &self.mods {
+      match sld {
+        Some(sld) if srcs.contains(&sld.0) => res.insert(*id, *sld),
+        _ => res.remove(id),
+      };
+    }
+    res
+  }
+
   pub fn id_dst_by_src_label(&self, txr: &impl EdgeSetTransactor, src: u128, label: u64) -> BTreeMap<u128, u128> {
     let mut res = txr.id_dst_by_src_label(self.prefix(), self.name(), src, label);
     for (id, (_, (_, _, sld))) in &self.mods {
@@ -151,6 +222,18 @@ impl EdgeSet {
     res
   }
 
+  /// Returns up to `limit` saved actions of one bucket strictly later than
+  /// given clock value, in increasing order of clock values.
+  pub fn actions_batch(
+    &self,
+    txr: &impl EdgeSetTransactor,
+    bucket: u64,
+    lower: Option<u64>,
+    limit: u64,
+  ) -> Vec<(u128, Item)> {
+    txr.by_bucket_clock_batch(self.prefix(), self.name(), bucket, lower, limit)
+  }
+
   /// Modifies item.
   pub fn set(
     &mut self,
@@ -164,7 +247,7 @@ impl EdgeSet {
       let item = (bucket, clock, sld);
       match self.mods.entry(id) {
         Entry::Vacant(entry) => {
-          let prev = txr.get(self.metadata.prefix(), self.metadata.name(), id);
+          let prev = txr.get(&self.sql, id);
           if prev.is_none() || item_lt(prev.as_ref().unwrap(), &item) {
             entry.insert((prev, item));
             return true;
@@ -185,12 +268,36 @@ impl EdgeSet {
   pub fn save(&mut self, txr: &mut impl EdgeSetTransactor) {
     self.metadata.save(txr);
     for (id, (_, curr)) in std::mem::take(&mut self.mods) {
-      txr.set(self.prefix(), self.name(), id, curr);
+      txr.set(&self.sql, id, curr);
     }
   }
+
+  /// Removes all items and clock values, discarding pending modifications.
+  /// No actions are generated.
+  pub fn clear(&mut self, txr: &mut impl EdgeSetTransactor) {
+    self.metadata.clear(txr);
+    self.mods.clear();
+    txr.clear(self.prefix(), self.name());
+  }
+
+  /// Prepares all statements by running them on dummy arguments. Writes some
+  /// dummy data, which should be rolled back by the caller.
+  pub fn warm_up(&self, txr: &mut impl EdgeSetTransactor) {
+    let (prefix, name) = (self.prefix(), self.name());
+    txr.get(&self.sql, 0);
+    txr.id_label_dst_by_src(prefix, name, 0);
+    txr.id_dst_by_src_label(prefix, name, 0, 0);
+    txr.id_src_label_by_dst(prefix, name, 0);
+    txr.id_src_by_dst_label(prefix, name, 0, 0);
+    txr.by_bucket_clock_range(prefix, name, 0, None);
+    txr.by_bucket_clock_batch(prefix, name, 0, None, 0);
+    txr.get_buckets(prefix, name);
+    txr.set(&self.sql, 0, (0, 0, None));
+    txr.set_bucket(prefix, name, 0, 0);
+  }
 }
 
-fn read_row(row: &Row<'_>) -> (u128, Item) {
+pub(super) fn read_row(row: &Row<'_>) -> (u128, Item) {
   let id = row.get(0).unwrap();
   let bucket = row.get(1).unwrap();
   let clock = row.get(2).unwrap();
@@ -214,6 +321,14 @@ fn read_row_id_label_dst(row: &Row<'_>) -> (u128, (u64, u128)) {
   (u128::from_be_bytes(id), (u64::from_be_bytes(label), u128::from_be_bytes(dst)))
 }
 
+fn read_row_id_src_label_dst(row: &Row<'_>) -> (u128, (u128, u64, u128)) {
+  let id = row.get(0).unwrap();
+  let src = row.get(1).unwrap();
+  let label = row.get(2).unwrap();
+  let dst = row.get(3).unwrap();
+  (u128::from_be_bytes(id), (u128::from_be_bytes(src), u64::from_be_bytes(label), u128::from_be_bytes(dst)))
+}
+
 fn read_row_id_dst(row: &Row<'_>) -> (u128, u128) {
   let id = row.get(0).unwrap();
   let dst = row.get(1).unwrap();
@@ -265,12 +380,16 @@ impl EdgeSetTransactor for Transactor {
       .unwrap();
   }
 
-  fn get(&self, prefix: &str, name: &str, id: u128) -> Option<Item> {
+  fn item_statements(&self, prefix: &str, name: &str) -> ItemStatements {
+    ItemStatements {
+      get: format!("SELECT id, bucket, clock, src, label, dst FROM \"{prefix}.{name}.data\" WHERE id = ?"),
+      set: format!("REPLACE INTO \"{prefix}.{name}.data\" VALUES (?, ?, ?, ?, ?, ?)"),
+    }
+  }
+
+  fn get(&self, sql: &ItemStatements, id: u128) -> Option<Item> {
     self
-      .prepare_cached(&format!(
-        "SELECT id, bucket, clock, src, label, dst FROM \"{prefix}.{name}.data\"
-        WHERE id = ?"
-      ))
+      .prepare_cached(&sql.get)
       .unwrap()
       .query_row((id.to_be_bytes(),), |row| Ok(read_row(row)))
       .optional()
@@ -278,12 +397,26 @@ impl EdgeSetTransactor for Transactor {
       .map(|(_, item)| item)
   }
 
-  fn set(&mut self, prefix: &str, name: &str, id: u128, item: Item) {
+  fn get_many(&self, prefix: &str, name: &str, ids: &[u128]) -> BTreeMap<u128, Item> {
+    let params = vec!["?"; ids.len()].join(", ");
     self
-      .prepare_cached(&format!("REPLACE INTO \"{prefix}.{name}.data\" VALUES (?, ?, ?, ?, ?, ?)"))
+      .prepare(&format!(
+        "SELECT id, bucket, clock, src, label, dst FROM \"{prefix}.{name}.data\"
+        WHERE id IN ({params})"
+      ))
       .unwrap()
-      .execute(make_row(id, item))
-      .unwrap();
+      .query_map(params_from_iter(ids.iter().map(|id| id.to_be_bytes())), |row| Ok(read_row(row)))
+      .unwrap()
+      .map(Result::unwrap)
+      .collect()
+  }
+
+  fn set(&mut self, sql: &ItemStatements, id: u128, item: Item) {
+    self.prepare_cached(&sql.set).unwrap().execute(make_row(id, item)).unwrap();
+  }
+
+  fn clear(&mut self, prefix: &str, name: &str) {
+    self.execute_batch(&format!("DELETE FROM \"{prefix}.{name}.data\"")).unwrap();
   }
 
   fn id_label_dst_by_src(&self, prefix: &str, name: &str, src: u128) -> BTreeMap<u128, (u64, u128)> {
@@ -299,6 +432,20 @@ impl EdgeSetTransactor for Transactor {
       .collect()
   }
 
+  fn id_src_label_dst_by_srcs(&self, prefix: &str, name: &str, srcs: &[u128]) -> BTreeMap<u128, (u128, u64, u128)> {
+    let params = vec!["?"; srcs.len()].join(", ");
+    self
+      .prepare(&format!(
+        "SELECT id, src, label, dst FROM \"{prefix}.{name}.data\" INDEXED BY \"{prefix}.{name}.data.idx_src_label\"
+        WHERE src IN ({params})"
+      ))
+      .unwrap()
+      .query_map(params_from_iter(srcs.iter().map(|src| src.to_be_bytes())), |row| Ok(read_row_id_src_label_dst(row)))
+      .unwrap()
+      .map(Result::unwrap)
+      .collect()
+  }
+
   fn id_dst_by_src_label(&self, prefix: &str, name: &str, src: u128, label: u64) -> BTreeMap<u128, u128> {
     self
       .prepare_cached(&format!(
@@ -338,11 +485,37 @@ impl EdgeSetTransactor for Transactor {
       .collect()
   }
 
+  fn id_dst_by_src_label_atom(
+    &self,
+    prefix: &str,
+    name: &str,
+    atoms_name: &str,
+    src: u128,
+    label: u64,
+    atom_label: u64,
+    value: &[u8],
+  ) -> BTreeMap<u128, u128> {
+    self
+      .prepare_cached(&format!(
+        "SELECT DISTINCT e.id, e.dst FROM \"{prefix}.{name}.data\" AS e INDEXED BY \"{prefix}.{name}.data.idx_src_label\"
+        JOIN \"{prefix}.{atoms_name}.data\" AS a INDEXED BY \"{prefix}.{atoms_name}.data.idx_src_label\"
+        ON a.src = e.dst AND a.label = ?3 AND a.value = ?4
+        WHERE e.src = ?1 AND e.label = ?2"
+      ))
+      .unwrap()
+      .query_map((src.to_be_bytes(), label.to_be_bytes(), atom_label.to_be_bytes(), value), |row| {
+        Ok(read_row_id_dst(row))
+      })
+      .unwrap()
+      .map(Result::unwrap)
+      .collect()
+  }
+
   fn by_bucket_clock_range(&self, prefix: &str, name: &str, bucket: u64, lower: Option<u64>) -> Vec<(u128, Item)> {
     self
       .prepare_cached(&format!(
         "SELECT id, bucket, clock, src, label, dst FROM \"{prefix}.{name}.data\" INDEXED BY \"{prefix}.{name}.data.idx_bucket_clock\"
-        WHERE bucket = ? AND clock > ? ORDER BY clock ASC"
+        WHERE bucket = ?1 AND clock > COALESCE(?2, x'') ORDER BY clock ASC"
       ))
       .unwrap()
       .query_map((bucket.to_be_bytes(), lower.map(u64::to_be_bytes)), |row| Ok(read_row(row)))
@@ -350,4 +523,24 @@ impl EdgeSetTransactor for Transactor {
       .map(Result::unwrap)
       .collect()
   }
+
+  fn by_bucket_clock_batch(
+    &self,
+    prefix: &str,
+    name: &str,
+    bucket: u64,
+    lower: Option<u64>,
+    limit: u64,
+  ) -> Vec<(u128, Item)> {
+    self
+      .prepare_cached(&format!(
+        "SELECT id, bucket, clock, src, label, dst FROM \"{prefix}.{name}.data\" INDEXED BY \"{prefix}.{name}.data.idx_bucket_clock\"
+        WHERE bucket = ?1 AND clock > COALESCE(?2, x'') ORDER BY clock ASC LIMIT ?3"
+      ))
+      .unwrap()
+      .query_map((bucket.to_be_bytes(), lower.map(u64::to_be_bytes), limit), |row| Ok(read_row(row)))
+      .unwrap()
+      .map(Result::unwrap)
+      .collect()
+  }
 }
@@ -24,6 +24,10 @@ use crate::Transactor;
 /// Base schema version.
 pub const CURRENT_VERSION: u64 = 1;
 
+/// Maximum number of values bound to a single `IN (...)` query, well below
+/// SQLite's limit on host parameters.
+pub const IN_BATCH_SIZE: usize = 256;
+
 /// Stores the metadata for workspaces.
 #[derive(Debug, Clone)]
 pub struct WorkspaceMetadata {
@@ -151,11 +155,21 @@ pub struct StructureMetadata {
   next: u64,
 }
 
+/// SQL of the statements reading and writing single items of a structure,
+/// which run on every lookup and modification, so they are formatted once per
+/// structure instead of on every call.
+#[derive(Debug, Clone)]
+pub struct ItemStatements {
+  pub get: String,
+  pub set: String,
+}
+
 /// Database interface for [`StructureMetadata`].
 pub trait StructureMetadataTransactor {
   fn init_buckets(&mut self, prefix: &str, name: &str);
   fn get_buckets(&self, prefix: &str, name: &str) -> BTreeMap<u64, u64>;
   fn set_bucket(&mut self, prefix: &str, name: &str, bucket: u64, clock: u64);
+  fn clear_buckets(&mut self, prefix: &str, name: &str);
 }
 
 impl StructureMetadata {
@@ -219,6 +233,14 @@ impl StructureMetadata {
       txr.set_bucket(self.prefix, self.name, key, value);
     }
   }
+
+  /// Removes all clock values, discarding pending modifications. The next
+  /// clock value is kept, so that clocks remain monotonic.
+  pub fn clear(&mut self, txr: &mut impl StructureMetadataTransactor) {
+    self.buckets.clear();
+    self.mods.clear();
+    txr.clear_buckets(self.prefix, self.name);
+  }
 }
 
 impl StructureMetadataTransactor for Transactor {
@@ -257,6 +279,10 @@ impl StructureMetadataTransactor for Transactor {
       .execute((bucket.to_be_bytes(), clock.to_be_bytes()))
       .unwrap();
   }
+
+  fn clear_buckets(&mut self, prefix: &str, name: &str) {
+    self.execute_batch(&format!("DELETE FROM \"{prefix}.{name}.buckets\"")).unwrap();
+  }
 }
 
 #[cfg(test)]
@@ -0,0 +1,242 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Streaming backups in newline-delimited JSON. Each line is a flat object
+//! describing one item of one of the sets, including removed items:
+//!
+//! ```text
+//! {"node":"1","bucket":2,"clock":3,"label":4}
+//! {"atom":"5","bucket":2,"clock":4,"src":"1","label":6,"value":"0a0b"}
+//! {"edge":"7","bucket":2,"clock":5,"src":null,"label":null,"dst":null}
+//! ```
+//!
+//! IDs are written as decimal strings (since they do not fit in JSON numbers)
+//! and atom values as lowercase hexadecimal strings.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+
+use super::{Workspace, MERGE_BATCH_SIZE};
+use crate::{StoreError, Transactor};
+
+impl Workspace {
+  /// Writes all items as newline-delimited JSON, reading them in batches of
+  /// [`MERGE_BATCH_SIZE`]. There must be no pending modifications.
+  pub fn export_ndjson(&self, txr: &Transactor, mut writer: impl Write) -> Result<(), StoreError> {
+    assert!(self.nodes.is_saved() && self.atoms.is_saved() && self.edges.is_saved());
+
+    for &bucket in self.nodes.buckets().keys() {
+      let mut lower = None;
+      loop {
+        let batch = self.nodes.actions_batch(txr, bucket, lower, MERGE_BATCH_SIZE);
+        let Some(&(_, (_, clock, _))) = batch.last() else { break };
+        lower = Some(clock);
+        for (id, (bucket, clock, l)) in batch {
+          writeln!(writer, "{{\"node\":\"{id}\",\"bucket\":{bucket},\"clock\":{clock},\"label\":{}}}", number(l))?;
+        }
+      }
+    }
+    for &bucket in self.atoms.buckets().keys() {
+      let mut lower = None;
+      loop {
+        let batch = self.atoms.actions_batch(txr, bucket, lower, MERGE_BATCH_SIZE);
+        let Some(&(_, (_, clock, _))) = batch.last() else { break };
+        lower = Some(clock);
+        for (id, (bucket, clock, slv)) in batch {
+          let (src, label, value) = match slv {
+            Some((src, label, value)) => (Some(src), Some(label), Some(hex(&value))),
+            None => (None, None, None),
+          };
+          writeln!(
+            writer,
+            "{{\"atom\":\"{id}\",\"bucket\":{bucket},\"clock\":{clock},\"src\":{},\"label\":{},\"value\":{}}}",
+            string(src),
+            number(label),
+            string(value)
+          )?;
+        }
+      }
+    }
+    for &bucket in self.edges.buckets().keys() {
+      let mut lower = None;
+      loop {
+        let batch = self.edges.actions_batch(txr, bucket, lower, MERGE_BATCH_SIZE);
+        let Some(&(_, (_, clock, _))) = batch.last() else { break };
+        lower = Some(clock);
+        for (id, (bucket, clock, sld)) in batch {
+          let (src, label, dst) = match sld {
+            Some((src, label, dst)) => (Some(src), Some(label), Some(dst)),
+            None => (None, None, None),
+          };
+          writeln!(
+            writer,
+            "{{\"edge\":\"{id}\",\"bucket\":{bucket},\"clock\":{clock},\"src\":{},\"label\":{},\"dst\":{}}}",
+            string(src),
+            number(label),
+            string(dst)
+          )?;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// Joins all items written by [`Workspace::export_ndjson`], issuing a
+  /// [`Workspace::barrier`] after every [`MERGE_BATCH_SIZE`] lines so that
+  /// pending modifications do not pile up in memory. Version vectors are not
+  /// exported, so concurrent values of causal atoms are resolved by
+  /// last-writer-wins.
+  pub fn import_ndjson(&mut self, txr: &mut Transactor, reader: impl BufRead) -> Result<(), StoreError> {
+    for (index, line) in reader.lines().enumerate() {
+      let line = line?;
+      if line.trim().is_empty() {
+        continue;
+      }
+      self.import_line(txr, &line).ok_or(StoreError::InvalidNdjson(index + 1))?;
+      if (index as u64 + 1).is_multiple_of(MERGE_BATCH_SIZE) {
+        self.barrier(txr);
+      }
+    }
+    self.barrier(txr);
+    Ok(())
+  }
+
+  fn import_line(&mut self, txr: &Transactor, line: &str) -> Option<()> {
+    let fields = parse_object(line)?;
+    let get = |key: &str| fields.get(key).copied();
+    let bucket = get("bucket")??.parse().ok()?;
+    let clock = get("clock")??.parse().ok()?;
+    let src = get("src").flatten().map(str::parse).transpose().ok()?;
+    let label = get("label").flatten().map(str::parse).transpose().ok()?;
+    if let Some(id) = get("node") {
+      let id = id?.parse().ok()?;
+      self.nodes.set(txr, id, bucket, clock, label);
+    } else if let Some(id) = get("atom") {
+      let id = id?.parse().ok()?;
+      let value = match get("value")? {
+        Some(value) => Some(unhex(value)?),
+        None => None,
+      };
+      let slv = match (src, label, value) {
+        (Some(src), Some(label), Some(value)) => Some((src, label, value)),
+        (None, None, None) => None,
+        _ => return None,
+      };
+      self.join_atom(txr, id, bucket, clock, slv, BTreeMap::new());
+    } else if let Some(id) = get("edge") {
+      let id = id?.parse().ok()?;
+      let dst = get("dst")?.map(str::parse).transpose().ok()?;
+      let sld = match (src, label, dst) {
+        (Some(src), Some(label), Some(dst)) => Some((src, label, dst)),
+        (None, None, None) => None,
+        _ => return None,
+      };
+      self.edges.set(txr, id, bucket, clock, sld);
+    } else {
+      return None;
+    }
+    Some(())
+  }
+}
+
+fn number(value: Option<u64>) -> String {
+  value.map_or_else(|| "null".to_owned(), |value| value.to_string())
+}
+
+fn string(value: Option<impl ToString>) -> String {
+  value.map_or_else(|| "null".to_owned(), |value| format!("\"{}\"", value.to_string()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<Box<[u8]>> {
+  if !s.len().is_multiple_of(2) {
+    return None;
+  }
+  (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// Parses a flat JSON object whose values are unescaped strings, unsigned
+/// integers or `null` (which is all [`Workspace::export_ndjson`] writes).
+fn parse_object(line: &str) -> Option<BTreeMap<&str, Option<&str>>> {
+  let mut rest = line.trim().strip_prefix('{')?.strip_suffix('}')?.trim();
+  let mut res = BTreeMap::new();
+  while !rest.is_empty() {
+    let (key, after) = rest.strip_prefix('"')?.split_once('"')?;
+    let after = after.trim_start().strip_prefix(':')?.trim_start();
+    let (value, after) = if let Some(after) = after.strip_prefix('"') {
+      let (value, after) = after.split_once('"')?;
+      (Some(value), after)
+    } else {
+      let end = after.find(',').unwrap_or(after.len());
+      let (value, after) = after.split_at(end);
+      let value = value.trim();
+      match value {
+        "null" => (None, after),
+        _ if !value.is_empty() && value.bytes().all(|c| c.is_ascii_digit()) => (Some(value), after),
+        _ => return None,
+      }
+    };
+    res.insert(key, value);
+    rest = after.trim_start();
+    if let Some(after) = rest.strip_prefix(',') {
+      rest = after.trim_start();
+    } else if !rest.is_empty() {
+      return None;
+    }
+  }
+  Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+  use rusqlite::Connection;
+
+  use super::*;
+  use crate::workspace::Constraints;
+
+  #[test]
+  fn ndjson_simple() {
+    let mut txr0: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws0 = Workspace::new("", Constraints::new(), &mut txr0);
+    for i in 0..300 {
+      ws0.set_node(&txr0, i, Some(1));
+      ws0.set_atom(&txr0, i, Some((i, 2, vec![i as u8, 0xff].into())));
+      ws0.set_edge(&txr0, i, Some((i, 3, (i + 1) % 300)));
+    }
+    ws0.barrier(&mut txr0);
+    ws0.set_node(&txr0, 0, None);
+    ws0.set_atom(&txr0, 1, None);
+    ws0.barrier(&mut txr0);
+
+    let mut buffer = Vec::new();
+    ws0.export_ndjson(&txr0, &mut buffer).unwrap();
+    assert_eq!(String::from_utf8_lossy(&buffer).lines().count(), 900);
+
+    let mut txr1: Transactor = Connection::open_in_memory().unwrap().try_into().unwrap();
+    let mut ws1 = Workspace::new("", Constraints::new(), &mut txr1);
+    ws1.import_ndjson(&mut txr1, &buffer[..]).unwrap();
+    assert_eq!(ws1.sync_version(&txr1), ws0.sync_version(&txr0));
+    for i in 0..300 {
+      assert_eq!(ws1.node(&txr1, i), ws0.node(&txr0, i));
+      assert_eq!(ws1.atom(&txr1, i), ws0.atom(&txr0, i));
+      assert_eq!(ws1.edge(&txr1, i), ws0.edge(&txr0, i));
+    }
+
+    let invalid = b"{\"node\":\"1\",\"bucket\":1}\n";
+    assert!(matches!(ws1.import_ndjson(&mut txr1, &invalid[..]), Err(StoreError::InvalidNdjson(1))));
+  }
+}
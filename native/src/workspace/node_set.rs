@@ -13,16 +13,20 @@
 // limitations under the License.
 
 use rusqlite::{OptionalExtension, Result, Row};
+use std::cell::RefCell;
 use std::collections::{btree_map::Entry, BTreeMap};
+use std::ops::ControlFlow;
 
-use super::metadata::{StructureMetadata, StructureMetadataTransactor};
+use super::metadata::{ItemStatements, StructureMetadata, StructureMetadataTransactor};
 use crate::Transactor;
 
 /// A last-writer-wins element set for storing nodes.
 #[derive(Debug)]
 pub struct NodeSet {
   metadata: StructureMetadata,
+  sql: ItemStatements,
   mods: BTreeMap<u128, (Option<Item>, Item)>,
+  label_cache: Option<RefCell<BTreeMap<u64, BTreeMap<u128, ()>>>>,
 }
 
 /// `(bucket, clock, label)`.
@@ -35,10 +39,37 @@ fn item_lt(lhs: &Item, rhs: &Item) -> bool {
 /// Database interface for [`NodeSet`].
 pub trait NodeSetTransactor: StructureMetadataTransactor {
   fn init(&mut self, prefix: &str, name: &str);
-  fn get(&self, prefix: &str, name: &str, id: u128) -> Option<Item>;
-  fn set(&mut self, prefix: &str, name: &str, id: u128, item: Item);
+  fn item_statements(&self, prefix: &str, name: &str) -> ItemStatements;
+  fn get(&self, sql: &ItemStatements, id: u128) -> Option<Item>;
+  fn set(&mut self, sql: &ItemStatements, id: u128, item: Item);
+  fn clear(&mut self, prefix: &str, name: &str);
   fn id_by_label(&self, prefix: &str, name: &str, label: u64) -> BTreeMap<u128, ()>;
+  fn id_by_label_for_each(
+    &self,
+    prefix: &str,
+    name: &str,
+    label: u64,
+    f: &mut dyn FnMut(u128) -> ControlFlow<()>,
+  ) -> ControlFlow<()>;
+  fn count_by_label(&self, prefix: &str, name: &str) -> BTreeMap<u64, u64>;
+  fn id_by_label_ordered_by_atom(
+    &self,
+    prefix: &str,
+    name: &str,
+    atoms_name: &str,
+    label: u64,
+    atom_label: u64,
+    desc: bool,
+  ) -> Vec<u128>;
   fn by_bucket_clock_range(&self, prefix: &str, name: &str, bucket: u64, lower: Option<u64>) -> BTreeMap<u128, Item>;
+  fn by_bucket_clock_batch(
+    &self,
+    prefix: &str,
+    name: &str,
+    bucket: u64,
+    lower: Option<u64>,
+    limit: u64,
+  ) -> Vec<(u128, Item)>;
 }
 
 impl NodeSet {
@@ -47,7 +78,16 @@ impl NodeSet {
     let metadata = StructureMetadata::new(prefix, name, txr);
     let mods = BTreeMap::new();
     txr.init(prefix, name);
-    Self { metadata, mods }
+    let sql = txr.item_statements(prefix, name);
+    Self { metadata, sql, mods, label_cache: None }
+  }
+
+  /// Starts caching saved results of [`NodeSet::id_by_label`] in memory. A
+  /// label's entry is dropped whenever a node with that label (before or
+  /// after the modification) is saved, which covers joins as well, since they
+  /// are saved the same way.
+  pub fn enable_label_cache(&mut self) {
+    self.label_cache.get_or_insert_with(Default::default);
   }
 
   /// Returns the name of the workspace.
@@ -70,6 +110,11 @@ impl NodeSet {
     self.metadata.next()
   }
 
+  /// Returns if there are no pending modifications.
+  pub fn is_saved(&self) -> bool {
+    self.mods.is_empty()
+  }
+
   /// Returns pending modifications.
   pub fn mods(&self) -> Vec<(u128, Option<u64>, Option<u64>)> {
     let mut res = Vec::new();
@@ -84,11 +129,17 @@ impl NodeSet {
   }
 
   pub fn get(&self, txr: &impl NodeSetTransactor, id: u128) -> Option<Item> {
-    self.mods.get(&id).map_or_else(|| txr.get(self.prefix(), self.name(), id), |(_, curr)| Some(*curr))
+    self.mods.get(&id).map_or_else(|| txr.get(&self.sql, id), |(_, curr)| Some(*curr))
   }
 
   pub fn id_by_label(&self, txr: &impl NodeSetTransactor, label: u64) -> BTreeMap<u128, ()> {
-    let mut res = txr.id_by_label(self.prefix(), self.name(), label);
+    let mut res = match &self.label_cache {
+      Some(cache) => {
+        let mut cache = cache.borrow_mut();
+        cache.entry(label).or_insert_with(|| txr.id_by_label(self.prefix(), self.name(), label)).clone()
+      }
+      None => txr.id_by_label(self.prefix(), self.name(), label),
+    };
     for (id, (_, (_, _, l))) in &self.mods {
       match l {
         Some(label_) if label_ == &label => res.insert(*id, ()),
@@ -98,6 +149,45 @@ impl NodeSet {
     res
   }
 
+  /// Same as [`NodeSet::id_by_label`], but calls `f` on each ID as rows are
+  /// read (pending modifications first), stopping once it breaks. Bypasses
+  /// the label cache.
+  pub fn id_by_label_for_each(
+    &self,
+    txr: &impl NodeSetTransactor,
+    label: u64,
+    mut f: impl FnMut(u128) -> ControlFlow<()>,
+  ) -> ControlFlow<()> {
+    for (id, (_, (_, _, l))) in &self.mods {
+      if *l == Some(label) {
+        f(*id)?;
+      }
+    }
+    txr.id_by_label_for_each(self.prefix(), self.name(), label, &mut |id| match self.mods.contains_key(&id) {
+      true => ControlFlow::Continue(()),
+      false => f(id),
+    })
+  }
+
+  /// Returns the number of present items with each label.
+  pub fn count_by_label(&self, txr: &impl NodeSetTransactor) -> BTreeMap<u64, u64> {
+    let mut res = txr.count_by_label(self.prefix(), self.name());
+    for (prev, (_, _, curr)) in self.mods.values() {
+      if let Some(label) = prev.and_then(|(_, _, l)| l) {
+        if let Entry::Occupied(mut entry) = res.entry(label) {
+          *entry.get_mut() -= 1;
+          if *entry.get() == 0 {
+            entry.remove();
+          }
+        }
+      }
+      if let Some(label) = curr {
+        *res.entry(*label).or_default() += 1;
+      }
+    }
+    res
+  }
+
   /// Returns all actions strictly later than given clock values.
   /// Absent entries are assumed to be `None`.
   pub fn actions(&self, txr: &impl NodeSetTransactor, version: BTreeMap<u64, u64>) -> BTreeMap<u128, Item> {
@@ -119,13 +209,25 @@ impl NodeSet {
     res
   }
 
+  /// Returns up to `limit` saved actions of one bucket strictly later than
+  /// given clock value, in increasing order of clock values.
+  pub fn actions_batch(
+    &self,
+    txr: &impl NodeSetTransactor,
+    bucket: u64,
+    lower: Option<u64>,
+    limit: u64,
+  ) -> Vec<(u128, Item)> {
+    txr.by_bucket_clock_batch(self.prefix(), self.name(), bucket, lower, limit)
+  }
+
   /// Modifies item.
   pub fn set(&mut self, txr: &impl NodeSetTransactor, id: u128, bucket: u64, clock: u64, l: Option<u64>) -> bool {
     if self.metadata.update(bucket, clock) {
       let item = (bucket, clock, l);
       match self.mods.entry(id) {
         Entry::Vacant(entry) => {
-          let prev = txr.get(self.metadata.prefix(), self.metadata.name(), id);
+          let prev = txr.get(&self.sql, id);
           if prev.is_none() || item_lt(prev.as_ref().unwrap(), &item) {
             entry.insert((prev, item));
             return true;
@@ -145,13 +247,43 @@ impl NodeSet {
   /// Saves all pending modifications.
   pub fn save(&mut self, txr: &mut impl NodeSetTransactor) {
     self.metadata.save(txr);
-    for (id, (_, curr)) in std::mem::take(&mut self.mods) {
-      txr.set(self.prefix(), self.name(), id, curr);
+    for (id, (prev, curr)) in std::mem::take(&mut self.mods) {
+      if let Some(cache) = &self.label_cache {
+        let mut cache = cache.borrow_mut();
+        for label in [prev.and_then(|(_, _, l)| l), curr.2].into_iter().flatten() {
+          cache.remove(&label);
+        }
+      }
+      txr.set(&self.sql, id, curr);
     }
   }
+
+  /// Removes all items and clock values, discarding pending modifications.
+  /// No actions are generated.
+  pub fn clear(&mut self, txr: &mut impl NodeSetTransactor) {
+    self.metadata.clear(txr);
+    self.mods.clear();
+    if let Some(cache) = &self.label_cache {
+      cache.borrow_mut().clear();
+    }
+    txr.clear(self.prefix(), self.name());
+  }
+
+  /// Prepares all statements by running them on dummy arguments. Writes some
+  /// dummy data, which should be rolled back by the caller.
+  pub fn warm_up(&self, txr: &mut impl NodeSetTransactor) {
+    let (prefix, name) = (self.prefix(), self.name());
+    txr.get(&self.sql, 0);
+    txr.id_by_label(prefix, name, 0);
+    txr.by_bucket_clock_range(prefix, name, 0, None);
+    txr.by_bucket_clock_batch(prefix, name, 0, None, 0);
+    txr.get_buckets(prefix, name);
+    txr.set(&self.sql, 0, (0, 0, None));
+    txr.set_bucket(prefix, name, 0, 0);
+  }
 }
 
-fn read_row(row: &Row<'_>) -> (u128, Item) {
+pub(super) fn read_row(row: &Row<'_>) -> (u128, Item) {
   let id = row.get(0).unwrap();
   let bucket = row.get(1).unwrap();
   let clock = row.get(2).unwrap();
@@ -189,12 +321,16 @@ impl NodeSetTransactor for Transactor {
       .unwrap();
   }
 
-  fn get(&self, prefix: &str, name: &str, id: u128) -> Option<Item> {
+  fn item_statements(&self, prefix: &str, name: &str) -> ItemStatements {
+    ItemStatements {
+      get: format!("SELECT id, bucket, clock, label FROM \"{prefix}.{name}.data\" WHERE id = ?"),
+      set: format!("REPLACE INTO \"{prefix}.{name}.data\" VALUES (?, ?, ?, ?)"),
+    }
+  }
+
+  fn get(&self, sql: &ItemStatements, id: u128) -> Option<Item> {
     self
-      .prepare_cached(&format!(
-        "SELECT id, bucket, clock, label FROM \"{prefix}.{name}.data\"
-        WHERE id = ?"
-      ))
+      .prepare_cached(&sql.get)
       .unwrap()
       .query_row((id.to_be_bytes(),), |row| Ok(read_row(row)))
       .optional()
@@ -202,12 +338,12 @@ impl NodeSetTransactor for Transactor {
       .map(|(_, item)| item)
   }
 
-  fn set(&mut self, prefix: &str, name: &str, id: u128, item: Item) {
-    self
-      .prepare_cached(&format!("REPLACE INTO \"{prefix}.{name}.data\" VALUES (?, ?, ?, ?)"))
-      .unwrap()
-      .execute(make_row(id, item))
-      .unwrap();
+  fn set(&mut self, sql: &ItemStatements, id: u128, item: Item) {
+    self.prepare_cached(&sql.set).unwrap().execute(make_row(id, item)).unwrap();
+  }
+
+  fn clear(&mut self, prefix: &str, name: &str) {
+    self.execute_batch(&format!("DELETE FROM \"{prefix}.{name}.data\"")).unwrap();
   }
 
   fn id_by_label(&self, prefix: &str, name: &str, label: u64) -> BTreeMap<u128, ()> {
@@ -223,11 +359,70 @@ impl NodeSetTransactor for Transactor {
       .collect()
   }
 
+  fn id_by_label_for_each(
+    &self,
+    prefix: &str,
+    name: &str,
+    label: u64,
+    f: &mut dyn FnMut(u128) -> ControlFlow<()>,
+  ) -> ControlFlow<()> {
+    let mut stmt = self
+      .prepare_cached(&format!(
+        "SELECT id FROM \"{prefix}.{name}.data\" INDEXED BY \"{prefix}.{name}.data.idx_label\"
+        WHERE label = ?"
+      ))
+      .unwrap();
+    let mut rows = stmt.query((label.to_be_bytes(),)).unwrap();
+    while let Some(row) = rows.next().unwrap() {
+      f(read_row_id(row).0)?;
+    }
+    ControlFlow::Continue(())
+  }
+
+  fn count_by_label(&self, prefix: &str, name: &str) -> BTreeMap<u64, u64> {
+    self
+      .prepare_cached(&format!(
+        "SELECT label, count(*) FROM \"{prefix}.{name}.data\" INDEXED BY \"{prefix}.{name}.data.idx_label\"
+        WHERE label IS NOT NULL GROUP BY label"
+      ))
+      .unwrap()
+      .query_map((), |row| Ok((u64::from_be_bytes(row.get(0).unwrap()), row.get(1).unwrap())))
+      .unwrap()
+      .map(Result::unwrap)
+      .collect()
+  }
+
+  fn id_by_label_ordered_by_atom(
+    &self,
+    prefix: &str,
+    name: &str,
+    atoms_name: &str,
+    label: u64,
+    atom_label: u64,
+    desc: bool,
+  ) -> Vec<u128> {
+    let order = if desc { "DESC" } else { "ASC" };
+    self
+      .prepare_cached(&format!(
+        "SELECT n.id FROM \"{prefix}.{name}.data\" AS n INDEXED BY \"{prefix}.{name}.data.idx_label\"
+        WHERE n.label = ?1
+        ORDER BY (
+          SELECT MIN(a.value) FROM \"{prefix}.{atoms_name}.data\" AS a INDEXED BY \"{prefix}.{atoms_name}.data.idx_src_label\"
+          WHERE a.src = n.id AND a.label = ?2
+        ) {order}, n.id ASC"
+      ))
+      .unwrap()
+      .query_map((label.to_be_bytes(), atom_label.to_be_bytes()), |row| Ok(read_row_id(row).0))
+      .unwrap()
+      .map(Result::unwrap)
+      .collect()
+  }
+
   fn by_bucket_clock_range(&self, prefix: &str, name: &str, bucket: u64, lower: Option<u64>) -> BTreeMap<u128, Item> {
     self
       .prepare_cached(&format!(
         "SELECT id, bucket, clock, label FROM \"{prefix}.{name}.data\" INDEXED BY \"{prefix}.{name}.data.idx_bucket_clock\"
-        WHERE bucket = ? AND clock > ?"
+        WHERE bucket = ?1 AND clock > COALESCE(?2, x'')"
       ))
       .unwrap()
       .query_map((bucket.to_be_bytes(), lower.map(u64::to_be_bytes)), |row| Ok(read_row(row)))
@@ -235,4 +430,24 @@ impl NodeSetTransactor for Transactor {
       .map(Result::unwrap)
       .collect()
   }
+
+  fn by_bucket_clock_batch(
+    &self,
+    prefix: &str,
+    name: &str,
+    bucket: u64,
+    lower: Option<u64>,
+    limit: u64,
+  ) -> Vec<(u128, Item)> {
+    self
+      .prepare_cached(&format!(
+        "SELECT id, bucket, clock, label FROM \"{prefix}.{name}.data\" INDEXED BY \"{prefix}.{name}.data.idx_bucket_clock\"
+        WHERE bucket = ?1 AND clock > COALESCE(?2, x'') ORDER BY clock ASC LIMIT ?3"
+      ))
+      .unwrap()
+      .query_map((bucket.to_be_bytes(), lower.map(u64::to_be_bytes), limit), |row| Ok(read_row(row)))
+      .unwrap()
+      .map(Result::unwrap)
+      .collect()
+  }
 }
@@ -0,0 +1,223 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rusqlite::{types::Value, Row};
+use std::{
+  collections::BTreeMap,
+  ops::{Bound, RangeBounds},
+};
+
+use crate::Transactor;
+
+/// How the values of an indexed atom are decoded, see [`NumericIndex`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumericKind {
+  /// Big-endian `i64`, stored as `INTEGER`.
+  Integer,
+  /// Big-endian `f64`, stored as `REAL`.
+  Real,
+}
+
+impl NumericKind {
+  /// Decodes a serialized atom value. Returns `None` for values of
+  /// unexpected length and for NaN (which SQLite would store as `NULL`),
+  /// which are then left out of the index.
+  pub fn decode(self, value: &[u8]) -> Option<Value> {
+    let bytes: [u8; 8] = value.try_into().ok()?;
+    match self {
+      Self::Integer => Some(Value::Integer(i64::from_be_bytes(bytes))),
+      Self::Real => Some(f64::from_be_bytes(bytes)).filter(|value| !value.is_nan()).map(Value::Real),
+    }
+  }
+}
+
+/// Stores decoded numeric values of atoms in a typed column of a parallel
+/// table, so that they can be compared and range-queried in SQL.
+///
+/// The index is updated from the saved modifications at each barrier, so it
+/// does not reflect pending modifications.
+#[derive(Debug)]
+pub struct NumericIndex {
+  prefix: &'static str,
+  name: &'static str,
+}
+
+/// Database interface for [`NumericIndex`].
+pub trait NumericIndexTransactor {
+  fn init_numeric(&mut self, prefix: &str, name: &str);
+  fn set_numeric(
+    &mut self,
+    prefix: &str,
+    name: &str,
+    id: u128,
+    slv: Option<(u128, u64, Value)>,
+  ) -> rusqlite::Result<()>;
+  fn count_numeric_by_label(&self, prefix: &str, name: &str, label: u64) -> u64;
+  fn id_src_by_label_range(
+    &self,
+    prefix: &str,
+    name: &str,
+    label: u64,
+    lower: Bound<f64>,
+    upper: Bound<f64>,
+  ) -> BTreeMap<u128, u128>;
+  fn clear_numeric(&mut self, prefix: &str, name: &str);
+}
+
+impl NumericIndex {
+  /// Creates or loads data.
+  pub fn new(prefix: &'static str, name: &'static str, txr: &mut impl NumericIndexTransactor) -> Self {
+    txr.init_numeric(prefix, name);
+    Self { prefix, name }
+  }
+
+  /// Returns if nothing with the given label has been indexed.
+  pub fn is_empty(&self, txr: &impl NumericIndexTransactor, label: u64) -> bool {
+    txr.count_numeric_by_label(self.prefix, self.name, label) == 0
+  }
+
+  /// Updates the indexed value of an atom.
+  pub fn set(
+    &self,
+    txr: &mut impl NumericIndexTransactor,
+    id: u128,
+    slv: Option<(u128, u64, Value)>,
+  ) -> rusqlite::Result<()> {
+    txr.set_numeric(self.prefix, self.name, id, slv)
+  }
+
+  /// Same as [`NumericIndex::set`], but if that fails, leaves the atom out of
+  /// the index instead. The index only holds values derived from the atoms, so
+  /// failing to index one must not fail the barrier which saves them.
+  pub fn set_or_skip(&self, txr: &mut impl NumericIndexTransactor, id: u128, slv: Option<(u128, u64, Value)>) {
+    if let Err(_err) = self.set(txr, id, slv) {
+      #[cfg(feature = "tracing")]
+      tracing::warn!(id = %id, error = %_err, "numeric index");
+      let _ = self.set(txr, id, None);
+    }
+  }
+
+  /// Returns the atoms with given label whose values lie within `range`.
+  pub fn id_src_by_label_range(
+    &self,
+    txr: &impl NumericIndexTransactor,
+    label: u64,
+    range: impl RangeBounds<f64>,
+  ) -> BTreeMap<u128, u128> {
+    let (lower, upper) = (range.start_bound().cloned(), range.end_bound().cloned());
+    txr.id_src_by_label_range(self.prefix, self.name, label, lower, upper)
+  }
+
+  /// Removes all indexed values.
+  pub fn clear(&self, txr: &mut impl NumericIndexTransactor) {
+    txr.clear_numeric(self.prefix, self.name);
+  }
+
+  /// Prepares all statements by running them on dummy arguments. Writes some
+  /// dummy data, which should be rolled back by the caller.
+  pub fn warm_up(&self, txr: &mut impl NumericIndexTransactor) {
+    txr.count_numeric_by_label(self.prefix, self.name, 0);
+    txr.id_src_by_label_range(self.prefix, self.name, 0, Bound::Unbounded, Bound::Unbounded);
+    txr.set_numeric(self.prefix, self.name, 0, Some((0, 0, Value::Integer(0)))).unwrap();
+    txr.set_numeric(self.prefix, self.name, 0, None).unwrap();
+  }
+}
+
+fn read_row_id_src(row: &Row<'_>) -> (u128, u128) {
+  let id = row.get(0).unwrap();
+  let src = row.get(1).unwrap();
+  (u128::from_be_bytes(id), u128::from_be_bytes(src))
+}
+
+/// Splits a bound into `(exclusive, inclusive)` parameters.
+fn split_bound(bound: Bound<f64>) -> (Option<f64>, Option<f64>) {
+  match bound {
+    Bound::Excluded(value) => (Some(value), None),
+    Bound::Included(value) => (None, Some(value)),
+    Bound::Unbounded => (None, None),
+  }
+}
+
+impl NumericIndexTransactor for Transactor {
+  fn init_numeric(&mut self, prefix: &str, name: &str) {
+    self
+      .execute_batch(&format!(
+        "
+        CREATE TABLE IF NOT EXISTS \"{prefix}.{name}.numeric\" (
+          id BLOB NOT NULL,
+          src BLOB NOT NULL,
+          label BLOB NOT NULL,
+          value ANY NOT NULL,
+          PRIMARY KEY (id)
+        ) STRICT, WITHOUT ROWID;
+
+        CREATE INDEX IF NOT EXISTS \"{prefix}.{name}.numeric.idx_label_value\" ON \"{prefix}.{name}.numeric\" (label, value);
+        "
+      ))
+      .unwrap();
+  }
+
+  fn set_numeric(
+    &mut self,
+    prefix: &str,
+    name: &str,
+    id: u128,
+    slv: Option<(u128, u64, Value)>,
+  ) -> rusqlite::Result<()> {
+    match slv {
+      Some((src, label, value)) => self
+        .prepare_cached(&format!("REPLACE INTO \"{prefix}.{name}.numeric\" VALUES (?, ?, ?, ?)"))?
+        .execute((id.to_be_bytes(), src.to_be_bytes(), label.to_be_bytes(), value))?,
+      None => self
+        .prepare_cached(&format!("DELETE FROM \"{prefix}.{name}.numeric\" WHERE id = ?"))?
+        .execute((id.to_be_bytes(),))?,
+    };
+    Ok(())
+  }
+
+  fn count_numeric_by_label(&self, prefix: &str, name: &str, label: u64) -> u64 {
+    self
+      .prepare_cached(&format!("SELECT COUNT(*) FROM \"{prefix}.{name}.numeric\" WHERE label = ?"))
+      .unwrap()
+      .query_row((label.to_be_bytes(),), |row| row.get(0))
+      .unwrap()
+  }
+
+  fn id_src_by_label_range(
+    &self,
+    prefix: &str,
+    name: &str,
+    label: u64,
+    lower: Bound<f64>,
+    upper: Bound<f64>,
+  ) -> BTreeMap<u128, u128> {
+    let (gt, ge) = split_bound(lower);
+    let (lt, le) = split_bound(upper);
+    self
+      .prepare_cached(&format!(
+        "SELECT id, src FROM \"{prefix}.{name}.numeric\" INDEXED BY \"{prefix}.{name}.numeric.idx_label_value\"
+        WHERE label = ?1 AND (?2 IS NULL OR value > ?2) AND (?3 IS NULL OR value >= ?3)
+        AND (?4 IS NULL OR value < ?4) AND (?5 IS NULL OR value <= ?5)"
+      ))
+      .unwrap()
+      .query_map((label.to_be_bytes(), gt, ge, lt, le), |row| Ok(read_row_id_src(row)))
+      .unwrap()
+      .map(Result::unwrap)
+      .collect()
+  }
+
+  fn clear_numeric(&mut self, prefix: &str, name: &str) {
+    self.execute_batch(&format!("DELETE FROM \"{prefix}.{name}.numeric\"")).unwrap();
+  }
+}
@@ -0,0 +1,131 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Schema-less reading of any workspace stored in a database, e.g. for a
+//! generic inspector opening a database written by an app whose models are
+//! unknown. Unlike [`Workspace::new`](super::Workspace::new), this neither
+//! creates nor writes anything, and needs no constraints, so it can read
+//! through a read-only connection in a [`Transactor::snapshot`].
+
+use std::collections::BTreeMap;
+
+use super::atom_set::AtomSetTransactor;
+use super::edge_set::EdgeSetTransactor;
+use super::node_set::NodeSetTransactor;
+use super::{list_collections, ATOMS_NAME, EDGES_NAME, NODES_NAME};
+use crate::Transactor;
+
+/// A read-only view of the saved nodes, atoms and edges of the workspace with
+/// given prefix, as untyped rows. Removed items read as absent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawGraph {
+  prefix: String,
+}
+
+impl RawGraph {
+  pub fn new(prefix: impl Into<String>) -> Self {
+    Self { prefix: prefix.into() }
+  }
+
+  /// Returns a view of each workspace in the database, i.e. of each prefix
+  /// found by [`list_collections`] with all of nodes, atoms and edges.
+  pub fn list(txr: &Transactor) -> Vec<Self> {
+    let mut names: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (prefix, name) in list_collections(txr) {
+      names.entry(prefix).or_default().push(name);
+    }
+    names
+      .into_iter()
+      .filter(|(_, names)| [NODES_NAME, ATOMS_NAME, EDGES_NAME].iter().all(|name| names.iter().any(|n| n == name)))
+      .map(|(prefix, _)| Self::new(prefix))
+      .collect()
+  }
+
+  pub fn prefix(&self) -> &str {
+    &self.prefix
+  }
+
+  pub fn node(&self, txr: &Transactor, id: u128) -> Option<u64> {
+    let sql = NodeSetTransactor::item_statements(txr, &self.prefix, NODES_NAME);
+    NodeSetTransactor::get(txr, &sql, id).and_then(|(_, _, label)| label)
+  }
+  pub fn node_id_by_label(&self, txr: &Transactor, label: u64) -> BTreeMap<u128, ()> {
+    txr.id_by_label(&self.prefix, NODES_NAME, label)
+  }
+  pub fn node_count_by_label(&self, txr: &Transactor) -> BTreeMap<u64, u64> {
+    txr.count_by_label(&self.prefix, NODES_NAME)
+  }
+
+  pub fn atom(&self, txr: &Transactor, id: u128) -> Option<(u128, u64, Box<[u8]>)> {
+    let sql = AtomSetTransactor::item_statements(txr, &self.prefix, ATOMS_NAME);
+    AtomSetTransactor::get(txr, &sql, id).and_then(|(_, _, slv)| slv)
+  }
+  pub fn atom_id_label_value_by_src(&self, txr: &Transactor, src: u128) -> BTreeMap<u128, (u64, Box<[u8]>)> {
+    txr.id_label_value_by_src(&self.prefix, ATOMS_NAME, src)
+  }
+
+  pub fn edge(&self, txr: &Transactor, id: u128) -> Option<(u128, u64, u128)> {
+    let sql = EdgeSetTransactor::item_statements(txr, &self.prefix, EDGES_NAME);
+    EdgeSetTransactor::get(txr, &sql, id).and_then(|(_, _, sld)| sld)
+  }
+  pub fn edge_id_label_dst_by_src(&self, txr: &Transactor, src: u128) -> BTreeMap<u128, (u64, u128)> {
+    txr.id_label_dst_by_src(&self.prefix, EDGES_NAME, src)
+  }
+  pub fn edge_id_src_label_by_dst(&self, txr: &Transactor, dst: u128) -> BTreeMap<u128, (u128, u64)> {
+    txr.id_src_label_by_dst(&self.prefix, EDGES_NAME, dst)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rusqlite::{Connection, OpenFlags};
+
+  use super::*;
+  use crate::workspace::{Constraints, Workspace};
+
+  #[test]
+  fn raw_graph_simple() {
+    let path = std::env::temp_dir().join(format!("dust-raw-{}.sqlite3", rand::random::<u64>()));
+    {
+      let mut txr: Transactor = Connection::open(&path).unwrap().try_into().unwrap();
+      let mut constraints = Constraints::new();
+      constraints.add_sticky_node(1);
+      let mut ws = Workspace::new("app", constraints, &mut txr);
+      ws.set_node(&txr, 1, Some(1));
+      ws.set_node(&txr, 2, Some(1));
+      ws.set_node(&txr, 3, Some(2));
+      ws.set_atom(&txr, 4, Some((1, 10, vec![42].into())));
+      ws.set_edge(&txr, 5, Some((1, 20, 3)));
+      ws.barrier(&mut txr);
+      txr.execute_batch("COMMIT").unwrap();
+    }
+
+    // Opened read-only, without any workspace or constraints.
+    let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY).unwrap();
+    let txr = Transactor::snapshot(conn).unwrap();
+    let graphs = RawGraph::list(&txr);
+    assert_eq!(graphs, [RawGraph::new("app")]);
+    let graph = &graphs[0];
+    assert_eq!(graph.node_count_by_label(&txr), BTreeMap::from([(1, 2), (2, 1)]));
+    assert_eq!(graph.node_id_by_label(&txr, 1).into_keys().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!((graph.node(&txr, 3), graph.node(&txr, 6)), (Some(2), None));
+    assert_eq!(graph.atom(&txr, 4), Some((1, 10, vec![42].into())));
+    assert_eq!(graph.atom_id_label_value_by_src(&txr, 1), BTreeMap::from([(4, (10, vec![42].into()))]));
+    assert_eq!(graph.edge(&txr, 5), Some((1, 20, 3)));
+    assert_eq!(graph.edge_id_label_dst_by_src(&txr, 1), BTreeMap::from([(5, (20, 3))]));
+    assert_eq!(graph.edge_id_src_label_by_dst(&txr, 3), BTreeMap::from([(5, (1, 20))]));
+    drop(txr);
+    let _ = std::fs::remove_file(&path);
+  }
+}
@@ -0,0 +1,144 @@
+// Copyright 2024 ParkourLabs
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rusqlite::OptionalExtension;
+use std::collections::BTreeMap;
+
+use crate::{deserialize, serialize, Transactor};
+
+/// Stores version vectors for items of a last-writer-wins structure, in a
+/// parallel table. A version vector maps each bucket to the largest clock
+/// value of that bucket which an item's current value has observed.
+///
+/// Version vectors do not take part in resolving modifications (which is
+/// still done by comparing `(clock, bucket)`), they only allow detecting
+/// whether two modifications were concurrent.
+#[derive(Debug)]
+pub struct VersionSet {
+  prefix: &'static str,
+  name: &'static str,
+  mods: BTreeMap<u128, BTreeMap<u64, u64>>,
+}
+
+/// Database interface for [`VersionSet`].
+pub trait VersionSetTransactor {
+  fn init_versions(&mut self, prefix: &str, name: &str);
+  fn get_version(&self, prefix: &str, name: &str, id: u128) -> Option<BTreeMap<u64, u64>>;
+  fn set_version(&mut self, prefix: &str, name: &str, id: u128, version: &BTreeMap<u64, u64>);
+  fn clear_versions(&mut self, prefix: &str, name: &str);
+}
+
+/// Returns if `lhs` has observed everything `rhs` has.
+pub fn dominates(lhs: &BTreeMap<u64, u64>, rhs: &BTreeMap<u64, u64>) -> bool {
+  rhs.iter().all(|(bucket, clock)| lhs.get(bucket) >= Some(clock))
+}
+
+/// Returns the pointwise maximum of `lhs` and `rhs`.
+pub fn pointwise_max(lhs: &BTreeMap<u64, u64>, rhs: &BTreeMap<u64, u64>) -> BTreeMap<u64, u64> {
+  let mut res = lhs.clone();
+  for (&bucket, &clock) in rhs {
+    let entry = res.entry(bucket).or_insert(clock);
+    *entry = clock.max(*entry);
+  }
+  res
+}
+
+/// Returns the largest clock in each bucket among the given
+/// `(bucket, clock)` pairs.
+pub fn bucket_max(clocks: impl IntoIterator<Item = (u64, u64)>) -> BTreeMap<u64, u64> {
+  let mut res = BTreeMap::new();
+  for (bucket, clock) in clocks {
+    let entry = res.entry(bucket).or_insert(clock);
+    *entry = clock.max(*entry);
+  }
+  res
+}
+
+impl VersionSet {
+  /// Creates or loads data.
+  pub fn new(prefix: &'static str, name: &'static str, txr: &mut impl VersionSetTransactor) -> Self {
+    txr.init_versions(prefix, name);
+    Self { prefix, name, mods: BTreeMap::new() }
+  }
+
+  /// Returns the version vector of an item, or an empty one if absent.
+  pub fn get(&self, txr: &impl VersionSetTransactor, id: u128) -> BTreeMap<u64, u64> {
+    self.mods.get(&id).cloned().or_else(|| txr.get_version(self.prefix, self.name, id)).unwrap_or_default()
+  }
+
+  /// Modifies the version vector of an item.
+  pub fn set(&mut self, id: u128, version: BTreeMap<u64, u64>) {
+    self.mods.insert(id, version);
+  }
+
+  /// Saves all pending modifications.
+  pub fn save(&mut self, txr: &mut impl VersionSetTransactor) {
+    for (id, version) in std::mem::take(&mut self.mods) {
+      txr.set_version(self.prefix, self.name, id, &version);
+    }
+  }
+
+  /// Removes all version vectors, discarding pending modifications.
+  pub fn clear(&mut self, txr: &mut impl VersionSetTransactor) {
+    self.mods.clear();
+    txr.clear_versions(self.prefix, self.name);
+  }
+
+  /// Prepares all statements by running them on dummy arguments. Writes some
+  /// dummy data, which should be rolled back by the caller.
+  pub fn warm_up(&self, txr: &mut impl VersionSetTransactor) {
+    txr.get_version(self.prefix, self.name, 0);
+    txr.set_version(self.prefix, self.name, 0, &BTreeMap::new());
+  }
+}
+
+impl VersionSetTransactor for Transactor {
+  fn init_versions(&mut self, prefix: &str, name: &str) {
+    self
+      .execute_batch(&format!(
+        "
+        CREATE TABLE IF NOT EXISTS \"{prefix}.{name}.versions\" (
+          id BLOB NOT NULL,
+          version BLOB NOT NULL,
+          PRIMARY KEY (id)
+        ) STRICT, WITHOUT ROWID;
+        "
+      ))
+      .unwrap();
+  }
+
+  fn get_version(&self, prefix: &str, name: &str, id: u128) -> Option<BTreeMap<u64, u64>> {
+    self
+      .prepare_cached(&format!("SELECT version FROM \"{prefix}.{name}.versions\" WHERE id = ?"))
+      .unwrap()
+      .query_row((id.to_be_bytes(),), |row| {
+        let version: Vec<u8> = row.get(0).unwrap();
+        Ok(deserialize(&version).unwrap())
+      })
+      .optional()
+      .unwrap()
+  }
+
+  fn set_version(&mut self, prefix: &str, name: &str, id: u128, version: &BTreeMap<u64, u64>) {
+    self
+      .prepare_cached(&format!("REPLACE INTO \"{prefix}.{name}.versions\" VALUES (?, ?)"))
+      .unwrap()
+      .execute((id.to_be_bytes(), serialize(version).unwrap()))
+      .unwrap();
+  }
+
+  fn clear_versions(&mut self, prefix: &str, name: &str) {
+    self.execute_batch(&format!("DELETE FROM \"{prefix}.{name}.versions\"")).unwrap();
+  }
+}